@@ -0,0 +1,110 @@
+use bytes::Buf;
+use criterion::{Criterion, Throughput};
+use prost::{DecodeMessage, EncodeMessage, Message};
+
+#[derive(Clone, PartialEq, Message)]
+struct Record {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(bytes = "vec", tag = "3")]
+    payload: Vec<u8>,
+    #[prost(int64, repeated, tag = "4")]
+    values: Vec<i64>,
+    #[prost(fixed64, tag = "5")]
+    timestamp: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Batch {
+    #[prost(message, repeated, tag = "1")]
+    records: Vec<Record>,
+}
+
+/// A buffer split into segments of a fixed size, like the buffers of a network stack.
+struct Fragmented<'a> {
+    segments: Vec<&'a [u8]>,
+    index: usize,
+    offset: usize,
+    remaining: usize,
+}
+
+impl<'a> Fragmented<'a> {
+    fn new(buf: &'a [u8], segment_len: usize) -> Fragmented<'a> {
+        Fragmented {
+            segments: buf.chunks(segment_len).collect(),
+            index: 0,
+            offset: 0,
+            remaining: buf.len(),
+        }
+    }
+}
+
+impl Buf for Fragmented<'_> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        match self.segments.get(self.index) {
+            Some(segment) => &segment[self.offset..],
+            None => &[],
+        }
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(cnt <= self.remaining, "advance out of bounds");
+        self.remaining -= cnt;
+        while cnt > 0 {
+            let left = self.segments[self.index].len() - self.offset;
+            if cnt < left {
+                self.offset += cnt;
+                return;
+            }
+            cnt -= left;
+            self.index += 1;
+            self.offset = 0;
+        }
+    }
+}
+
+fn batch() -> Batch {
+    Batch {
+        records: (0..100u64)
+            .map(|i| Record {
+                id: i << 40,
+                name: format!("record-{}", i),
+                payload: vec![i as u8; 200],
+                values: (0..10).map(|v| -(v << (i % 50))).collect(),
+                timestamp: 1_600_000_000_000 + i,
+            })
+            .collect(),
+    }
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+
+    let encoded = batch().encode_to_vec();
+    let mut group = criterion.benchmark_group("fragmented");
+    group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+    group.bench_function("contiguous", |b| {
+        b.iter(|| Batch::decode(&encoded[..]).unwrap())
+    });
+    for &segment_len in &[1500, 64, 7] {
+        group.bench_function(format!("segments/{}", segment_len), |b| {
+            b.iter(|| Batch::decode(Fragmented::new(&encoded, segment_len)).unwrap())
+        });
+    }
+    group.bench_function("chain", |b| {
+        b.iter(|| {
+            let (first, second) = encoded.split_at(encoded.len() / 2);
+            Batch::decode(first.chain(second)).unwrap()
+        })
+    });
+    group.finish();
+
+    criterion.final_summary();
+}
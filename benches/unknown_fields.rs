@@ -0,0 +1,41 @@
+use criterion::{Criterion, Throughput};
+use prost::{DecodeMessage, EncodeMessage, Message, UnknownFieldSet};
+
+/// A batch of messages whose fields are all unknown, as when routing messages by their raw
+/// fields.
+#[derive(Clone, PartialEq, Message)]
+struct Batch {
+    #[prost(message, repeated, tag = "1")]
+    records: Vec<UnknownFieldSet>,
+}
+
+fn batch(fields: u32) -> Batch {
+    Batch {
+        records: (0..100u64)
+            .map(|i| {
+                let mut record = UnknownFieldSet::new();
+                for tag in 1..=fields {
+                    record.push_varint(tag, i << tag);
+                }
+                record
+            })
+            .collect(),
+    }
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+
+    // Most messages have no unknown fields, or a single one.
+    for &fields in &[0, 1, 4, 16] {
+        let encoded = batch(fields).encode_to_vec();
+        let mut group = criterion.benchmark_group(format!("unknown_fields/{}", fields));
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_function("decode", |b| {
+            b.iter(|| Batch::decode(&encoded[..]).unwrap())
+        });
+        group.finish();
+    }
+
+    criterion.final_summary();
+}
@@ -123,7 +123,10 @@ pub struct Service {
 }
 
 /// A service method descriptor.
+///
+/// Fields may be added in minor releases, so methods can only be created by the code generator.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct Method {
     /// The name of the method in Rust style.
     pub name: String,
@@ -145,6 +148,24 @@ pub struct Method {
     pub client_streaming: bool,
     /// Identifies if server streams multiple server messages.
     pub server_streaming: bool,
+    /// The HTTP binding declared by the `google.api.http` method option, if any.
+    pub http_rule: Option<HttpRule>,
+}
+
+/// An HTTP binding of a service method, declared with the `google.api.http` method option.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRule {
+    /// The HTTP verb, or the verb of a `custom` pattern.
+    pub verb: String,
+    /// The URL path template.
+    pub path: String,
+    /// The request field mapped to the HTTP request body, or an empty string if there is none.
+    pub body: String,
+    /// The response field mapped to the HTTP response body, or an empty string for the whole
+    /// response.
+    pub response_body: String,
+    /// Additional bindings of the method. These never have additional bindings of their own.
+    pub additional_bindings: Vec<HttpRule>,
 }
 
 #[cfg(test)]
@@ -0,0 +1,99 @@
+//! Line diffs of generated code.
+
+use diff::Result as Line;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// Returns a unified diff of the lines of `old` and `new`, without the file header.
+pub(crate) fn unified_diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let lines = diff::slice(&old, &new);
+
+    // Indexes of the changed lines.
+    let changes = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Both(..)))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    // Group the changes into hunks, merging changes whose context overlaps.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changes {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, end) in hunks {
+        // Line numbers (1-based) of the hunk in the old and new inputs.
+        let old_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, Line::Right(_)))
+            .count();
+        let new_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, Line::Left(_)))
+            .count();
+        let old_len = lines[start..end]
+            .iter()
+            .filter(|line| !matches!(line, Line::Right(_)))
+            .count();
+        let new_len = lines[start..end]
+            .iter()
+            .filter(|line| !matches!(line, Line::Left(_)))
+            .count();
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_len, new_start, new_len
+        ));
+        for line in &lines[start..end] {
+            match line {
+                Line::Both(line, _) => out.push_str(&format!(" {}\n", line)),
+                Line::Left(line) => out.push_str(&format!("-{}\n", line)),
+                Line::Right(line) => out.push_str(&format!("+{}\n", line)),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified_diff(old, new),
+            "@@ -2,9 +2,10 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n i\n j\n+k\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old = (0..20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = old
+            .replacen("1\n", "one\n", 1)
+            .replacen("18\n", "eighteen\n", 1);
+        assert_eq!(
+            unified_diff(&old, &new),
+            "@@ -1,5 +1,5 @@\n 0\n-1\n+one\n 2\n 3\n 4\n\
+             @@ -16,5 +16,5 @@\n 15\n 16\n 17\n-18\n+eighteen\n 19\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_equal() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+    }
+}
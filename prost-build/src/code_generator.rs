@@ -1,25 +1,32 @@
 use std::ascii;
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::iter;
+use std::path::Path;
 
 use itertools::{Either, Itertools};
 use log::debug;
 use multimap::MultiMap;
+use prost::EncodeMessage;
+use prost_types::descriptor_proto::{ExtensionRange, ReservedRange};
 use prost_types::field_descriptor_proto::{Label, Type};
 use prost_types::source_code_info::Location;
 use prost_types::{
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FieldOptions, FileDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto,
-    SourceCodeInfo,
+    FieldOptions, FileDescriptorProto, FileDescriptorSet, OneofDescriptorProto,
+    ServiceDescriptorProto, SourceCodeInfo,
 };
+use sha2::{Digest, Sha256};
 
-use crate::ast::{Comments, Method, Service};
+use crate::ast::{Comments, HttpRule, Method, Service};
 use crate::extern_paths::ExternPaths;
-use crate::ident::{to_snake, to_upper_camel};
+use crate::ident::{to_snake, to_upper_camel, to_upper_snake};
 use crate::message_graph::MessageGraph;
 use crate::{BytesType, Config, MapType};
 
+/// The Rust type of `string` fields configured with `Config::lossy_string`.
+const LOSSY_STRING_TYPE: &str = "::prost::LossyString";
+
 #[derive(PartialEq)]
 enum Syntax {
     Proto2,
@@ -33,6 +40,7 @@ pub struct CodeGenerator<'a> {
     syntax: Syntax,
     message_graph: &'a MessageGraph,
     extern_paths: &'a ExternPaths,
+    http_rules: &'a HashMap<String, HttpRule>,
     depth: u8,
     path: Vec<i32>,
     buf: &'a mut String,
@@ -49,9 +57,14 @@ impl<'a> CodeGenerator<'a> {
         config: &mut Config,
         message_graph: &MessageGraph,
         extern_paths: &ExternPaths,
+        http_rules: &HashMap<String, HttpRule>,
         file: FileDescriptorProto,
         buf: &mut String,
     ) {
+        if config.schema_hash {
+            append_schema_hash(&file, buf);
+        }
+
         let mut source_info = file
             .source_code_info
             .expect("no source code info in request");
@@ -76,6 +89,7 @@ impl<'a> CodeGenerator<'a> {
             syntax,
             message_graph,
             extern_paths,
+            http_rules,
             depth: 0,
             path: Vec::new(),
             buf,
@@ -103,7 +117,7 @@ impl<'a> CodeGenerator<'a> {
         }
         code_gen.path.pop();
 
-        if code_gen.config.service_generator.is_some() {
+        if code_gen.config.service_generator.is_some() || code_gen.config.http_bindings {
             code_gen.path.push(6);
             for (idx, service) in file.service.into_iter().enumerate() {
                 code_gen.path.push(idx as i32);
@@ -184,18 +198,29 @@ impl<'a> CodeGenerator<'a> {
         self.append_doc(&fq_message_name, None);
         self.append_type_attributes(&fq_message_name);
         self.push_indent();
-        self.buf
-            .push_str("#[derive(Clone, PartialEq, ::prost::Message)]\n");
+        if self.config.reflect.get_first(&fq_message_name).is_some() {
+            self.buf.push_str(
+                "#[derive(Clone, PartialEq, ::prost::Message, ::prost::ReflectMessage)]\n",
+            );
+        } else {
+            self.buf
+                .push_str("#[derive(Clone, PartialEq, ::prost::Message)]\n");
+        }
         self.push_indent();
         self.buf.push_str("pub struct ");
         self.buf.push_str(&to_upper_camel(&message_name));
         self.buf.push_str(" {\n");
 
+        // The name, Rust type and label of each field, for the constructor.
+        let mut struct_fields = Vec::new();
+
         self.depth += 1;
         self.path.push(2);
         for (field, idx) in fields {
             self.path.push(idx as i32);
-            match field
+            let name = field.name().to_string();
+            let required = field.label() == Label::Required;
+            let field_type = match field
                 .type_name
                 .as_ref()
                 .and_then(|type_name| map_types.get(type_name))
@@ -204,7 +229,8 @@ impl<'a> CodeGenerator<'a> {
                     self.append_map_field(&fq_message_name, field, key, value)
                 }
                 None => self.append_field(&fq_message_name, field),
-            }
+            };
+            struct_fields.push((name, field_type, required));
             self.path.pop();
         }
         self.path.pop();
@@ -219,15 +245,59 @@ impl<'a> CodeGenerator<'a> {
             };
 
             self.path.push(idx);
-            self.append_oneof_field(&message_name, &fq_message_name, oneof, fields);
+            let field_type =
+                self.append_oneof_field(&message_name, &fq_message_name, oneof, fields);
+            struct_fields.push((oneof.name().to_string(), field_type, false));
             self.path.pop();
         }
         self.path.pop();
 
+        if self
+            .config
+            .cached_size
+            .get_first(&fq_message_name)
+            .is_some()
+        {
+            self.push_indent();
+            self.buf.push_str(
+                "#[prost(cached_size)]
+",
+            );
+            self.push_indent();
+            self.buf.push_str(
+                "pub _cached_size: ::prost::CachedSize,
+",
+            );
+            struct_fields.push((
+                "_cached_size".to_string(),
+                "::prost::CachedSize".to_string(),
+                false,
+            ));
+        }
+
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n");
 
+        if let Some(constructor_fields) = self.config.constructors.get_first(&fq_message_name) {
+            let constructor_fields = constructor_fields.clone();
+            self.append_constructor(
+                &message_name,
+                &fq_message_name,
+                &constructor_fields,
+                &struct_fields,
+            );
+        }
+
+        if self.config.message_ranges {
+            self.append_message_ranges(
+                &message_name,
+                &message.reserved_range,
+                &message.reserved_name,
+                &message.extension_range,
+            );
+        }
+
         if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
             self.push_mod(&message_name);
             self.path.push(3);
@@ -269,6 +339,150 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
+    fn append_message_ranges(
+        &mut self,
+        message_name: &str,
+        reserved_ranges: &[ReservedRange],
+        reserved_names: &[String],
+        extension_ranges: &[ExtensionRange],
+    ) {
+        let reserved_ranges = reserved_ranges
+            .iter()
+            .map(|range| format!("{}..{}", range.start(), range.end()))
+            .join(", ");
+        let reserved_names = reserved_names
+            .iter()
+            .map(|name| format!("{:?}", name))
+            .join(", ");
+        let extension_ranges = extension_ranges
+            .iter()
+            .map(|range| format!("{}..{}", range.start(), range.end()))
+            .join(", ");
+
+        self.push_indent();
+        self.buf.push_str("impl ");
+        self.buf.push_str(&to_upper_camel(message_name));
+        self.buf.push_str(" {\n");
+        self.depth += 1;
+
+        self.push_indent();
+        self.buf
+            .push_str("/// The reserved field numbers of the message, as half-open ranges.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const RESERVED_RANGES: &'static [::core::ops::Range<i32>] = &[{}];\n",
+            reserved_ranges
+        ));
+        self.push_indent();
+        self.buf
+            .push_str("/// The reserved field names of the message.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const RESERVED_NAMES: &'static [&'static str] = &[{}];\n",
+            reserved_names
+        ));
+        self.push_indent();
+        self.buf.push_str(
+            "/// The field numbers of the message reserved for extensions, as half-open ranges.\n",
+        );
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const EXTENSION_RANGES: &'static [::core::ops::Range<i32>] = &[{}];\n",
+            extension_ranges
+        ));
+
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
+    fn append_constructor(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        constructor_fields: &[String],
+        struct_fields: &[(String, String, bool)],
+    ) {
+        let params: Vec<&(String, String, bool)> = if constructor_fields.is_empty() {
+            struct_fields
+                .iter()
+                .filter(|&&(_, _, required)| required)
+                .collect()
+        } else {
+            constructor_fields
+                .iter()
+                .map(|name| {
+                    struct_fields
+                        .iter()
+                        .find(|&&(ref field_name, _, _)| field_name == name)
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "constructor field {} is not a field of message {}",
+                                name, fq_message_name
+                            )
+                        })
+                })
+                .collect()
+        };
+
+        self.push_indent();
+        self.buf.push_str("impl ");
+        self.buf.push_str(&to_upper_camel(message_name));
+        self.buf.push_str(" {\n");
+        self.depth += 1;
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "/// Creates a `{}` from {}, with the other fields set to their default values.\n",
+            to_upper_camel(message_name),
+            if params.is_empty() {
+                "no fields".to_string()
+            } else {
+                params
+                    .iter()
+                    .map(|&&(ref name, _, _)| format!("`{}`", to_snake(name)))
+                    .join(", ")
+            }
+        ));
+        self.push_indent();
+        self.buf.push_str("#[allow(clippy::too_many_arguments)]\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub fn new({}) -> Self {{\n",
+            params
+                .iter()
+                .map(|&&(ref name, ref ty, _)| format!(
+                    "{}: impl ::core::convert::Into<{}>",
+                    to_snake(name),
+                    ty
+                ))
+                .join(", ")
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str("Self {\n");
+        self.depth += 1;
+        for &&(ref name, _, _) in &params {
+            self.push_indent();
+            self.buf
+                .push_str(&format!("{}: {}.into(),\n", to_snake(name), to_snake(name)));
+        }
+        if params.len() < struct_fields.len() {
+            self.push_indent();
+            self.buf.push_str("..::core::default::Default::default()\n");
+        }
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
     fn append_field_attributes(&mut self, fq_message_name: &str, field_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self
@@ -282,12 +496,18 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
-    fn append_field(&mut self, fq_message_name: &str, field: FieldDescriptorProto) {
+    /// Appends a field declaration, returning the Rust type of the field.
+    fn append_field(&mut self, fq_message_name: &str, field: FieldDescriptorProto) -> String {
         let type_ = field.r#type();
         let repeated = field.label == Some(Label::Repeated as i32);
         let deprecated = self.deprecated(&field);
         let optional = self.optional(&field);
-        let ty = self.resolve_type(&field, fq_message_name);
+        let lossy_string = self.lossy_string(fq_message_name, &field);
+        let ty = if lossy_string {
+            LOSSY_STRING_TYPE.to_string()
+        } else {
+            self.resolve_type(&field, fq_message_name)
+        };
 
         let boxed = !repeated
             && (type_ == Type::Message || type_ == Type::Group)
@@ -311,18 +531,18 @@ impl<'a> CodeGenerator<'a> {
 
         self.push_indent();
         self.buf.push_str("#[prost(");
-        let type_tag = self.field_type_tag(&field);
+        let type_tag = if lossy_string {
+            Cow::Owned(format!("bytes=\"custom({})\"", LOSSY_STRING_TYPE))
+        } else {
+            self.field_type_tag(&field)
+        };
         self.buf.push_str(&type_tag);
 
         if type_ == Type::Bytes {
-            let bytes_type = self
-                .config
-                .bytes_type
-                .get_first_field(fq_message_name, field.name())
-                .copied()
-                .unwrap_or_default();
+            let bytes_type = self.bytes_type(fq_message_name, field.name());
             self.buf
                 .push_str(&format!("={:?}", bytes_type.annotation()));
+            self.append_bytes_codec(fq_message_name, field.name());
         }
 
         match field.label() {
@@ -348,6 +568,15 @@ impl<'a> CodeGenerator<'a> {
         if boxed {
             self.buf.push_str(", boxed");
         }
+        if type_ == Type::Enum
+            && self
+                .config
+                .open_enums
+                .get_first_field(fq_message_name, field.name())
+                .is_some()
+        {
+            self.buf.push_str(", open");
+        }
         self.buf.push_str(", tag=\"");
         self.buf.push_str(&field.number().to_string());
 
@@ -361,6 +590,14 @@ impl<'a> CodeGenerator<'a> {
                     );
                 }
                 self.buf.push_str("\\\"");
+            } else if lossy_string {
+                self.buf.push_str("b\\\"");
+                for b in default.bytes() {
+                    self.buf.extend(
+                        ascii::escape_default(b).flat_map(|c| (c as char).escape_default()),
+                    );
+                }
+                self.buf.push_str("\\\"");
             } else if type_ == Type::Enum {
                 let enum_value = to_upper_camel(default);
                 let stripped_prefix = if self.config.strip_enum_prefix {
@@ -389,31 +626,35 @@ impl<'a> CodeGenerator<'a> {
         self.buf.push_str("pub ");
         self.buf.push_str(&to_snake(field.name()));
         self.buf.push_str(": ");
+        let mut field_type = String::new();
         if repeated {
-            self.buf.push_str("::prost::alloc::vec::Vec<");
+            field_type.push_str("::prost::alloc::vec::Vec<");
         } else if optional {
-            self.buf.push_str("::core::option::Option<");
+            field_type.push_str("::core::option::Option<");
         }
         if boxed {
-            self.buf.push_str("::prost::alloc::boxed::Box<");
+            field_type.push_str("::prost::alloc::boxed::Box<");
         }
-        self.buf.push_str(&ty);
+        field_type.push_str(&ty);
         if boxed {
-            self.buf.push('>');
+            field_type.push('>');
         }
         if repeated || optional {
-            self.buf.push('>');
+            field_type.push('>');
         }
+        self.buf.push_str(&field_type);
         self.buf.push_str(",\n");
+        field_type
     }
 
+    /// Appends a map field declaration, returning the Rust type of the field.
     fn append_map_field(
         &mut self,
         fq_message_name: &str,
         field: FieldDescriptorProto,
         key: &FieldDescriptorProto,
         value: &FieldDescriptorProto,
-    ) {
+    ) -> String {
         let key_ty = self.resolve_type(key, fq_message_name);
         let value_ty = self.resolve_type(value, fq_message_name);
 
@@ -445,22 +686,23 @@ impl<'a> CodeGenerator<'a> {
         ));
         self.append_field_attributes(fq_message_name, field.name());
         self.push_indent();
+        let field_type = format!("{}<{}, {}>", map_type.rust_type(), key_ty, value_ty);
         self.buf.push_str(&format!(
-            "pub {}: {}<{}, {}>,\n",
+            "pub {}: {},\n",
             to_snake(field.name()),
-            map_type.rust_type(),
-            key_ty,
-            value_ty
+            field_type
         ));
+        field_type
     }
 
+    /// Appends a oneof field declaration, returning the Rust type of the field.
     fn append_oneof_field(
         &mut self,
         message_name: &str,
         fq_message_name: &str,
         oneof: &OneofDescriptorProto,
         fields: &[(FieldDescriptorProto, usize)],
-    ) {
+    ) -> String {
         let name = format!(
             "{}::{}",
             to_snake(message_name),
@@ -478,11 +720,13 @@ impl<'a> CodeGenerator<'a> {
         ));
         self.append_field_attributes(fq_message_name, oneof.name());
         self.push_indent();
+        let field_type = format!("::core::option::Option<{}>", name);
         self.buf.push_str(&format!(
-            "pub {}: ::core::option::Option<{}>,\n",
+            "pub {}: {},\n",
             to_snake(oneof.name()),
-            name
+            field_type
         ));
+        field_type
     }
 
     fn append_oneof(
@@ -501,8 +745,13 @@ impl<'a> CodeGenerator<'a> {
         let oneof_name = format!("{}.{}", fq_message_name, oneof.name());
         self.append_type_attributes(&oneof_name);
         self.push_indent();
-        self.buf
-            .push_str("#[derive(Clone, PartialEq, ::prost::Oneof)]\n");
+        if self.config.reflect.get_first(fq_message_name).is_some() {
+            self.buf
+                .push_str("#[derive(Clone, PartialEq, ::prost::Oneof, ::prost::ReflectOneof)]\n");
+        } else {
+            self.buf
+                .push_str("#[derive(Clone, PartialEq, ::prost::Oneof)]\n");
+        }
         self.push_indent();
         self.buf.push_str("pub enum ");
         self.buf.push_str(&to_upper_camel(oneof.name()));
@@ -518,16 +767,26 @@ impl<'a> CodeGenerator<'a> {
             self.path.pop();
 
             self.push_indent();
-            let ty_tag = self.field_type_tag(&field);
-            self.buf.push_str(&format!(
-                "#[prost({}, tag=\"{}\")]\n",
-                ty_tag,
-                field.number()
-            ));
+            let lossy_string = self.lossy_string(fq_message_name, &field);
+            let ty_tag = if lossy_string {
+                Cow::Owned(format!("bytes=\"custom({})\"", LOSSY_STRING_TYPE))
+            } else {
+                self.field_type_tag(&field)
+            };
+            self.buf.push_str(&format!("#[prost({}", ty_tag));
+            if type_ == Type::Bytes {
+                self.append_bytes_codec(fq_message_name, field.name());
+            }
+            self.buf
+                .push_str(&format!(", tag=\"{}\")]\n", field.number()));
             self.append_field_attributes(&oneof_name, field.name());
 
             self.push_indent();
-            let ty = self.resolve_type(&field, fq_message_name);
+            let ty = if lossy_string {
+                LOSSY_STRING_TYPE.to_string()
+            } else {
+                self.resolve_type(&field, fq_message_name)
+            };
 
             let boxed = (type_ == Type::Message || type_ == Type::Group)
                 && self
@@ -612,24 +871,33 @@ impl<'a> CodeGenerator<'a> {
         self.buf.push_str(&to_upper_camel(desc.name()));
         self.buf.push_str(" {\n");
 
-        let mut numbers = HashSet::new();
+        let stripped_prefix = if self.config.strip_enum_prefix {
+            Some(to_upper_camel(&enum_name))
+        } else {
+            None
+        };
+        let mut variants = HashMap::<i32, String>::new();
+        let mut aliases = Vec::new();
 
         self.depth += 1;
         self.path.push(2);
         for (idx, value) in enum_values.iter().enumerate() {
-            // Skip duplicate enum values. Protobuf allows this when the
-            // 'allow_alias' option is set.
-            if !numbers.insert(value.number()) {
+            // Duplicate enum values are aliases, which Protobuf allows when the 'allow_alias'
+            // option is set. They become constants of the first variant with the same value.
+            if let Some(variant) = variants.get(&value.number()) {
+                aliases.push((
+                    enum_value_name(value, stripped_prefix.as_deref()),
+                    variant.clone(),
+                ));
                 continue;
             }
+            variants.insert(
+                value.number(),
+                enum_value_name(value, stripped_prefix.as_deref()),
+            );
 
             self.path.push(idx as i32);
-            let stripped_prefix = if self.config.strip_enum_prefix {
-                Some(to_upper_camel(&enum_name))
-            } else {
-                None
-            };
-            self.append_enum_value(&fq_enum_name, value, stripped_prefix);
+            self.append_enum_value(&fq_enum_name, value, stripped_prefix.clone());
             self.path.pop();
         }
         self.path.pop();
@@ -637,6 +905,28 @@ impl<'a> CodeGenerator<'a> {
 
         self.push_indent();
         self.buf.push_str("}\n");
+
+        if !aliases.is_empty() {
+            let enum_ident = to_upper_camel(desc.name());
+            self.push_indent();
+            self.buf.push_str(&format!("impl {} {{\n", enum_ident));
+            for (alias, variant) in aliases {
+                self.push_indent();
+                self.buf.push_str(&format!(
+                    "    /// Alias of [`{0}::{1}`].\n",
+                    enum_ident, variant
+                ));
+                self.push_indent();
+                self.buf.push_str("    #[allow(non_upper_case_globals)]\n");
+                self.push_indent();
+                self.buf.push_str(&format!(
+                    "    pub const {1}: {0} = {0}::{2};\n",
+                    enum_ident, alias, variant
+                ));
+            }
+            self.push_indent();
+            self.buf.push_str("}\n");
+        }
     }
 
     fn append_enum_value(
@@ -648,12 +938,8 @@ impl<'a> CodeGenerator<'a> {
         self.append_doc(fq_enum_name, Some(value.name()));
         self.append_field_attributes(fq_enum_name, &value.name());
         self.push_indent();
-        let name = to_upper_camel(value.name());
-        let name_unprefixed = match prefix_to_strip {
-            Some(prefix) => strip_enum_prefix(&prefix, &name),
-            None => &name,
-        };
-        self.buf.push_str(name_unprefixed);
+        self.buf
+            .push_str(&enum_value_name(value, prefix_to_strip.as_deref()));
         self.buf.push_str(" = ");
         self.buf.push_str(&value.number().to_string());
         self.buf.push_str(",\n");
@@ -664,6 +950,12 @@ impl<'a> CodeGenerator<'a> {
         debug!("  service: {:?}", name);
 
         let comments = Comments::from_location(self.location());
+        let fq_service_name = format!(
+            "{}{}.{}",
+            if self.package.is_empty() { "" } else { "." },
+            self.package,
+            name
+        );
 
         self.path.push(2);
         let methods = service
@@ -683,6 +975,10 @@ impl<'a> CodeGenerator<'a> {
                 let output_type = self.resolve_ident(&output_proto_type);
                 let client_streaming = method.client_streaming();
                 let server_streaming = method.server_streaming();
+                let http_rule = self
+                    .http_rules
+                    .get(&format!("{}.{}", fq_service_name, name))
+                    .cloned();
 
                 Method {
                     name: to_snake(&name),
@@ -695,6 +991,7 @@ impl<'a> CodeGenerator<'a> {
                     options: method.options.unwrap_or_default(),
                     client_streaming,
                     server_streaming,
+                    http_rule,
                 }
             })
             .collect();
@@ -709,11 +1006,112 @@ impl<'a> CodeGenerator<'a> {
             options: service.options.unwrap_or_default(),
         };
 
+        if self.config.http_bindings {
+            self.append_http_bindings(&service);
+        }
+
         if let Some(service_generator) = self.config.service_generator.as_mut() {
             service_generator.generate(service, &mut self.buf)
         }
     }
 
+    fn append_http_bindings(&mut self, service: &Service) {
+        let methods = service
+            .methods
+            .iter()
+            .filter_map(|method| method.http_rule.as_ref().map(|rule| (method, rule)))
+            .collect::<Vec<_>>();
+        if methods.is_empty() {
+            return;
+        }
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "/// HTTP bindings of the `{}` service methods, declared by `google.api.http` options.\n",
+            service.proto_name
+        ));
+        // The module of a message of the package, holding its nested types, may take the name.
+        let package_prefix = if self.package.is_empty() {
+            ".".to_string()
+        } else {
+            format!(".{}.", self.package)
+        };
+        let message_modules = self
+            .message_graph
+            .message_names()
+            .filter_map(|name| name.strip_prefix(package_prefix.as_str()))
+            .filter(|name| !name.contains('.'))
+            .map(|name| to_snake(name).trim_start_matches("r#").to_string())
+            .collect::<Vec<_>>();
+        let mut module = format!(
+            "{}_http",
+            to_snake(&service.proto_name).trim_start_matches("r#")
+        );
+        while message_modules.contains(&module) {
+            module.push('_');
+        }
+
+        self.push_indent();
+        self.buf.push_str(&format!("pub mod {} {{\n", module));
+        self.depth += 1;
+
+        for (method, rule) in &methods {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "/// HTTP bindings of the `{}` method.\n",
+                method.proto_name
+            ));
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "pub const {}: &[::prost_types::http::HttpBinding] = &[\n",
+                method.name.trim_start_matches("r#").to_ascii_uppercase()
+            ));
+            self.depth += 1;
+            for binding in iter::once(*rule).chain(&rule.additional_bindings) {
+                self.push_indent();
+                self.buf.push_str(&format!(
+                    "::prost_types::http::HttpBinding {{ verb: {:?}, path: {:?}, body: {:?}, response_body: {:?} }},\n",
+                    binding.verb, binding.path, binding.body, binding.response_body
+                ));
+            }
+            self.depth -= 1;
+            self.push_indent();
+            self.buf.push_str("];\n");
+        }
+
+        // A function, so that its name can't be taken by the constant of a method.
+        self.push_indent();
+        self.buf.push_str(
+            "/// Returns the HTTP bindings of each method, keyed by the Protobuf method name.\n",
+        );
+        self.push_indent();
+        self.buf.push_str(
+            "pub fn bindings() -> &'static [(&'static str, &'static [::prost_types::http::HttpBinding])] {\n",
+        );
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str("&[\n");
+        self.depth += 1;
+        for (method, _) in &methods {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "({:?}, {}),\n",
+                method.proto_name,
+                method.name.trim_start_matches("r#").to_ascii_uppercase()
+            ));
+        }
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("]\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
     fn push_indent(&mut self) {
         push_indent(&mut self.buf, self.depth);
     }
@@ -756,17 +1154,48 @@ impl<'a> CodeGenerator<'a> {
             Type::Bool => String::from("bool"),
             Type::String => String::from("::prost::alloc::string::String"),
             Type::Bytes => self
-                .config
-                .bytes_type
-                .get_first_field(fq_message_name, field.name())
-                .copied()
-                .unwrap_or_default()
+                .bytes_type(fq_message_name, field.name())
                 .rust_type()
                 .to_owned(),
             Type::Group | Type::Message => self.resolve_ident(field.type_name()),
         }
     }
 
+    /// Appends the `codec` attribute of a `bytes` field, if one is configured.
+    fn append_bytes_codec(&mut self, fq_message_name: &str, field_name: &str) {
+        if let Some(codec) = self
+            .config
+            .bytes_codec
+            .get_first_field(fq_message_name, field_name)
+        {
+            self.buf.push_str(&format!(", codec={:?}", codec));
+        }
+    }
+
+    /// Returns `true` if the field is a `string` field configured to use `LossyString`.
+    fn lossy_string(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        field.r#type() == Type::String
+            && self
+                .config
+                .lossy_string
+                .get_first_field(fq_message_name, field.name())
+                .is_some()
+    }
+
+    /// Returns the Rust type of a `bytes` field.
+    fn bytes_type(&self, fq_message_name: &str, field_name: &str) -> BytesType {
+        self.config
+            .custom_bytes_type
+            .get_first_field(fq_message_name, field_name)
+            .or_else(|| {
+                self.config
+                    .bytes_type
+                    .get_first_field(fq_message_name, field_name)
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
+
     fn resolve_ident(&self, pb_ident: &str) -> String {
         // protoc should always give fully qualified identifiers.
         assert_eq!(".", &pb_ident[..1]);
@@ -861,6 +1290,186 @@ impl<'a> CodeGenerator<'a> {
     }
 }
 
+/// Returns the Rust name of an enum value, with the name of its enum stripped from its start.
+fn enum_value_name(value: &EnumValueDescriptorProto, prefix_to_strip: Option<&str>) -> String {
+    let name = to_upper_camel(value.name());
+    match prefix_to_strip {
+        Some(prefix) => strip_enum_prefix(prefix, &name).to_string(),
+        None => name,
+    }
+}
+
+/// Appends a constant holding the SHA-256 hash of a file descriptor.
+///
+/// Source code info is left out of the hash, so that only changes to the schema itself, and not
+/// to comments or formatting, change the hash.
+fn append_schema_hash(file: &FileDescriptorProto, buf: &mut String) {
+    let mut schema = file.clone();
+    schema.source_code_info = None;
+    let hash = Sha256::digest(&schema.encode_to_vec());
+
+    let stem = Path::new(file.name())
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    buf.push_str(&format!(
+        "/// SHA-256 hash of the `{}` file descriptor, excluding source code info.\n",
+        file.name()
+    ));
+    buf.push_str(&format!(
+        "pub const {}_SCHEMA_HASH: [u8; 32] = [{}];\n",
+        to_upper_snake(stem),
+        hash.iter().map(|b| format!("0x{:02x}", b)).join(", ")
+    ));
+}
+
+/// Appends the `descriptors` module of a package, embedding the descriptors of its files.
+pub fn append_descriptors(mut files: Vec<FileDescriptorProto>, buf: &mut String) {
+    let mut messages = Vec::new();
+    let mut services = Vec::new();
+    for (file_index, file) in files.iter_mut().enumerate() {
+        file.source_code_info = None;
+        let package = file.package();
+        for (index, message) in file.message_type.iter().enumerate() {
+            collect_messages(package, message, vec![file_index, index], &mut messages);
+        }
+        for (index, service) in file.service.iter().enumerate() {
+            services.push((qualified_name(package, service.name()), file_index, index));
+        }
+    }
+    let file_descriptor_set = FileDescriptorSet { file: files }.encode_to_vec();
+
+    buf.push_str("/// Descriptors of the messages and services of this package.\n");
+    buf.push_str("pub mod descriptors {\n");
+
+    buf.push_str(
+        "    /// The encoded `FileDescriptorSet` of the `.proto` files of this package, without \
+         source code info.\n",
+    );
+    buf.push_str("    pub const FILE_DESCRIPTOR_SET: &[u8] = &[\n");
+    for chunk in file_descriptor_set.chunks(16) {
+        buf.push_str("        ");
+        buf.push_str(&chunk.iter().map(|b| format!("0x{:02x},", b)).join(" "));
+        buf.push('\n');
+    }
+    buf.push_str("    ];\n");
+
+    buf.push_str(
+        "    /// The fully qualified names of the messages of this package, including nested \
+         messages.\n",
+    );
+    buf.push_str("    pub const MESSAGE_FULL_NAMES: &[&str] = &[\n");
+    for (name, _) in &messages {
+        buf.push_str(&format!("        \"{}\",\n", name));
+    }
+    buf.push_str("    ];\n");
+
+    buf.push_str("    /// The fully qualified names of the services of this package.\n");
+    buf.push_str("    pub const SERVICE_NAMES: &[&str] = &[\n");
+    for (name, _, _) in &services {
+        buf.push_str(&format!("        \"{}\",\n", name));
+    }
+    buf.push_str("    ];\n");
+
+    buf.push_str(
+        "    /// Decodes the `FileDescriptorSet` of the `.proto` files of this package.\n",
+    );
+    buf.push_str("    pub fn file_descriptor_set() -> ::prost_types::FileDescriptorSet {\n");
+    buf.push_str("        ::prost::DecodeMessage::decode(FILE_DESCRIPTOR_SET)\n");
+    buf.push_str("            .expect(\"invalid file descriptor set\")\n");
+    buf.push_str("    }\n");
+
+    buf.push_str(
+        "    /// Returns the descriptor of a message of this package, by fully qualified name.\n",
+    );
+    buf.push_str(
+        "    pub fn message_descriptor(full_name: &str) -> \
+         ::core::option::Option<::prost_types::DescriptorProto> {\n",
+    );
+    if messages.is_empty() {
+        buf.push_str("        let _ = full_name;\n");
+        buf.push_str("        ::core::option::Option::None\n");
+    } else {
+        buf.push_str("        let path: &[usize] = match full_name {\n");
+        for (name, path) in &messages {
+            buf.push_str(&format!(
+                "            \"{}\" => &[{}],\n",
+                name,
+                path.iter().join(", ")
+            ));
+        }
+        buf.push_str("            _ => return ::core::option::Option::None,\n");
+        buf.push_str("        };\n");
+        buf.push_str("        let mut file = file_descriptor_set().file.swap_remove(path[0]);\n");
+        buf.push_str("        let mut message = file.message_type.swap_remove(path[1]);\n");
+        buf.push_str("        for &index in &path[2..] {\n");
+        buf.push_str("            message = message.nested_type.swap_remove(index);\n");
+        buf.push_str("        }\n");
+        buf.push_str("        ::core::option::Option::Some(message)\n");
+    }
+    buf.push_str("    }\n");
+
+    buf.push_str(
+        "    /// Returns the descriptor of a service of this package, by fully qualified name.\n",
+    );
+    buf.push_str(
+        "    pub fn service_descriptor(name: &str) -> \
+         ::core::option::Option<::prost_types::ServiceDescriptorProto> {\n",
+    );
+    if services.is_empty() {
+        buf.push_str("        let _ = name;\n");
+        buf.push_str("        ::core::option::Option::None\n");
+    } else {
+        buf.push_str("        let (file, index) = match name {\n");
+        for (name, file, index) in &services {
+            buf.push_str(&format!(
+                "            \"{}\" => ({}, {}),\n",
+                name, file, index
+            ));
+        }
+        buf.push_str("            _ => return ::core::option::Option::None,\n");
+        buf.push_str("        };\n");
+        buf.push_str("        let mut file = file_descriptor_set().file.swap_remove(file);\n");
+        buf.push_str("        ::core::option::Option::Some(file.service.swap_remove(index))\n");
+    }
+    buf.push_str("    }\n");
+
+    buf.push_str("}\n");
+}
+
+/// Collects the fully qualified names of a message and its nested messages, except map entries,
+/// with their index paths in the file descriptor set.
+fn collect_messages(
+    scope: &str,
+    message: &DescriptorProto,
+    path: Vec<usize>,
+    messages: &mut Vec<(String, Vec<usize>)>,
+) {
+    if message
+        .options
+        .as_ref()
+        .map_or(false, |options| options.map_entry())
+    {
+        return;
+    }
+
+    let name = qualified_name(scope, message.name());
+    messages.push((name.clone(), path.clone()));
+    for (index, nested) in message.nested_type.iter().enumerate() {
+        let mut nested_path = path.clone();
+        nested_path.push(index);
+        collect_messages(&name, nested, nested_path, messages);
+    }
+}
+
+fn qualified_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
 /// Returns `true` if the repeated field type can be packed.
 fn can_pack(field: &FieldDescriptorProto) -> bool {
     matches!(
@@ -1033,18 +1642,20 @@ impl MapType {
 
 impl BytesType {
     /// The `prost-derive` annotation type corresponding to the bytes type.
-    fn annotation(&self) -> &'static str {
+    fn annotation(&self) -> Cow<'_, str> {
         match self {
-            BytesType::Vec => "vec",
-            BytesType::Bytes => "bytes",
+            BytesType::Vec => Cow::Borrowed("vec"),
+            BytesType::Bytes => Cow::Borrowed("bytes"),
+            BytesType::Custom(rust_type) => Cow::Owned(format!("custom({})", rust_type)),
         }
     }
 
     /// The fully-qualified Rust type corresponding to the bytes type.
-    fn rust_type(&self) -> &'static str {
+    fn rust_type(&self) -> &str {
         match self {
             BytesType::Vec => "::prost::alloc::vec::Vec<u8>",
             BytesType::Bytes => "::prost::bytes::Bytes",
+            BytesType::Custom(rust_type) => rust_type,
         }
     }
 }
@@ -1053,6 +1664,32 @@ impl BytesType {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_schema_hash() {
+        let file = FileDescriptorProto {
+            name: Some("foo/user_service.proto".to_string()),
+            package: Some("foo".to_string()),
+            ..FileDescriptorProto::default()
+        };
+        let mut buf = String::new();
+        append_schema_hash(&file, &mut buf);
+        assert!(buf.contains("pub const USER_SERVICE_SCHEMA_HASH: [u8; 32] = [0x"));
+
+        // Source code info doesn't change the hash.
+        let mut commented = file.clone();
+        commented.source_code_info = Some(SourceCodeInfo::default());
+        let mut commented_buf = String::new();
+        append_schema_hash(&commented, &mut commented_buf);
+        assert_eq!(buf, commented_buf);
+
+        // The schema does.
+        let mut changed = file;
+        changed.package = Some("bar".to_string());
+        let mut changed_buf = String::new();
+        append_schema_hash(&changed, &mut changed_buf);
+        assert_ne!(buf, changed_buf);
+    }
+
     #[test]
     fn test_unescape_c_escape_string() {
         assert_eq!(
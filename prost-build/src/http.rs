@@ -0,0 +1,144 @@
+//! Extraction of `google.api.http` method options.
+//!
+//! `prost-types` does not retain extension fields, so the `google.api.http` option is lost when the
+//! `FileDescriptorSet` is decoded. Instead, the rules are read from the encoded descriptor set with
+//! partial views of the descriptor messages, which only declare the fields leading to the option.
+
+use std::collections::HashMap;
+
+use prost::{DecodeError, DecodeMessage, Message};
+
+use crate::ast::HttpRule;
+
+#[derive(Clone, PartialEq, Message)]
+struct FileDescriptorSetView {
+    #[prost(message, repeated, tag = "1")]
+    file: Vec<FileDescriptorProtoView>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FileDescriptorProtoView {
+    #[prost(string, optional, tag = "2")]
+    package: Option<String>,
+    #[prost(message, repeated, tag = "6")]
+    service: Vec<ServiceDescriptorProtoView>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ServiceDescriptorProtoView {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(message, repeated, tag = "2")]
+    method: Vec<MethodDescriptorProtoView>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct MethodDescriptorProtoView {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(message, optional, tag = "4")]
+    options: Option<MethodOptionsView>,
+}
+
+/// `google.protobuf.MethodOptions`, with the `google.api.http` extension field (72295728).
+#[derive(Clone, PartialEq, Message)]
+struct MethodOptionsView {
+    #[prost(message, optional, tag = "72295728")]
+    http: Option<HttpRuleProto>,
+}
+
+/// `google.api.HttpRule`
+#[derive(Clone, PartialEq, Message)]
+struct HttpRuleProto {
+    #[prost(oneof = "Pattern", tags = "2, 3, 4, 5, 6, 8")]
+    pattern: Option<Pattern>,
+    #[prost(string, tag = "7")]
+    body: String,
+    #[prost(string, tag = "12")]
+    response_body: String,
+    #[prost(message, repeated, tag = "11")]
+    additional_bindings: Vec<HttpRuleProto>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum Pattern {
+    #[prost(string, tag = "2")]
+    Get(String),
+    #[prost(string, tag = "3")]
+    Put(String),
+    #[prost(string, tag = "4")]
+    Post(String),
+    #[prost(string, tag = "5")]
+    Delete(String),
+    #[prost(string, tag = "6")]
+    Patch(String),
+    #[prost(message, tag = "8")]
+    Custom(CustomHttpPattern),
+}
+
+/// `google.api.CustomHttpPattern`
+#[derive(Clone, PartialEq, Message)]
+struct CustomHttpPattern {
+    #[prost(string, tag = "1")]
+    kind: String,
+    #[prost(string, tag = "2")]
+    path: String,
+}
+
+impl HttpRuleProto {
+    /// Converts the rule, returning `None` if it doesn't declare a pattern.
+    fn into_rule(self) -> Option<HttpRule> {
+        let (verb, path) = match self.pattern? {
+            Pattern::Get(path) => ("GET".to_string(), path),
+            Pattern::Put(path) => ("PUT".to_string(), path),
+            Pattern::Post(path) => ("POST".to_string(), path),
+            Pattern::Delete(path) => ("DELETE".to_string(), path),
+            Pattern::Patch(path) => ("PATCH".to_string(), path),
+            Pattern::Custom(custom) => (custom.kind, custom.path),
+        };
+        Some(HttpRule {
+            verb,
+            path,
+            body: self.body,
+            response_body: self.response_body,
+            additional_bindings: self
+                .additional_bindings
+                .into_iter()
+                .filter_map(HttpRuleProto::into_rule)
+                .collect(),
+        })
+    }
+}
+
+/// Returns the HTTP rules declared in an encoded `FileDescriptorSet`, keyed by the fully qualified
+/// method name (e.g. `.my.package.MyService.MyMethod`).
+pub(crate) fn http_rules(
+    file_descriptor_set: &[u8],
+) -> Result<HashMap<String, HttpRule>, DecodeError> {
+    let file_descriptor_set = FileDescriptorSetView::decode(file_descriptor_set)?;
+
+    let mut rules = HashMap::new();
+    for file in file_descriptor_set.file {
+        let package = file.package.unwrap_or_default();
+        for service in file.service {
+            let service_name = service.name.unwrap_or_default();
+            for method in service.method {
+                let rule = method
+                    .options
+                    .and_then(|options| options.http)
+                    .and_then(HttpRuleProto::into_rule);
+                if let Some(rule) = rule {
+                    let fq_method_name = format!(
+                        "{}{}.{}.{}",
+                        if package.is_empty() { "" } else { "." },
+                        package,
+                        service_name,
+                        method.name.unwrap_or_default(),
+                    );
+                    rules.insert(fq_method_name, rule);
+                }
+            }
+        }
+    }
+    Ok(rules)
+}
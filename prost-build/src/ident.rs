@@ -1,6 +1,6 @@
 //! Utility functions for working with identifiers.
 
-use heck::{CamelCase, SnakeCase};
+use heck::{CamelCase, ShoutySnakeCase, SnakeCase};
 
 /// Converts a `camelCase` or `SCREAMING_SNAKE_CASE` identifier to a `lower_snake` case Rust field
 /// identifier.
@@ -29,6 +29,11 @@ pub fn to_snake(s: &str) -> String {
     ident
 }
 
+/// Converts an identifier to an `UPPER_SNAKE` case Rust constant identifier.
+pub fn to_upper_snake(s: &str) -> String {
+    s.to_shouty_snake_case()
+}
+
 /// Converts a `snake_case` identifier to an `UpperCamel` case Rust type identifier.
 pub fn to_upper_camel(s: &str) -> String {
     let mut ident = s.to_camel_case();
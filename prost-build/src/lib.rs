@@ -110,13 +110,16 @@
 //! environment.
 
 mod ast;
+mod code_diff;
 mod code_generator;
 mod extern_paths;
+mod http;
 mod ident;
 mod message_graph;
 mod path;
+mod workspace;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::default;
 use std::env;
 use std::ffi::{OsStr, OsString};
@@ -127,15 +130,16 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use log::trace;
-use prost::Message;
+use prost::DecodeMessage;
 use prost_types::{FileDescriptorProto, FileDescriptorSet};
 
-pub use crate::ast::{Comments, Method, Service};
-use crate::code_generator::CodeGenerator;
+pub use crate::ast::{Comments, HttpRule, Method, Service};
+use crate::code_generator::{append_descriptors, CodeGenerator};
 use crate::extern_paths::ExternPaths;
 use crate::ident::to_snake;
 use crate::message_graph::MessageGraph;
 use crate::path::PathMap;
+pub use crate::workspace::Workspace;
 
 type Module = Vec<String>;
 
@@ -202,12 +206,14 @@ impl Default for MapType {
 
 /// The bytes collection type to output for Protobuf `bytes` fields.
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 enum BytesType {
     /// The [`alloc::collections::Vec::<u8>`] type.
     Vec,
     /// The [`bytes::Bytes`] type.
     Bytes,
+    /// A user provided type implementing [`prost::BytesAdapter`].
+    Custom(String),
 }
 
 impl Default for BytesType {
@@ -222,8 +228,19 @@ impl Default for BytesType {
 pub struct Config {
     file_descriptor_set_path: Option<PathBuf>,
     service_generator: Option<Box<dyn ServiceGenerator>>,
+    http_bindings: bool,
+    message_ranges: bool,
+    constructors: PathMap<Vec<String>>,
+    schema_hash: bool,
+    descriptors: bool,
     map_type: PathMap<MapType>,
     bytes_type: PathMap<BytesType>,
+    custom_bytes_type: PathMap<BytesType>,
+    lossy_string: PathMap<()>,
+    open_enums: PathMap<()>,
+    reflect: PathMap<()>,
+    cached_size: PathMap<()>,
+    bytes_codec: PathMap<String>,
     type_attributes: PathMap<String>,
     field_attributes: PathMap<String>,
     prost_types: bool,
@@ -365,6 +382,238 @@ impl Config {
         self
     }
 
+    /// Configure the code generator to generate [`prost::LossyString`] fields for Protobuf
+    /// [`string`][1] type fields.
+    ///
+    /// A `LossyString` holds the raw bytes of the field, so a value which isn't valid UTF-8 no
+    /// longer fails decoding of the whole message, and is re-encoded unchanged. The text is read
+    /// with `LossyString::to_string_lossy`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use a
+    /// `LossyString` for Protobuf `string` fields. For details about matching fields see
+    /// [`bytes`](#method.bytes). Map keys and values are always generated as `String`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Tolerate invalid UTF-8 in the `user_agent` field of a message type.
+    /// config.lossy_string(&[".my_messages.Request.user_agent"]);
+    ///
+    /// // Tolerate invalid UTF-8 in all string fields of a package.
+    /// config.lossy_string(&[".my_messages"]);
+    /// ```
+    ///
+    /// [1]: https://developers.google.com/protocol-buffers/docs/proto3#scalar
+    pub fn lossy_string<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.lossy_string.clear();
+        for matcher in paths {
+            self.lossy_string.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configure the code generator to generate accessors returning [`prost::OpenEnum`] for
+    /// Protobuf enum fields.
+    ///
+    /// Enum fields are stored as `i32`, so values unknown to the enum survive decoding and
+    /// re-encoding. By default their accessor methods return the default value of the enum for
+    /// such values; with `OpenEnum`, unknown values are returned as `OpenEnum::Unknown`, and can be
+    /// set too, as proto3 open enum semantics require.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should have open enum
+    /// accessors. For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Open enum accessors for all enum fields.
+    /// config.open_enums(&["."]);
+    /// ```
+    pub fn open_enums<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.open_enums.clear();
+        for matcher in paths {
+            self.open_enums.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configure the code generator to derive [`prost::ReflectMessage`] for messages, so that
+    /// their fields can be enumerated and read at runtime.
+    ///
+    /// The messages of nested message fields must have reflection too, including well-known types
+    /// from other crates, which rules out `prost-types` unless
+    /// [`compile_well_known_types`](#method.compile_well_known_types) is used.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should have reflection. For
+    /// details about matching paths see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Reflection for all messages.
+    /// config.reflect(&["."]);
+    /// ```
+    pub fn reflect<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.reflect.clear();
+        for matcher in paths {
+            self.reflect.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configure the code generator to generate a [`prost::CachedSize`] field, named
+    /// `_cached_size`, in messages, which memoizes the encoded length of the message between
+    /// sizing and writing it.
+    ///
+    /// Without the cache, the length of a message nested `n` levels deep is computed `n` times
+    /// when the outermost message is encoded. The cached length is only used for nested messages
+    /// whose parent message has the cache too, so the option is usually enabled for whole
+    /// packages.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages whose messages should cache their
+    /// encoded length. For details about matching paths see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Cache the encoded length of all messages.
+    /// config.cached_size(&["."]);
+    /// ```
+    pub fn cached_size<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.cached_size.clear();
+        for matcher in paths {
+            self.cached_size.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configure the code generator to generate fields of a custom Rust type for Protobuf
+    /// [`bytes`][1] type fields.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. These fields get the custom bytes type.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// **`rust_type`** - the fully qualified path of the Rust type backing the matched fields. The
+    /// type must implement [`prost::BytesAdapter`] and `AsRef<[u8]>`, as well as the traits
+    /// derived on the containing message.
+    ///
+    /// Custom bytes types take precedence over [`bytes`](#method.bytes) when both match a field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Back the `payload` field of a message with a type implementing `BytesAdapter`.
+    /// config.custom_bytes(".my_messages.Frame.payload", "crate::SharedBytes");
+    /// ```
+    ///
+    /// [1]: https://developers.google.com/protocol-buffers/docs/proto3#scalar
+    pub fn custom_bytes<P, T>(&mut self, path: P, rust_type: T) -> &mut Self
+    where
+        P: AsRef<str>,
+        T: AsRef<str>,
+    {
+        self.custom_bytes_type.insert(
+            path.as_ref().to_string(),
+            BytesType::Custom(rust_type.as_ref().to_string()),
+        );
+        self
+    }
+
+    /// Configure the code generator to generate [`prost::Blob`] fields for Protobuf [`bytes`][1]
+    /// type fields.
+    ///
+    /// A `Blob` decoded from a `Bytes` buffer shares the buffer rather than copying the value,
+    /// until it's modified. This suits large payloads which are mostly read or forwarded.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use `Blob`.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Share the decode buffer for the `content` field of the `Attachment` message.
+    /// config.blob(&[".my_messages.Attachment.content"]);
+    /// ```
+    ///
+    /// [1]: https://developers.google.com/protocol-buffers/docs/proto3#scalar
+    pub fn blob<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for matcher in paths {
+            self.custom_bytes(matcher, "::prost::Blob");
+        }
+        self
+    }
+
+    /// Configure the code generator to transform matched Protobuf [`bytes`][1] fields with a
+    /// [`prost::BytesCodec`] when they are encoded and decoded.
+    ///
+    /// The generated field holds the plain value, and the codec is applied to it on the wire.
+    /// This allows field-level encryption or compression to be declared once in the build script,
+    /// rather than at every place the field is read or written.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. These fields get the codec.
+    /// For details about matching fields see [`btree_map`](#method.btree_map).
+    ///
+    /// **`codec`** - the fully qualified path of a type implementing `prost::BytesCodec`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Encrypt the `ssn` field of a message type.
+    /// config.bytes_codec(".my_messages.Person.ssn", "crate::crypto::Encrypt");
+    /// ```
+    ///
+    /// [1]: https://developers.google.com/protocol-buffers/docs/proto3#scalar
+    pub fn bytes_codec<P, C>(&mut self, path: P, codec: C) -> &mut Self
+    where
+        P: AsRef<str>,
+        C: AsRef<str>,
+    {
+        self.bytes_codec
+            .insert(path.as_ref().to_string(), codec.as_ref().to_string());
+        self
+    }
+
     /// Add additional attribute to matched fields.
     ///
     /// # Arguments
@@ -453,6 +702,151 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to emit the HTTP bindings of service methods.
+    ///
+    /// Methods may declare how they are mapped to a REST API with the [`google.api.http`][1]
+    /// option. When this option is set, a `<service>_http` module is generated next to each
+    /// service with bound methods. The module contains a constant per method, holding the
+    /// [`prost_types::http::HttpBinding`]s of its primary rule followed by its
+    /// `additional_bindings`, and a `bindings()` function returning them keyed by the Protobuf
+    /// method name. If a message of the package has a nested module of the same name, underscores
+    /// are appended to the name of the bindings module until it is unique.
+    ///
+    /// The `google/api/http.proto` and `google/api/annotations.proto` files must be available to
+    /// `protoc` on the include path. Regardless of this option, the rules are available to service
+    /// generators through [`Method::http_rule`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut config = prost_build::Config::new();
+    /// config.http_bindings();
+    /// config.compile_protos(&["src/library.proto"], &["src", "googleapis"])?;
+    /// ```
+    ///
+    /// [1]: https://github.com/googleapis/googleapis/blob/master/google/api/http.proto
+    pub fn http_bindings(&mut self) -> &mut Self {
+        self.http_bindings = true;
+        self
+    }
+
+    /// Configures the code generator to emit the reserved field numbers, reserved field names and
+    /// extension ranges of each message as associated constants.
+    ///
+    /// Every generated message gets `RESERVED_RANGES`, `RESERVED_NAMES` and `EXTENSION_RANGES`
+    /// constants, so that validators and schema linters can read them from compiled crates.
+    /// Ranges are half-open, like the `reserved_range` and `extension_range` descriptor fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut config = prost_build::Config::new();
+    /// config.message_ranges();
+    /// config.compile_protos(&["src/frontend.proto"], &["src"])?;
+    ///
+    /// // In the crate including the generated code:
+    /// assert!(Request::RESERVED_RANGES.iter().all(|range| !range.contains(&7)));
+    /// ```
+    pub fn message_ranges(&mut self) -> &mut Self {
+        self.message_ranges = true;
+        self
+    }
+
+    /// Configures the code generator to emit a `new` constructor for matched messages.
+    ///
+    /// The constructor takes the given fields, in order, as arguments of any type convertible
+    /// into the field type, and sets the other fields to their default values. Fields are named
+    /// as in the `.proto` file; a `oneof` is named by its name. If `fields` is empty, the
+    /// constructor takes the `required` fields of the message.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of messages. For details about matching messages
+    /// see [`btree_map`](#method.btree_map).
+    ///
+    /// **`fields`** - the names of the fields taken by the constructor. Code generation panics if
+    /// a matched message lacks one of them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut config = prost_build::Config::new();
+    /// config.constructor(".my_messages.Person", &["name", "email"]);
+    /// config.compile_protos(&["src/people.proto"], &["src"])?;
+    ///
+    /// // In the crate including the generated code:
+    /// let person = Person::new("Ferris", "ferris@example.com");
+    /// ```
+    pub fn constructor<P, I, S>(&mut self, path: P, fields: I) -> &mut Self
+    where
+        P: AsRef<str>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.constructors.insert(
+            path.as_ref().to_string(),
+            fields
+                .into_iter()
+                .map(|field| field.as_ref().to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// Configures the code generator to emit a hash of each compiled `.proto` file.
+    ///
+    /// Each file gets a `<FILE>_SCHEMA_HASH: [u8; 32]` constant in the module of its package,
+    /// named after the file (e.g. `USER_SERVICE_SCHEMA_HASH` for `user_service.proto`). The
+    /// constant is the SHA-256 hash of the encoded `FileDescriptorProto`, without its source code
+    /// info, so it changes with the schema but not with comments. Services can advertise it to
+    /// detect schema mismatches, and caches can use it to invalidate entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut config = prost_build::Config::new();
+    /// config.schema_hash();
+    /// config.compile_protos(&["src/user_service.proto"], &["src"])?;
+    ///
+    /// // In the crate including the generated code:
+    /// let version = hex::encode(USER_SERVICE_SCHEMA_HASH);
+    /// ```
+    pub fn schema_hash(&mut self) -> &mut Self {
+        self.schema_hash = true;
+        self
+    }
+
+    /// Configures the code generator to emit a `descriptors` module in the module of each package.
+    ///
+    /// The module embeds the descriptors of the package's `.proto` files (without source code
+    /// info), so that runtime tooling can look up the schema of a message or service directly,
+    /// instead of loading and scanning a whole descriptor pool:
+    ///
+    /// - `FILE_DESCRIPTOR_SET: &[u8]`, the encoded `FileDescriptorSet` of the package's files;
+    /// - `MESSAGE_FULL_NAMES: &[&str]`, the fully qualified names of the messages of the package,
+    ///   including nested messages;
+    /// - `SERVICE_NAMES: &[&str]`, the fully qualified names of the services of the package;
+    /// - `file_descriptor_set()`, `message_descriptor(full_name)` and `service_descriptor(name)`,
+    ///   which decode the embedded descriptors on each call.
+    ///
+    /// The generated code depends on the `prost-types` crate, without its `no-descriptor` feature.
+    /// A message named `Descriptors` with nested types conflicts with the module.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut config = prost_build::Config::new();
+    /// config.descriptors();
+    /// config.compile_protos(&["src/store.proto"], &["src"])?;
+    ///
+    /// // In the crate including the generated code:
+    /// let order = store::descriptors::message_descriptor("store.Order").unwrap();
+    /// ```
+    pub fn descriptors(&mut self) -> &mut Self {
+        self.descriptors = true;
+        self
+    }
+
     /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
     /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
     pub fn compile_well_known_types(&mut self) -> &mut Self {
@@ -769,17 +1163,7 @@ impl Config {
         protos: &[impl AsRef<Path>],
         includes: &[impl AsRef<Path>],
     ) -> Result<()> {
-        let mut target_is_env = false;
-        let target: PathBuf = self.out_dir.clone().map(Ok).unwrap_or_else(|| {
-            env::var_os("OUT_DIR")
-                .ok_or_else(|| {
-                    Error::new(ErrorKind::Other, "OUT_DIR environment variable is not set")
-                })
-                .map(|val| {
-                    target_is_env = true;
-                    Into::into(val)
-                })
-        })?;
+        let target = self.target_dir()?;
 
         // TODO: This should probably emit 'rerun-if-changed=PATH' directives for cargo, however
         // according to [1] if any are output then those paths replace the default crate root,
@@ -787,6 +1171,49 @@ impl Config {
         // this figured out.
         // [1]: http://doc.crates.io/build-script.html#outputs-of-the-build-script
 
+        let files = self.generate_files(protos, includes)?;
+        write_files(&target, &files)
+    }
+
+    /// Compile `.proto` files into Rust code, returning it instead of writing it to the output
+    /// directory.
+    ///
+    /// The returned map is keyed by the name of each generated file (including the
+    /// [`include_file`](#method.include_file), if any), relative to the output directory.
+    /// Nothing is written, except to the [`file_descriptor_set_path`](#method.file_descriptor_set_path)
+    /// if one is configured. See [`compile_protos`](#method.compile_protos) for the arguments.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///   let files = prost_build::Config::new()
+    ///       .generate_files(&["src/frontend.proto", "src/backend.proto"], &["src"])?;
+    ///   for (filename, content) in &files {
+    ///       println!("{}: {} bytes", filename, content.len());
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    pub fn generate_files(
+        &mut self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<BTreeMap<String, String>> {
+        let buf = self.load_file_descriptor_set(protos, includes)?;
+        let (file_descriptor_set, http_rules) = decode_file_descriptor_set(&buf)?;
+        let modules = self.generate(file_descriptor_set.file, &http_rules)?;
+        let basepath = self.out_dir.clone();
+        self.output_files(modules, basepath.as_ref())
+    }
+
+    /// Runs `protoc` (unless `skip_protoc_run` is set), returning the encoded `FileDescriptorSet`.
+    fn load_file_descriptor_set(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<Vec<u8>> {
         let tmp;
         let file_descriptor_set_path = if let Some(path) = &self.file_descriptor_set_path {
             path.clone()
@@ -839,16 +1266,24 @@ impl Config {
             }
         }
 
-        let buf = fs::read(file_descriptor_set_path)?;
-        let file_descriptor_set = FileDescriptorSet::decode(&*buf).map_err(|error| {
-            Error::new(
-                ErrorKind::InvalidInput,
-                format!("invalid FileDescriptorSet: {}", error.to_string()),
-            )
-        })?;
+        fs::read(file_descriptor_set_path)
+    }
 
-        let modules = self.generate(file_descriptor_set.file)?;
-        for (module, content) in &modules {
+    /// Lays out generated modules as files: the include file, if any, and one file per module.
+    fn output_files(
+        &self,
+        modules: HashMap<Module, String>,
+        basepath: Option<&PathBuf>,
+    ) -> Result<BTreeMap<String, String>> {
+        let mut files = BTreeMap::new();
+        if let Some(ref include_file) = self.include_file {
+            let mut content = Vec::new();
+            self.write_includes(modules.keys().collect(), &mut content, 0, basepath)?;
+            let content = String::from_utf8(content).expect("include file is not UTF-8");
+            files.insert(include_file.to_string_lossy().into_owned(), content);
+        }
+
+        for (module, content) in modules {
             let mut filename = if module.is_empty() {
                 self.default_package_filename.clone()
             } else {
@@ -856,44 +1291,94 @@ impl Config {
             };
 
             filename.push_str(".rs");
+            files.insert(filename, content);
+        }
 
-            let output_path = target.join(&filename);
-
-            let previous_content = fs::read(&output_path);
+        Ok(files)
+    }
 
-            if previous_content
-                .map(|previous_content| previous_content == content.as_bytes())
-                .unwrap_or(false)
-            {
-                trace!("unchanged: {:?}", filename);
-            } else {
-                trace!("writing: {:?}", filename);
-                fs::write(output_path, content)?;
+    /// Checks that the Rust code generated from `.proto` files matches the files in the output
+    /// directory.
+    ///
+    /// This is intended for repositories which check generated code in: a test can call
+    /// `check_protos` with the [`out_dir`](#method.out_dir) set to the checked-in directory, and
+    /// fail when the `.proto` files drift from the generated code. Files in the output directory
+    /// which aren't generated are ignored. See [`compile_protos`](#method.compile_protos) for the
+    /// arguments.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of [`compile_protos`](#method.compile_protos), an error of kind
+    /// `InvalidData` is returned if any generated file is missing or out of date. Its message
+    /// contains a line diff of each stale file.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// #[test]
+    /// fn generated_code_is_fresh() {
+    ///     prost_build::Config::new()
+    ///         .out_dir("src/generated")
+    ///         .check_protos(&["protos/items.proto"], &["protos"])
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub fn check_protos(
+        &mut self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<()> {
+        let target = self.target_dir()?;
+        let files = self.generate_files(protos, includes)?;
+
+        let mut report = String::new();
+        for (filename, content) in &files {
+            let path = target.join(filename);
+            match fs::read_to_string(&path) {
+                Ok(previous_content) if previous_content == *content => (),
+                Ok(previous_content) => {
+                    report.push_str(&format!("--- {} (existing)\n", path.display()));
+                    report.push_str(&format!("+++ {} (generated)\n", path.display()));
+                    report.push_str(&code_diff::unified_diff(&previous_content, content));
+                }
+                Err(error) if error.kind() == ErrorKind::NotFound => {
+                    report.push_str(&format!("missing: {}\n", path.display()));
+                }
+                Err(error) => return Err(error),
             }
         }
 
-        if let Some(ref include_file) = self.include_file {
-            trace!("Writing include file: {:?}", target.join(include_file));
-            let mut file = fs::File::create(target.join(include_file))?;
-            self.write_includes(
-                modules.keys().collect(),
-                &mut file,
-                0,
-                if target_is_env { None } else { Some(&target) },
-            )?;
-            file.flush()?;
+        if report.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("generated code is out of date:\n{}", report),
+            ))
         }
+    }
 
-        Ok(())
+    /// Returns the directory generated files are written to.
+    fn target_dir(&self) -> Result<PathBuf> {
+        self.out_dir.clone().map(Ok).unwrap_or_else(|| {
+            env::var_os("OUT_DIR")
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::Other, "OUT_DIR environment variable is not set")
+                })
+                .map(Into::into)
+        })
     }
 
-    fn write_includes(
+    fn write_includes<W>(
         &self,
         mut entries: Vec<&Module>,
-        outfile: &mut fs::File,
+        outfile: &mut W,
         depth: usize,
         basepath: Option<&PathBuf>,
-    ) -> Result<usize> {
+    ) -> Result<usize>
+    where
+        W: Write,
+    {
         let mut written = 0;
         while !entries.is_empty() {
             let modident = &entries[0][depth];
@@ -945,11 +1430,18 @@ impl Config {
         Ok(written)
     }
 
-    fn write_line(&self, outfile: &mut fs::File, depth: usize, line: &str) -> Result<()> {
+    fn write_line<W>(&self, outfile: &mut W, depth: usize, line: &str) -> Result<()>
+    where
+        W: Write,
+    {
         outfile.write_all(format!("{}{}\n", ("    ").to_owned().repeat(depth), line).as_bytes())
     }
 
-    fn generate(&mut self, files: Vec<FileDescriptorProto>) -> Result<HashMap<Module, String>> {
+    fn generate(
+        &mut self,
+        files: Vec<FileDescriptorProto>,
+        http_rules: &HashMap<String, HttpRule>,
+    ) -> Result<HashMap<Module, String>> {
         let mut modules = HashMap::new();
         let mut packages = HashMap::new();
 
@@ -958,6 +1450,7 @@ impl Config {
         let extern_paths = ExternPaths::new(&self.extern_paths, self.prost_types)
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
 
+        let mut package_files = HashMap::new();
         for file in files {
             let module = self.module(&file);
 
@@ -966,8 +1459,22 @@ impl Config {
                 packages.insert(module.clone(), file.package().to_string());
             }
 
+            if self.descriptors {
+                package_files
+                    .entry(module.clone())
+                    .or_insert_with(Vec::new)
+                    .push(file.clone());
+            }
+
             let mut buf = modules.entry(module).or_insert_with(String::new);
-            CodeGenerator::generate(self, &message_graph, &extern_paths, file, &mut buf);
+            CodeGenerator::generate(
+                self,
+                &message_graph,
+                &extern_paths,
+                http_rules,
+                file,
+                &mut buf,
+            );
         }
 
         if let Some(ref mut service_generator) = self.service_generator {
@@ -977,6 +1484,11 @@ impl Config {
             }
         }
 
+        for (module, files) in package_files {
+            let buf = modules.get_mut(&module).unwrap();
+            append_descriptors(files, buf);
+        }
+
         Ok(modules)
     }
 
@@ -994,8 +1506,19 @@ impl default::Default for Config {
         Config {
             file_descriptor_set_path: None,
             service_generator: None,
+            http_bindings: false,
+            message_ranges: false,
+            constructors: PathMap::default(),
+            schema_hash: false,
+            descriptors: false,
             map_type: PathMap::default(),
             bytes_type: PathMap::default(),
+            custom_bytes_type: PathMap::default(),
+            lossy_string: PathMap::default(),
+            open_enums: PathMap::default(),
+            reflect: PathMap::default(),
+            cached_size: PathMap::default(),
+            bytes_codec: PathMap::default(),
             type_attributes: PathMap::default(),
             field_attributes: PathMap::default(),
             prost_types: true,
@@ -1016,8 +1539,19 @@ impl fmt::Debug for Config {
         fmt.debug_struct("Config")
             .field("file_descriptor_set_path", &self.file_descriptor_set_path)
             .field("service_generator", &self.service_generator.is_some())
+            .field("http_bindings", &self.http_bindings)
+            .field("message_ranges", &self.message_ranges)
+            .field("constructors", &self.constructors)
+            .field("schema_hash", &self.schema_hash)
+            .field("descriptors", &self.descriptors)
             .field("map_type", &self.map_type)
             .field("bytes_type", &self.bytes_type)
+            .field("custom_bytes_type", &self.custom_bytes_type)
+            .field("lossy_string", &self.lossy_string)
+            .field("open_enums", &self.open_enums)
+            .field("reflect", &self.reflect)
+            .field("cached_size", &self.cached_size)
+            .field("bytes_codec", &self.bytes_codec)
             .field("type_attributes", &self.type_attributes)
             .field("field_attributes", &self.field_attributes)
             .field("prost_types", &self.prost_types)
@@ -1077,6 +1611,47 @@ pub fn compile_protos(protos: &[impl AsRef<Path>], includes: &[impl AsRef<Path>]
     Config::new().compile_protos(protos, includes)
 }
 
+/// Decodes an encoded `FileDescriptorSet`, along with the `google.api.http` rules of its methods.
+fn decode_file_descriptor_set(
+    buf: &[u8],
+) -> Result<(FileDescriptorSet, HashMap<String, HttpRule>)> {
+    let file_descriptor_set = FileDescriptorSet::decode(buf).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid FileDescriptorSet: {}", error.to_string()),
+        )
+    })?;
+
+    let http_rules = http::http_rules(buf).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid FileDescriptorSet: {}", error.to_string()),
+        )
+    })?;
+
+    Ok((file_descriptor_set, http_rules))
+}
+
+/// Writes generated files to a directory, leaving files whose content is unchanged untouched.
+fn write_files(target: &Path, files: &BTreeMap<String, String>) -> Result<()> {
+    for (filename, content) in files {
+        let output_path = target.join(filename);
+
+        let previous_content = fs::read(&output_path);
+
+        if previous_content
+            .map(|previous_content| previous_content == content.as_bytes())
+            .unwrap_or(false)
+        {
+            trace!("unchanged: {:?}", filename);
+        } else {
+            trace!("writing: {:?}", filename);
+            fs::write(output_path, content)?;
+        }
+    }
+    Ok(())
+}
+
 /// Returns the path to the `protoc` binary.
 pub fn protoc() -> PathBuf {
     match env::var_os("PROTOC") {
@@ -1189,4 +1764,48 @@ mod tests {
         assert_eq!(&state.package_names, &["helloworld"]);
         assert_eq!(state.finalized, 3);
     }
+
+    #[test]
+    fn check_protos() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let files = Config::new()
+            .include_file("_protos.rs")
+            .generate_files(&["src/hello.proto", "src/goodbye.proto"], &["src"])
+            .unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            &["_protos.rs", "helloworld.rs"]
+        );
+        assert!(fs::read_dir(tempdir.path()).unwrap().next().is_none());
+
+        let mut config = Config::new();
+        config.out_dir(tempdir.path()).include_file("_protos.rs");
+        let error = config
+            .check_protos(&["src/hello.proto", "src/goodbye.proto"], &["src"])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("missing: "));
+
+        config
+            .compile_protos(&["src/hello.proto", "src/goodbye.proto"], &["src"])
+            .unwrap();
+        config
+            .check_protos(&["src/hello.proto", "src/goodbye.proto"], &["src"])
+            .unwrap();
+
+        let path = tempdir.path().join("helloworld.rs");
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(
+            &path,
+            content.replacen("pub struct", "pub(crate) struct", 1),
+        )
+        .unwrap();
+        let error = config
+            .check_protos(&["src/hello.proto", "src/goodbye.proto"], &["src"])
+            .unwrap_err();
+        assert!(error.to_string().contains("\n-pub(crate) struct"));
+        assert!(error.to_string().contains("\n+pub struct"));
+    }
 }
@@ -82,4 +82,10 @@ impl MessageGraph {
 
         has_path_connecting(&self.graph, outer, inner, None)
     }
+
+    /// Returns the fully qualified names of the messages in the graph, and of the message types
+    /// of their fields.
+    pub fn message_names(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
 }
@@ -0,0 +1,293 @@
+//! Compilation of `.proto` files shared between the crates of a workspace.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::ident::to_snake;
+use crate::{decode_file_descriptor_set, write_files, Config};
+
+/// Compiles `.proto` files for several crates at once.
+///
+/// Large workspaces often split their Protobuf packages across crates: a crate of common types,
+/// and crates of services which import them. Compiling each crate separately means running
+/// `protoc` once per crate, and keeping the `extern_path` of every imported package in sync by
+/// hand. A `Workspace` instead maps each package to the crate which owns it, runs `protoc` once,
+/// and writes the code of each package to its crate's output directory, with `extern_path`s
+/// pointing at the packages of the other crates.
+///
+/// Each crate gets an include file (the configured
+/// [`include_file`](struct.Config.html#method.include_file), or `_includes.rs`) which declares the
+/// modules of its packages, and which is expected to be included at the root of the crate:
+/// package `foo.bar` of crate `common` is referred to as `::common::foo::bar` by the other crates.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use std::io::Result;
+/// fn main() -> Result<()> {
+///     prost_build::Workspace::new()
+///         .crate_dir("acme-common", "common/src/generated")
+///         .crate_dir("acme-api", "api/src/generated")
+///         .package(".acme.common", "acme-common")
+///         .package(".acme.api", "acme-api")
+///         .compile_protos(
+///             &mut prost_build::Config::new(),
+///             &["protos/acme/api/service.proto"],
+///             &["protos", "third-party/protos"],
+///         )
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    crates: Vec<(String, PathBuf)>,
+    packages: Vec<(String, String)>,
+}
+
+impl Workspace {
+    /// Creates an empty workspace.
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    /// Declares a crate, and the directory its generated code is written to.
+    ///
+    /// The crate name is the name other crates refer to it by, e.g. `acme-common` (dashes are
+    /// replaced by underscores).
+    pub fn crate_dir<C, P>(&mut self, crate_name: C, out_dir: P) -> &mut Self
+    where
+        C: Into<String>,
+        P: Into<PathBuf>,
+    {
+        self.crates.push((crate_name.into(), out_dir.into()));
+        self
+    }
+
+    /// Assigns a Protobuf package, and the packages nested in it, to a crate.
+    ///
+    /// The package must be fully qualified, e.g. `.acme.common`. When packages are nested, the
+    /// most specific assignment wins.
+    pub fn package<P, C>(&mut self, package: P, crate_name: C) -> &mut Self
+    where
+        P: Into<String>,
+        C: Into<String>,
+    {
+        self.packages.push((package.into(), crate_name.into()));
+        self
+    }
+
+    /// Compiles `.proto` files and writes the generated code of each crate to its output
+    /// directory.
+    ///
+    /// `protoc` runs once with the `config` options; see
+    /// [`Config::compile_protos`](struct.Config.html#method.compile_protos) for the arguments.
+    /// The `extern_path`s of `config` apply to every crate.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of `Config::compile_protos`, an error of kind `InvalidInput` is
+    /// returned if a package is assigned to an undeclared crate, or if a compiled file belongs to
+    /// no crate and isn't covered by an `extern_path`.
+    pub fn compile_protos(
+        &self,
+        config: &mut Config,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<()> {
+        for (package, crate_name) in &self.packages {
+            if !self.crates.iter().any(|(name, _)| name == crate_name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "package {} is assigned to undeclared crate {}",
+                        package, crate_name
+                    ),
+                ));
+            }
+        }
+
+        let buf = config.load_file_descriptor_set(protos, includes)?;
+        let (file_descriptor_set, http_rules) = decode_file_descriptor_set(&buf)?;
+
+        let mut files_by_crate = HashMap::new();
+        for file in file_descriptor_set.file {
+            let package = format!(".{}", file.package());
+            match self.owner(&package) {
+                Some(crate_name) => files_by_crate
+                    .entry(crate_name)
+                    .or_insert_with(Vec::new)
+                    .push(file),
+                None if self.is_extern(config, &package) => (),
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "{} (package {}) belongs to no crate of the workspace",
+                            file.name(),
+                            package
+                        ),
+                    ))
+                }
+            }
+        }
+
+        let extern_paths = config.extern_paths.clone();
+        let include_file = config.include_file.clone();
+        if config.include_file.is_none() {
+            config.include_file("_includes.rs");
+        }
+
+        let result = self.crates.iter().try_for_each(|(crate_name, out_dir)| {
+            let files = match files_by_crate.remove(crate_name.as_str()) {
+                Some(files) => files,
+                None => return Ok(()),
+            };
+
+            config.extern_paths = extern_paths.clone();
+            for (package, owner) in &self.packages {
+                if owner != crate_name {
+                    config.extern_path(package.clone(), rust_path(owner, package));
+                }
+            }
+
+            let modules = config.generate(files, &http_rules)?;
+            let files = config.output_files(modules, Some(&PathBuf::from(".")))?;
+            fs::create_dir_all(out_dir)?;
+            write_files(out_dir, &files)
+        });
+
+        config.extern_paths = extern_paths;
+        config.include_file = include_file;
+        result
+    }
+
+    /// Returns the crate owning a fully qualified package, if any.
+    fn owner(&self, package: &str) -> Option<&str> {
+        self.packages
+            .iter()
+            .filter(|(prefix, _)| contains(prefix, package))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, crate_name)| crate_name.as_str())
+    }
+
+    /// Returns `true` if a fully qualified package is provided by an `extern_path` of `config`.
+    fn is_extern(&self, config: &Config, package: &str) -> bool {
+        (config.prost_types && contains(".google.protobuf", package))
+            || config
+                .extern_paths
+                .iter()
+                .any(|(proto_path, _)| contains(proto_path, package))
+    }
+}
+
+/// Returns `true` if `package` is `prefix` or is nested in it.
+fn contains(prefix: &str, package: &str) -> bool {
+    prefix == "."
+        || package == prefix
+        || (package.starts_with(prefix) && package[prefix.len()..].starts_with('.'))
+}
+
+/// Returns the Rust path of a package generated in another crate of the workspace.
+fn rust_path(crate_name: &str, package: &str) -> String {
+    let mut path = format!("::{}", crate_name.replace('-', "_"));
+    for segment in package.split('.').filter(|s| !s.is_empty()) {
+        path.push_str("::");
+        path.push_str(&to_snake(segment));
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner() {
+        let mut workspace = Workspace::new();
+        workspace
+            .crate_dir("a", "a")
+            .crate_dir("b", "b")
+            .package(".acme", "a")
+            .package(".acme.api", "b");
+
+        assert_eq!(workspace.owner(".acme"), Some("a"));
+        assert_eq!(workspace.owner(".acme.common"), Some("a"));
+        assert_eq!(workspace.owner(".acme.api"), Some("b"));
+        assert_eq!(workspace.owner(".acme.api.v1"), Some("b"));
+        assert_eq!(workspace.owner(".acme.apis"), Some("a"));
+        assert_eq!(workspace.owner(".other"), None);
+    }
+
+    #[test]
+    fn test_rust_path() {
+        assert_eq!(
+            rust_path("acme-common", ".acme.common.V1"),
+            "::acme_common::acme::common::v1"
+        );
+    }
+
+    #[test]
+    fn compile_protos() {
+        let _ = env_logger::try_init();
+        let protos = tempfile::tempdir().unwrap();
+        fs::create_dir(protos.path().join("acme")).unwrap();
+        fs::write(
+            protos.path().join("acme/common.proto"),
+            "syntax = \"proto3\";\n\
+             package acme.common;\n\
+             message Id { string value = 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            protos.path().join("acme/api.proto"),
+            "syntax = \"proto3\";\n\
+             package acme.api;\n\
+             import \"acme/common.proto\";\n\
+             import \"google/protobuf/timestamp.proto\";\n\
+             message Request {\n\
+               acme.common.Id id = 1;\n\
+               google.protobuf.Timestamp time = 2;\n\
+             }\n",
+        )
+        .unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let common_dir = out.path().join("common");
+        let api_dir = out.path().join("api");
+        Workspace::new()
+            .crate_dir("acme-common", &common_dir)
+            .crate_dir("acme-api", &api_dir)
+            .package(".acme.common", "acme-common")
+            .package(".acme.api", "acme-api")
+            .compile_protos(
+                &mut Config::new(),
+                &[protos.path().join("acme/api.proto")],
+                &[protos.path()],
+            )
+            .unwrap();
+
+        let common = fs::read_to_string(common_dir.join("acme.common.rs")).unwrap();
+        assert!(common.contains("pub struct Id"));
+        let includes = fs::read_to_string(common_dir.join("_includes.rs")).unwrap();
+        assert!(includes.contains("include!(\"./acme.common.rs\");"));
+        assert!(!common_dir.join("acme.api.rs").exists());
+
+        let api = fs::read_to_string(api_dir.join("acme.api.rs")).unwrap();
+        assert!(api.contains("::acme_common::acme::common::Id"));
+        assert!(api.contains("::prost_types::Timestamp"));
+        assert!(!api_dir.join("acme.common.rs").exists());
+
+        let error = Workspace::new()
+            .crate_dir("acme-api", &api_dir)
+            .package(".acme.api", "acme-api")
+            .compile_protos(
+                &mut Config::new(),
+                &[protos.path().join("acme/api.proto")],
+                &[protos.path()],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("package .acme.common"));
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{bail, Error};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Meta, Type};
+
+use crate::field::{set_bool, set_option, tags_attr, word_attr};
+
+/// A message embedded in its parent: its fields are encoded as fields of the parent, rather than
+/// as a nested message.
+///
+/// The flattened message must be derived, and its tags must be exactly those of the `tags`
+/// attribute; the parent fails to compile otherwise.
+#[derive(Clone)]
+pub struct Field {
+    pub tags: Vec<u32>,
+    /// The type of the flattened message, once the field of the parent has been parsed.
+    pub ty: Option<Type>,
+}
+
+impl Field {
+    pub fn new(attrs: &[Meta]) -> Result<Option<Field>, Error> {
+        let mut flatten = false;
+        let mut tags = None;
+        let mut unknown_attrs = Vec::new();
+
+        for attr in attrs {
+            if word_attr("flatten", attr) {
+                set_bool(&mut flatten, "duplicate flatten attribute")?;
+            } else if let Some(t) = tags_attr(attr)? {
+                set_option(&mut tags, t, "duplicate tags attributes")?;
+            } else {
+                unknown_attrs.push(attr);
+            }
+        }
+
+        if !flatten {
+            return Ok(None);
+        }
+
+        match unknown_attrs.len() {
+            0 => (),
+            1 => bail!(
+                "unknown attribute for flattened field: {:?}",
+                unknown_attrs[0]
+            ),
+            _ => bail!(
+                "unknown attributes for flattened field: {:?}",
+                unknown_attrs
+            ),
+        }
+
+        let tags = match tags {
+            Some(tags) => tags,
+            None => bail!("flattened field is missing a tags attribute"),
+        };
+
+        Ok(Some(Field { tags, ty: None }))
+    }
+
+    /// Returns an expression which evaluates to `true` if the flattened message has exactly the
+    /// declared tags.
+    pub fn tags_match(&self) -> TokenStream {
+        let ty = &self.ty;
+        let mut tags = self.tags.clone();
+        tags.sort_unstable();
+        quote! {
+            ::prost::encoding::same_tags(<#ty>::__PROST_TAGS, &[#(#tags),*])
+        }
+    }
+
+    /// Returns a statement which encodes the fields of the flattened message.
+    pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            ::prost::EncodeMessage::encode_raw(&#ident, buf);
+        }
+    }
+
+    /// Returns a statement which encodes the fields of the flattened message deterministically.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            ::prost::EncodeMessage::encode_raw_deterministic(&#ident, buf);
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a field into the flattened
+    /// message.
+    pub fn merge(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            ::prost::DecodeMessage::merge_field(#ident, tag, wire_type, buf, ctx)
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a field, which may borrow
+    /// from the buffer, into the flattened message.
+    pub fn merge_borrowed(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            ::prost::DecodeBorrowed::merge_field_borrowed(#ident, tag, wire_type, buf, ctx)
+        }
+    }
+
+    /// Returns an expression which evaluates to the encoded length of the fields of the flattened
+    /// message.
+    pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            ::prost::EncodeMessage::encoded_len(&#ident)
+        }
+    }
+
+    pub fn clear(&self, ident: TokenStream) -> TokenStream {
+        quote!(::prost::DecodeMessage::clear(&mut #ident))
+    }
+}
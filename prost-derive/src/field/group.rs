@@ -71,19 +71,31 @@ impl Field {
     }
 
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(::prost::encoding::group::encode))
+    }
+
+    /// Returns a statement which encodes the field with the groups encoded deterministically.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(
+            ident,
+            quote!(::prost::encoding::group::encode_deterministic),
+        )
+    }
+
+    fn encode_with(&self, ident: TokenStream, encode_fn: TokenStream) -> TokenStream {
         let tag = self.tag;
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
-                    ::prost::encoding::group::encode(#tag, msg, buf);
+                    #encode_fn(#tag, msg, buf);
                 }
             },
             Label::Required => quote! {
-                ::prost::encoding::group::encode(#tag, &#ident, buf);
+                #encode_fn(#tag, &#ident, buf);
             },
             Label::Repeated => quote! {
                 for msg in &#ident {
-                    ::prost::encoding::group::encode(#tag, msg, buf);
+                    #encode_fn(#tag, msg, buf);
                 }
             },
         }
@@ -127,7 +139,7 @@ impl Field {
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
-            Label::Required => quote!(#ident.clear()),
+            Label::Required => quote!(::prost::DecodeMessage::clear(&mut #ident)),
             Label::Repeated => quote!(#ident.clear()),
         }
     }
@@ -9,6 +9,8 @@ use crate::field::{scalar, set_option, tag_attr};
 pub enum MapTy {
     HashMap,
     BTreeMap,
+    /// A custom `prost::MapContainer`.
+    Custom,
 }
 
 impl MapTy {
@@ -16,6 +18,7 @@ impl MapTy {
         match s {
             "map" | "hash_map" => Some(MapTy::HashMap),
             "btree_map" => Some(MapTy::BTreeMap),
+            "map_container" => Some(MapTy::Custom),
             _ => None,
         }
     }
@@ -24,13 +27,14 @@ impl MapTy {
         match *self {
             MapTy::HashMap => Ident::new("hash_map", Span::call_site()),
             MapTy::BTreeMap => Ident::new("btree_map", Span::call_site()),
+            MapTy::Custom => Ident::new("map", Span::call_site()),
         }
     }
 
     fn lib(&self) -> TokenStream {
         match self {
             MapTy::HashMap => quote! { std },
-            MapTy::BTreeMap => quote! { prost::alloc },
+            MapTy::BTreeMap | MapTy::Custom => quote! { prost::alloc },
         }
     }
 }
@@ -41,6 +45,9 @@ fn fake_scalar(ty: scalar::Ty) -> scalar::Field {
         ty,
         kind,
         tag: 0, // Not used here
+        codec: None,
+        open: false,
+        borrowed: false,
     }
 }
 
@@ -129,16 +136,38 @@ impl Field {
 
     /// Returns a statement which encodes the map field.
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, false)
+    }
+
+    /// Returns a statement which encodes the map field deterministically: the entries of a hash
+    /// map are encoded in key order, and message values deterministically. The entries of a custom
+    /// map are encoded in its iteration order.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, true)
+    }
+
+    fn encode_with(&self, ident: TokenStream, deterministic: bool) -> TokenStream {
         let tag = self.tag;
         let key_mod = self.key_ty.module();
         let ke = quote!(::prost::encoding::#key_mod::encode);
         let kl = quote!(::prost::encoding::#key_mod::encoded_len);
         let module = self.map_ty.module();
+        let sorted = deterministic && !matches!(self.map_ty, MapTy::Custom);
+        let (encode_fn, encode_with_default_fn) = if sorted {
+            (quote!(encode_sorted), quote!(encode_sorted_with_default))
+        } else {
+            (quote!(encode), quote!(encode_with_default))
+        };
+        let encode_message_fn = if deterministic {
+            quote!(::prost::encoding::message::encode_deterministic)
+        } else {
+            quote!(::prost::encoding::message::encode)
+        };
         match &self.value_ty {
             ValueTy::Scalar(scalar::Ty::Enumeration(ty)) => {
                 let default = quote!(#ty::default() as i32);
                 quote! {
-                    ::prost::encoding::#module::encode_with_default(
+                    ::prost::encoding::#module::#encode_with_default_fn(
                         #ke,
                         #kl,
                         ::prost::encoding::int32::encode,
@@ -155,7 +184,7 @@ impl Field {
                 let ve = quote!(::prost::encoding::#val_mod::encode);
                 let vl = quote!(::prost::encoding::#val_mod::encoded_len);
                 quote! {
-                    ::prost::encoding::#module::encode(
+                    ::prost::encoding::#module::#encode_fn(
                         #ke,
                         #kl,
                         #ve,
@@ -167,10 +196,10 @@ impl Field {
                 }
             }
             ValueTy::Message => quote! {
-                ::prost::encoding::#module::encode(
+                ::prost::encoding::#module::#encode_fn(
                     #ke,
                     #kl,
-                    ::prost::encoding::message::encode,
+                    #encode_message_fn,
                     ::prost::encoding::message::encoded_len,
                     #tag,
                     &#ident,
@@ -186,6 +215,12 @@ impl Field {
         let key_mod = self.key_ty.module();
         let km = quote!(::prost::encoding::#key_mod::merge);
         let module = self.map_ty.module();
+        // `ident` is a mutable reference to the field, which is only coerced to the map type when
+        // it is known to the merge function.
+        let ident = match self.map_ty {
+            MapTy::HashMap | MapTy::BTreeMap => ident,
+            MapTy::Custom => quote!(*#ident),
+        };
         match &self.value_ty {
             ValueTy::Scalar(scalar::Ty::Enumeration(ty)) => {
                 let default = quote!(#ty::default() as i32);
@@ -253,11 +288,36 @@ impl Field {
     }
 
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
-        quote!(#ident.clear())
+        match self.map_ty {
+            MapTy::HashMap | MapTy::BTreeMap => quote!(#ident.clear()),
+            MapTy::Custom => quote!(::prost::MapContainer::clear(&mut #ident)),
+        }
+    }
+
+    /// Returns an expression which evaluates to the `prost::reflect::Value` of the map.
+    ///
+    /// The map is iterated through a shared reference, which custom map containers must support.
+    pub fn reflect_value(&self, ident: TokenStream) -> TokenStream {
+        let key = self.key_ty.reflect_value(quote!(key));
+        let value = match self.value_ty {
+            ValueTy::Scalar(ref ty) => ty.reflect_value(quote!(value)),
+            ValueTy::Message => quote!(::prost::reflect::Value::Message(value)),
+        };
+        quote! {
+            ::prost::reflect::Value::Map(
+                ::core::iter::IntoIterator::into_iter(&#ident)
+                    .map(|(key, value)| (#key, #value))
+                    .collect(),
+            )
+        }
     }
 
     /// Returns methods to embed in the message.
     pub fn methods(&self, ident: &Ident) -> Option<TokenStream> {
+        if let MapTy::Custom = self.map_ty {
+            // Custom containers aren't required to support lookups.
+            return None;
+        }
         if let ValueTy::Scalar(scalar::Ty::Enumeration(ty)) = &self.value_ty {
             let key_ty = self.key_ty.rust_type();
             let key_ref_ty = self.key_ty.rust_ref_type();
@@ -299,6 +359,7 @@ impl Field {
         let type_name = match self.map_ty {
             MapTy::HashMap => Ident::new("HashMap", Span::call_site()),
             MapTy::BTreeMap => Ident::new("BTreeMap", Span::call_site()),
+            MapTy::Custom => return self.debug_custom(wrapper_name),
         };
 
         // A fake field for generating the debug wrapper
@@ -338,6 +399,40 @@ impl Field {
             },
         }
     }
+
+    /// Returns a newtype wrapper around a custom map container, implementing nicer Debug.
+    fn debug_custom(&self, wrapper_name: TokenStream) -> TokenStream {
+        let key_wrapper = fake_scalar(self.key_ty.clone()).debug(quote!(KeyWrapper));
+        let key = self.key_ty.rust_type();
+        let value_wrapper = self.value_ty.debug();
+        let bounds = match &self.value_ty {
+            ValueTy::Scalar(ty) => {
+                let value = ty.rust_type();
+                quote!(M: ::prost::MapContainer<Key = #key, Value = #value>)
+            }
+            ValueTy::Message => quote! {
+                M: ::prost::MapContainer<Key = #key>,
+                M::Value: ::core::fmt::Debug,
+            },
+        };
+        quote! {
+            struct #wrapper_name<'a, M: 'a>(&'a M);
+            impl<'a, M> ::core::fmt::Debug for #wrapper_name<'a, M>
+            where
+                #bounds
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    #key_wrapper
+                    #value_wrapper
+                    let mut builder = f.debug_map();
+                    ::prost::MapContainer::for_each(self.0, |k, v| {
+                        builder.entry(&KeyWrapper(k), &ValueWrapper(v));
+                    });
+                    builder.finish()
+                }
+            }
+        }
+    }
 }
 
 fn key_ty_from_str(s: &str) -> Result<scalar::Ty, Error> {
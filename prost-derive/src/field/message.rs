@@ -74,37 +74,66 @@ impl Field {
     }
 
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(::prost::encoding::message::encode))
+    }
+
+    /// Returns a statement which encodes the field with the encoded lengths cached while sizing
+    /// the enclosing message.
+    pub fn encode_cached(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(ident, quote!(::prost::encoding::message::encode_cached))
+    }
+
+    /// Returns a statement which encodes the field with the nested messages encoded
+    /// deterministically.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        self.encode_with(
+            ident,
+            quote!(::prost::encoding::message::encode_deterministic),
+        )
+    }
+
+    fn encode_with(&self, ident: TokenStream, encode_fn: TokenStream) -> TokenStream {
         let tag = self.tag;
         match self.label {
             Label::Optional => quote! {
                 if let Some(ref msg) = #ident {
-                    ::prost::encoding::message::encode(#tag, msg, buf);
+                    #encode_fn(#tag, msg, buf);
                 }
             },
             Label::Required => quote! {
-                ::prost::encoding::message::encode(#tag, &#ident, buf);
+                #encode_fn(#tag, &#ident, buf);
             },
             Label::Repeated => quote! {
                 for msg in &#ident {
-                    ::prost::encoding::message::encode(#tag, msg, buf);
+                    #encode_fn(#tag, msg, buf);
                 }
             },
         }
     }
 
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
+        self.merge_with(ident, quote!(::prost::encoding::message))
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded message, which may
+    /// borrow from the buffer, into the field.
+    pub fn merge_borrowed(&self, ident: TokenStream) -> TokenStream {
+        self.merge_with(ident, quote!(::prost::encoding::borrowed::message))
+    }
+
+    fn merge_with(&self, ident: TokenStream, module: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote! {
-                ::prost::encoding::message::merge(wire_type,
-                                                 #ident.get_or_insert_with(::core::default::Default::default),
-                                                 buf,
-                                                 ctx)
+                #module::merge(wire_type,
+                               #ident.get_or_insert_with(::core::default::Default::default),
+                               buf,
+                               ctx)
             },
             Label::Required => quote! {
-                ::prost::encoding::message::merge(wire_type, #ident, buf, ctx)
+                #module::merge(wire_type, #ident, buf, ctx)
             },
             Label::Repeated => quote! {
-                ::prost::encoding::message::merge_repeated(wire_type, #ident, buf, ctx)
+                #module::merge_repeated(wire_type, #ident, buf, ctx)
             },
         }
     }
@@ -127,7 +156,7 @@ impl Field {
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
-            Label::Required => quote!(#ident.clear()),
+            Label::Required => quote!(::prost::DecodeMessage::clear(&mut #ident)),
             Label::Repeated => quote!(#ident.clear()),
         }
     }
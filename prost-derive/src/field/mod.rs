@@ -1,3 +1,4 @@
+mod flatten;
 mod group;
 mod map;
 mod message;
@@ -24,6 +25,8 @@ pub enum Field {
     Oneof(oneof::Field),
     /// A group field.
     Group(group::Field),
+    /// A message whose fields are flattened into the message.
+    Flatten(flatten::Field),
 }
 
 impl Field {
@@ -46,6 +49,8 @@ impl Field {
             Field::Oneof(field)
         } else if let Some(field) = group::Field::new(&attrs, inferred_tag)? {
             Field::Group(field)
+        } else if let Some(field) = flatten::Field::new(&attrs)? {
+            Field::Flatten(field)
         } else {
             bail!("no type attribute");
         };
@@ -84,6 +89,78 @@ impl Field {
             Field::Map(ref map) => vec![map.tag],
             Field::Oneof(ref oneof) => oneof.tags.clone(),
             Field::Group(ref group) => vec![group.tag],
+            Field::Flatten(ref flatten) => flatten.tags.clone(),
+        }
+    }
+
+    /// Returns the tags of the field which may appear at most once in a message: the tag of a
+    /// non-repeated field, or the tags of a oneof.
+    ///
+    /// The fields of a flattened message aren't known to the derive, so the flattened message
+    /// tells which of them are singular.
+    pub fn singular_tags(&self) -> Vec<u32> {
+        match *self {
+            Field::Scalar(ref scalar) => match scalar.kind {
+                scalar::Kind::Repeated | scalar::Kind::Packed => Vec::new(),
+                _ => vec![scalar.tag],
+            },
+            Field::Message(ref message) if message.label != Label::Repeated => vec![message.tag],
+            Field::Group(ref group) if group.label != Label::Repeated => vec![group.tag],
+            Field::Message(..) | Field::Group(..) | Field::Map(..) | Field::Flatten(..) => {
+                Vec::new()
+            }
+            Field::Oneof(ref oneof) => oneof.tags.clone(),
+        }
+    }
+
+    /// Returns the tag of the field if it is a proto2 `required` field.
+    pub fn required_tag(&self) -> Option<u32> {
+        match *self {
+            Field::Scalar(ref scalar) => match scalar.kind {
+                scalar::Kind::Required(..) => Some(scalar.tag),
+                _ => None,
+            },
+            Field::Message(ref message) if message.label == Label::Required => Some(message.tag),
+            Field::Group(ref group) if group.label == Label::Required => Some(group.tag),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the field is a repeated field other than a map, whose values are decoded
+    /// into a `Vec`.
+    pub fn is_repeated(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => {
+                matches!(scalar.kind, scalar::Kind::Repeated | scalar::Kind::Packed)
+            }
+            Field::Message(ref message) => message.label == Label::Repeated,
+            Field::Group(ref group) => group.label == Label::Repeated,
+            Field::Map(..) | Field::Oneof(..) | Field::Flatten(..) => false,
+        }
+    }
+
+    /// Returns `true` if the field borrows its value from the decoded buffer.
+    pub fn is_borrowed(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.borrowed,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the field is a repeated message field.
+    pub fn is_repeated_message(&self) -> bool {
+        match *self {
+            Field::Message(ref message) => message.label == Label::Repeated,
+            _ => false,
+        }
+    }
+
+    /// Returns a statement which encodes the field, in a message which caches its encoded length:
+    /// nested messages are encoded with the lengths cached while sizing the message.
+    pub fn encode_cached(&self, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Message(ref message) => message.encode_cached(ident),
+            _ => self.encode(ident),
         }
     }
 
@@ -95,6 +172,20 @@ impl Field {
             Field::Map(ref map) => map.encode(ident),
             Field::Oneof(ref oneof) => oneof.encode(ident),
             Field::Group(ref group) => group.encode(ident),
+            Field::Flatten(ref flatten) => flatten.encode(ident),
+        }
+    }
+
+    /// Returns a statement which encodes the field deterministically: the entries of hash maps are
+    /// encoded in key order, and nested messages deterministically.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.encode(ident),
+            Field::Message(ref message) => message.encode_deterministic(ident),
+            Field::Map(ref map) => map.encode_deterministic(ident),
+            Field::Oneof(ref oneof) => oneof.encode_deterministic(ident),
+            Field::Group(ref group) => group.encode_deterministic(ident),
+            Field::Flatten(ref flatten) => flatten.encode_deterministic(ident),
         }
     }
 
@@ -107,6 +198,20 @@ impl Field {
             Field::Map(ref map) => map.merge(ident),
             Field::Oneof(ref oneof) => oneof.merge(ident),
             Field::Group(ref group) => group.merge(ident),
+            Field::Flatten(ref flatten) => flatten.merge(ident),
+        }
+    }
+
+    /// Returns an expression which evaluates to the result of merging a decoded value into the
+    /// field, in a message decoded from a borrowed buffer.
+    ///
+    /// Nested and flattened messages are merged through `prost::DecodeBorrowed`, so that they may
+    /// borrow from the buffer as well.
+    pub fn merge_borrowed(&self, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Message(ref message) => message.merge_borrowed(ident),
+            Field::Flatten(ref flatten) => flatten.merge_borrowed(ident),
+            _ => self.merge(ident),
         }
     }
 
@@ -118,6 +223,7 @@ impl Field {
             Field::Message(ref msg) => msg.encoded_len(ident),
             Field::Oneof(ref oneof) => oneof.encoded_len(ident),
             Field::Group(ref group) => group.encoded_len(ident),
+            Field::Flatten(ref flatten) => flatten.encoded_len(ident),
         }
     }
 
@@ -129,6 +235,7 @@ impl Field {
             Field::Map(ref map) => map.clear(ident),
             Field::Oneof(ref oneof) => oneof.clear(ident),
             Field::Group(ref group) => group.clear(ident),
+            Field::Flatten(ref flatten) => flatten.clear(ident),
         }
     }
 
@@ -164,6 +271,80 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the `prost::reflect::FieldInfo` of the field.
+    pub fn reflect_info(&self, name: &str) -> TokenStream {
+        let tags = self.tags();
+        let (kind, label) = match *self {
+            Field::Scalar(ref scalar) => (scalar.ty.reflect_kind(), scalar.reflect_label()),
+            Field::Message(ref message) => (quote!(Message), message.label.reflect_label()),
+            Field::Map(..) => (quote!(Map), quote!(::prost::reflect::FieldLabel::Repeated)),
+            Field::Oneof(..) => (
+                quote!(Oneof),
+                quote!(::prost::reflect::FieldLabel::Optional),
+            ),
+            Field::Group(ref group) => (quote!(Group), group.label.reflect_label()),
+            Field::Flatten(..) => (
+                quote!(Message),
+                quote!(::prost::reflect::FieldLabel::Singular),
+            ),
+        };
+        let kind = match *self {
+            Field::Scalar(..) => kind,
+            _ => quote!(::prost::reflect::FieldKind::#kind),
+        };
+        quote! {
+            ::prost::reflect::FieldInfo {
+                name: #name,
+                tags: &[#(#tags),*],
+                kind: #kind,
+                label: #label,
+            }
+        }
+    }
+
+    /// Returns an expression which evaluates to the optional `prost::reflect::Value` of the
+    /// field.
+    pub fn reflect_value(&self, ident: TokenStream) -> TokenStream {
+        let message = quote!(::prost::reflect::Value::Message(value));
+        match *self {
+            Field::Scalar(ref scalar) => scalar.reflect_value(ident),
+            Field::Message(ref message_field) if message_field.label == Label::Repeated => quote! {
+                ::core::option::Option::Some(::prost::reflect::Value::List(
+                    #ident.iter().map(|value| #message).collect(),
+                ))
+            },
+            Field::Group(ref group) if group.label == Label::Repeated => quote! {
+                ::core::option::Option::Some(::prost::reflect::Value::List(
+                    #ident.iter().map(|value| #message).collect(),
+                ))
+            },
+            Field::Message(..) | Field::Group(..) => quote!(#ident.as_ref().map(|value| #message)),
+            Field::Map(ref map) => {
+                let value = map.reflect_value(ident);
+                quote!(::core::option::Option::Some(#value))
+            }
+            Field::Oneof(..) => quote! {
+                #ident.as_ref().map(|value| {
+                    let (tag, value) = ::prost::reflect::ReflectOneof::reflect(value);
+                    ::prost::reflect::Value::Oneof(tag, ::prost::alloc::boxed::Box::new(value))
+                })
+            },
+            Field::Flatten(..) => quote! {
+                ::core::option::Option::Some(::prost::reflect::Value::Message(&#ident))
+            },
+        }
+    }
+
+    /// Returns an expression converting a reference `value` to the value of a oneof variant to a
+    /// `prost::reflect::Value`.
+    pub fn reflect_oneof_value(&self) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.ty.reflect_value(quote!(value)),
+            Field::Map(ref map) => map.reflect_value(quote!(*value)),
+            _ => quote!(::prost::reflect::Value::Message(value)),
+        }
+    }
+
     pub fn methods(&self, ident: &Ident) -> Option<TokenStream> {
         match *self {
             Field::Scalar(ref scalar) => scalar.methods(ident),
@@ -184,6 +365,15 @@ pub enum Label {
 }
 
 impl Label {
+    /// Returns the `prost::reflect::FieldLabel` of a message or group field.
+    fn reflect_label(self) -> TokenStream {
+        match self {
+            Label::Optional => quote!(::prost::reflect::FieldLabel::Optional),
+            Label::Required => quote!(::prost::reflect::FieldLabel::Required),
+            Label::Repeated => quote!(::prost::reflect::FieldLabel::Repeated),
+        }
+    }
+
     fn as_str(self) -> &'static str {
         match self {
             Label::Optional => "optional",
@@ -223,6 +413,18 @@ impl fmt::Display for Label {
     }
 }
 
+/// Returns `true` if the attributes mark the `prost::CachedSize` field of a message.
+pub fn is_cached_size(attrs: &[Attribute]) -> Result<bool, Error> {
+    let attrs = prost_attrs(attrs.to_vec());
+    if !attrs.iter().any(|attr| word_attr("cached_size", attr)) {
+        return Ok(false);
+    }
+    if attrs.len() > 1 {
+        bail!("invalid attributes for cached_size field: {:?}", attrs);
+    }
+    Ok(true)
+}
+
 /// Get the items belonging to the 'prost' list attribute, e.g. `#[prost(foo, bar="baz")]`.
 fn prost_attrs(attrs: Vec<Attribute>) -> Vec<Meta> {
     attrs
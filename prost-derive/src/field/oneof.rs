@@ -77,6 +77,15 @@ impl Field {
         }
     }
 
+    /// Returns a statement which encodes the oneof field deterministically.
+    pub fn encode_deterministic(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let Some(ref oneof) = #ident {
+                oneof.encode_deterministic(buf)
+            }
+        }
+    }
+
     /// Returns an expression which evaluates to the result of decoding the oneof field.
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
         let ty = &self.ty;
@@ -4,9 +4,11 @@ use std::fmt;
 use anyhow::{anyhow, bail, Error};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_str, Ident, Lit, LitByteStr, Meta, MetaList, MetaNameValue, NestedMeta, Path};
+use syn::{
+    parse_str, Ident, Lit, LitByteStr, Meta, MetaList, MetaNameValue, NestedMeta, Path, Type,
+};
 
-use crate::field::{bool_attr, set_option, tag_attr, Label};
+use crate::field::{bool_attr, set_bool, set_option, tag_attr, word_attr, Label};
 
 /// A scalar protobuf field.
 #[derive(Clone)]
@@ -14,6 +16,13 @@ pub struct Field {
     pub ty: Ty,
     pub kind: Kind,
     pub tag: u32,
+    /// The `prost::BytesCodec` transforming a `bytes` field on the wire.
+    pub codec: Option<Path>,
+    /// Whether the accessors of an enumeration field return `prost::OpenEnum`.
+    pub open: bool,
+    /// Whether a `string` or `bytes` field borrows its value from the decoded buffer, as a
+    /// `Cow<'a, str>` or a `&'a [u8]`.
+    pub borrowed: bool,
 }
 
 impl Field {
@@ -23,6 +32,9 @@ impl Field {
         let mut packed = None;
         let mut default = None;
         let mut tag = None;
+        let mut codec = None;
+        let mut open = None;
+        let mut borrowed = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -37,6 +49,12 @@ impl Field {
                 set_option(&mut label, l, "duplicate label attributes")?;
             } else if let Some(d) = DefaultValue::from_attr(attr)? {
                 set_option(&mut default, d, "duplicate default attributes")?;
+            } else if let Some(c) = codec_attr(attr)? {
+                set_option(&mut codec, c, "duplicate codec attributes")?;
+            } else if let Some(o) = bool_attr("open", attr)? {
+                set_option(&mut open, o, "duplicate open attributes")?;
+            } else if word_attr("borrowed", attr) {
+                set_bool(&mut borrowed, "duplicate borrowed attribute")?;
             } else {
                 unknown_attrs.push(attr);
             }
@@ -58,6 +76,20 @@ impl Field {
             None => bail!("missing tag attribute"),
         };
 
+        if codec.is_some() && !matches!(ty, Ty::Bytes(..)) {
+            bail!("codec attribute may only be applied to bytes fields");
+        }
+        let open = open.unwrap_or(false);
+        if open && !matches!(ty, Ty::Enumeration(..)) {
+            bail!("open attribute may only be applied to enumeration fields");
+        }
+        if borrowed && !matches!(ty, Ty::String | Ty::Bytes(BytesTy::Vec)) {
+            bail!("borrowed attribute may only be applied to string and bytes fields");
+        }
+        if borrowed && codec.is_some() {
+            bail!("borrowed attribute may not be applied to fields with a codec");
+        }
+
         let has_default = default.is_some();
         let default = default.map_or_else(
             || Ok(DefaultValue::new(&ty)),
@@ -86,11 +118,21 @@ impl Field {
             (Some(Label::Repeated), _, false) => Kind::Repeated,
         };
 
-        Ok(Some(Field { ty, kind, tag }))
+        Ok(Some(Field {
+            ty,
+            kind,
+            tag,
+            codec,
+            open,
+            borrowed,
+        }))
     }
 
     pub fn new_oneof(attrs: &[Meta]) -> Result<Option<Field>, Error> {
         if let Some(mut field) = Field::new(attrs, None)? {
+            if field.borrowed {
+                bail!("invalid borrowed attribute on oneof field");
+            }
             match field.kind {
                 Kind::Plain(default) => {
                     field.kind = Kind::Required(default);
@@ -105,21 +147,35 @@ impl Field {
         }
     }
 
-    pub fn encode(&self, ident: TokenStream) -> TokenStream {
+    /// Returns the path of the `prost::encoding` module encoding the field.
+    fn module(&self) -> TokenStream {
         let module = self.ty.module();
+        if self.borrowed {
+            quote!(borrowed::#module)
+        } else {
+            quote!(#module)
+        }
+    }
+
+    pub fn encode(&self, ident: TokenStream) -> TokenStream {
+        let module = self.module();
         let encode_fn = match self.kind {
             Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(encode),
             Kind::Repeated => quote!(encode_repeated),
             Kind::Packed => quote!(encode_packed),
         };
-        let encode_fn = quote!(::prost::encoding::#module::#encode_fn);
+        let encode_fn = match self.codec {
+            Some(ref codec) => quote!(::prost::encoding::codec::#encode_fn::<#codec, _, _>),
+            None => quote!(::prost::encoding::#module::#encode_fn),
+        };
         let tag = self.tag;
 
         match self.kind {
             Kind::Plain(ref default) => {
                 let default = default.typed();
+                let value = self.ty.comparable(&ident);
                 quote! {
-                    if #ident != #default {
+                    if #value != #default {
                         #encode_fn(#tag, &#ident, buf);
                     }
                 }
@@ -138,12 +194,15 @@ impl Field {
     /// Returns an expression which evaluates to the result of merging a decoded
     /// scalar value into the field.
     pub fn merge(&self, ident: TokenStream) -> TokenStream {
-        let module = self.ty.module();
+        let module = self.module();
         let merge_fn = match self.kind {
             Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(merge),
             Kind::Repeated | Kind::Packed => quote!(merge_repeated),
         };
-        let merge_fn = quote!(::prost::encoding::#module::#merge_fn);
+        let merge_fn = match self.codec {
+            Some(ref codec) => quote!(::prost::encoding::codec::#merge_fn::<#codec, _, _>),
+            None => quote!(::prost::encoding::#module::#merge_fn),
+        };
 
         match self.kind {
             Kind::Plain(..) | Kind::Required(..) | Kind::Repeated | Kind::Packed => quote! {
@@ -160,20 +219,24 @@ impl Field {
 
     /// Returns an expression which evaluates to the encoded length of the field.
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
-        let module = self.ty.module();
+        let module = self.module();
         let encoded_len_fn = match self.kind {
             Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..) => quote!(encoded_len),
             Kind::Repeated => quote!(encoded_len_repeated),
             Kind::Packed => quote!(encoded_len_packed),
         };
-        let encoded_len_fn = quote!(::prost::encoding::#module::#encoded_len_fn);
+        let encoded_len_fn = match self.codec {
+            Some(ref codec) => quote!(::prost::encoding::codec::#encoded_len_fn::<#codec, _>),
+            None => quote!(::prost::encoding::#module::#encoded_len_fn),
+        };
         let tag = self.tag;
 
         match self.kind {
             Kind::Plain(ref default) => {
                 let default = default.typed();
+                let value = self.ty.comparable(&ident);
                 quote! {
-                    if #ident != #default {
+                    if #value != #default {
                         #encoded_len_fn(#tag, &#ident)
                     } else {
                         0
@@ -189,11 +252,48 @@ impl Field {
         }
     }
 
+    /// Returns the `prost::reflect::FieldLabel` of the field.
+    pub fn reflect_label(&self) -> TokenStream {
+        match self.kind {
+            Kind::Plain(..) => quote!(::prost::reflect::FieldLabel::Singular),
+            Kind::Optional(..) => quote!(::prost::reflect::FieldLabel::Optional),
+            Kind::Required(..) => quote!(::prost::reflect::FieldLabel::Required),
+            Kind::Repeated | Kind::Packed => quote!(::prost::reflect::FieldLabel::Repeated),
+        }
+    }
+
+    /// Returns an expression which evaluates to the optional `prost::reflect::Value` of the
+    /// field.
+    pub fn reflect_value(&self, ident: TokenStream) -> TokenStream {
+        let value = self.ty.reflect_value(quote!(value));
+        match self.kind {
+            Kind::Plain(..) | Kind::Required(..) => quote! {
+                ::core::option::Option::Some({
+                    let value = &#ident;
+                    #value
+                })
+            },
+            Kind::Optional(..) => quote!(#ident.as_ref().map(|value| #value)),
+            Kind::Repeated | Kind::Packed => quote! {
+                ::core::option::Option::Some(::prost::reflect::Value::List(
+                    #ident.iter().map(|value| #value).collect(),
+                ))
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.kind {
+            Kind::Plain(ref default) | Kind::Required(ref default) if self.borrowed => {
+                let default = default.borrowed();
+                quote!(#ident = #default)
+            }
             Kind::Plain(ref default) | Kind::Required(ref default) => {
                 let default = default.typed();
                 match self.ty {
+                    Ty::Bytes(BytesTy::Custom(_)) => {
+                        quote!(#ident = ::core::default::Default::default())
+                    }
                     Ty::String | Ty::Bytes(..) => quote!(#ident.clear()),
                     _ => quote!(#ident = #default),
                 }
@@ -206,6 +306,7 @@ impl Field {
     /// Returns an expression which evaluates to the default value of the field.
     pub fn default(&self) -> TokenStream {
         match self.kind {
+            Kind::Plain(ref value) | Kind::Required(ref value) if self.borrowed => value.borrowed(),
             Kind::Plain(ref value) | Kind::Required(ref value) => value.owned(),
             Kind::Optional(_) => quote!(::core::option::Option::None),
             Kind::Repeated | Kind::Packed => quote!(::prost::alloc::vec::Vec::new()),
@@ -238,6 +339,8 @@ impl Field {
         let wrapper = self.debug_inner(quote!(Inner));
         let inner_ty = self.ty.rust_type();
         match self.kind {
+            // Borrowed fields are never enumerations, and are formatted as they are.
+            _ if self.borrowed => self.debug_inner(wrapper_name),
             Kind::Plain(_) | Kind::Required(_) => self.debug_inner(wrapper_name),
             Kind::Optional(_) => quote! {
                 struct #wrapper_name<'a>(&'a ::core::option::Option<#inner_ty>);
@@ -273,7 +376,71 @@ impl Field {
             ident_str = ident_str[2..].to_owned();
         }
 
-        if let Ty::Enumeration(ref ty) = self.ty {
+        if let (Ty::Enumeration(ref ty), true) = (&self.ty, self.open) {
+            let set = Ident::new(&format!("set_{}", ident_str), Span::call_site());
+            let set_doc = format!("Sets `{}` to the provided enum value.", ident_str);
+            let open_ty = quote!(::prost::OpenEnum<#ty>);
+            let to_open = quote! {
+                |value: i32| match #ty::from_i32(value) {
+                    ::core::option::Option::Some(known) => ::prost::OpenEnum::Known(known),
+                    ::core::option::Option::None => ::prost::OpenEnum::Unknown(value),
+                }
+            };
+            Some(match self.kind {
+                Kind::Plain(_) | Kind::Required(_) => {
+                    let get_doc = format!("Returns the enum value of `{}`.", ident_str);
+                    quote! {
+                        #[doc=#get_doc]
+                        pub fn #ident(&self) -> #open_ty {
+                            (#to_open)(self.#ident)
+                        }
+
+                        #[doc=#set_doc]
+                        pub fn #set(&mut self, value: impl ::core::convert::Into<#open_ty>) {
+                            self.#ident = value.into().to_i32();
+                        }
+                    }
+                }
+                Kind::Optional(ref default) => {
+                    let get_doc = format!(
+                        "Returns the enum value of `{}`, or the default if the field is unset.",
+                        ident_str,
+                    );
+                    quote! {
+                        #[doc=#get_doc]
+                        pub fn #ident(&self) -> #open_ty {
+                            (#to_open)(self.#ident.unwrap_or(#default as i32))
+                        }
+
+                        #[doc=#set_doc]
+                        pub fn #set(&mut self, value: impl ::core::convert::Into<#open_ty>) {
+                            self.#ident = ::core::option::Option::Some(value.into().to_i32());
+                        }
+                    }
+                }
+                Kind::Repeated | Kind::Packed => {
+                    let iter_doc = format!(
+                        "Returns an iterator which yields the enum values contained in `{}`.",
+                        ident_str,
+                    );
+                    let push = Ident::new(&format!("push_{}", ident_str), Span::call_site());
+                    let push_doc = format!("Appends the provided enum value to `{}`.", ident_str);
+                    quote! {
+                        #[doc=#iter_doc]
+                        pub fn #ident(&self) -> ::core::iter::Map<
+                            ::core::iter::Cloned<::core::slice::Iter<i32>>,
+                            fn(i32) -> #open_ty,
+                        > {
+                            self.#ident.iter().cloned().map(#to_open)
+                        }
+                        #[doc=#push_doc]
+                        pub fn #push(&mut self, value: impl ::core::convert::Into<#open_ty>) {
+                            self.#ident.push(value.into().to_i32());
+                        }
+                    }
+                }
+            })
+        } else if let Ty::Enumeration(ref ty) = self.ty {
             let set = Ident::new(&format!("set_{}", ident_str), Span::call_site());
             let set_doc = format!("Sets `{}` to the provided enum value.", ident_str);
             Some(match self.kind {
@@ -340,6 +507,10 @@ impl Field {
 
             let match_some = if self.ty.is_numeric() {
                 quote!(::core::option::Option::Some(val) => val,)
+            } else if let Ty::Bytes(BytesTy::Custom(_)) = self.ty {
+                quote! {
+                    ::core::option::Option::Some(ref val) => ::core::convert::AsRef::<[u8]>::as_ref(val),
+                }
             } else {
                 quote!(::core::option::Option::Some(ref val) => &val[..],)
             };
@@ -389,6 +560,8 @@ pub enum Ty {
 pub enum BytesTy {
     Vec,
     Bytes,
+    /// A user provided type implementing `prost::BytesAdapter`, declared as `custom(path::Ty)`.
+    Custom(Type),
 }
 
 impl BytesTy {
@@ -396,7 +569,13 @@ impl BytesTy {
         match s {
             "vec" => Ok(BytesTy::Vec),
             "bytes" => Ok(BytesTy::Bytes),
-            _ => bail!("Invalid bytes type: {}", s),
+            _ => match s.strip_prefix("custom(").and_then(|s| s.strip_suffix(')')) {
+                Some(ty) => match parse_str::<Type>(ty) {
+                    Ok(ty) => Ok(BytesTy::Custom(ty)),
+                    Err(_) => bail!("Invalid custom bytes type: {}", ty),
+                },
+                None => bail!("Invalid bytes type: {}", s),
+            },
         }
     }
 
@@ -404,10 +583,25 @@ impl BytesTy {
         match self {
             BytesTy::Vec => quote! { ::prost::alloc::vec::Vec<u8> },
             BytesTy::Bytes => quote! { ::prost::bytes::Bytes },
+            BytesTy::Custom(ty) => quote! { #ty },
         }
     }
 }
 
+/// Parses a `codec = "path::to::Codec"` attribute.
+fn codec_attr(attr: &Meta) -> Result<Option<Path>, Error> {
+    if !attr.path().is_ident("codec") {
+        return Ok(None);
+    }
+    match *attr {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(ref lit),
+            ..
+        }) => Ok(Some(parse_str::<Path>(&lit.value())?)),
+        _ => bail!("invalid codec attribute: {:?}", attr),
+    }
+}
+
 impl Ty {
     pub fn from_attr(attr: &Meta) -> Result<Option<Ty>, Error> {
         let ty = match *attr {
@@ -547,6 +741,16 @@ impl Ty {
         }
     }
 
+    /// Returns an expression of the field `ident` which can be compared with its default value.
+    fn comparable(&self, ident: &TokenStream) -> TokenStream {
+        match self {
+            Ty::Bytes(BytesTy::Custom(_)) => {
+                quote!(::core::convert::AsRef::<[u8]>::as_ref(&#ident))
+            }
+            _ => ident.clone(),
+        }
+    }
+
     pub fn module(&self) -> Ident {
         match *self {
             Ty::Enumeration(..) => Ident::new("int32", Span::call_site()),
@@ -558,6 +762,51 @@ impl Ty {
     pub fn is_numeric(&self) -> bool {
         !matches!(self, Ty::String | Ty::Bytes(..))
     }
+
+    /// Returns the `prost::reflect::FieldKind` of the type.
+    pub fn reflect_kind(&self) -> TokenStream {
+        let kind = match *self {
+            Ty::Double => "Double",
+            Ty::Float => "Float",
+            Ty::Int32 => "Int32",
+            Ty::Int64 => "Int64",
+            Ty::Uint32 => "Uint32",
+            Ty::Uint64 => "Uint64",
+            Ty::Sint32 => "Sint32",
+            Ty::Sint64 => "Sint64",
+            Ty::Fixed32 => "Fixed32",
+            Ty::Fixed64 => "Fixed64",
+            Ty::Sfixed32 => "Sfixed32",
+            Ty::Sfixed64 => "Sfixed64",
+            Ty::Bool => "Bool",
+            Ty::String => "String",
+            Ty::Bytes(..) => "Bytes",
+            Ty::Enumeration(..) => "Enum",
+        };
+        let kind = Ident::new(kind, Span::call_site());
+        quote!(::prost::reflect::FieldKind::#kind)
+    }
+
+    /// Returns an expression converting a reference to a value of the type to a
+    /// `prost::reflect::Value`.
+    pub fn reflect_value(&self, value: TokenStream) -> TokenStream {
+        match *self {
+            Ty::Double => quote!(::prost::reflect::Value::F64(*#value)),
+            Ty::Float => quote!(::prost::reflect::Value::F32(*#value)),
+            Ty::Int32 | Ty::Sint32 | Ty::Sfixed32 => quote!(::prost::reflect::Value::I32(*#value)),
+            Ty::Int64 | Ty::Sint64 | Ty::Sfixed64 => quote!(::prost::reflect::Value::I64(*#value)),
+            Ty::Uint32 | Ty::Fixed32 => quote!(::prost::reflect::Value::U32(*#value)),
+            Ty::Uint64 | Ty::Fixed64 => quote!(::prost::reflect::Value::U64(*#value)),
+            Ty::Bool => quote!(::prost::reflect::Value::Bool(*#value)),
+            Ty::String => quote! {
+                ::prost::reflect::Value::String(::core::convert::AsRef::<str>::as_ref(#value))
+            },
+            Ty::Bytes(..) => quote! {
+                ::prost::reflect::Value::Bytes(::core::convert::AsRef::<[u8]>::as_ref(#value))
+            },
+            Ty::Enumeration(..) => quote!(::prost::reflect::Value::Enum(*#value)),
+        }
+    }
 }
 
 impl fmt::Debug for Ty {
@@ -649,9 +898,7 @@ impl DefaultValue {
 
             Lit::Bool(ref lit) if *ty == Ty::Bool => DefaultValue::Bool(lit.value),
             Lit::Str(ref lit) if *ty == Ty::String => DefaultValue::String(lit.value()),
-            Lit::ByteStr(ref lit)
-                if *ty == Ty::Bytes(BytesTy::Bytes) || *ty == Ty::Bytes(BytesTy::Vec) =>
-            {
+            Lit::ByteStr(ref lit) if matches!(*ty, Ty::Bytes(..)) => {
                 DefaultValue::Bytes(lit.value())
             }
 
@@ -781,6 +1028,16 @@ impl DefaultValue {
         }
     }
 
+    /// Returns the value of a borrowed `string` or `bytes` field.
+    pub fn borrowed(&self) -> TokenStream {
+        match *self {
+            DefaultValue::String(ref value) => {
+                quote!(::prost::alloc::borrow::Cow::Borrowed(#value))
+            }
+            ref other => other.typed(),
+        }
+    }
+
     pub fn typed(&self) -> TokenStream {
         if let DefaultValue::Enumeration(_) = *self {
             quote!(#self as i32)
@@ -18,20 +18,13 @@ use syn::{
 mod field;
 use crate::field::Field;
 
-fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
-    let input: DeriveInput = syn::parse(input)?;
-
-    let ident = input.ident;
-
-    let variant_data = match input.data {
-        Data::Struct(variant_data) => variant_data,
-        Data::Enum(..) => bail!("Message can not be derived for an enum"),
-        Data::Union(..) => bail!("Message can not be derived for a union"),
-    };
-
-    let generics = &input.generics;
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-
+/// Parses the fields of a message struct, in declaration order, and the `#[prost(cached_size)]`
+/// field caching its encoded length, if any.
+#[allow(clippy::type_complexity)]
+fn message_fields(
+    ident: &Ident,
+    variant_data: DataStruct,
+) -> Result<(Vec<(Ident, Field)>, Option<Ident>), Error> {
     let fields = match variant_data {
         DataStruct {
             fields: Fields::Named(FieldsNamed { named: fields, .. }),
@@ -51,17 +44,45 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     };
 
     let mut next_tag: u32 = 1;
-    let mut fields = fields
+    let mut cached_size = None;
+    let fields = fields
         .into_iter()
         .enumerate()
         .flat_map(|(idx, field)| {
             let field_ident = field
                 .ident
                 .unwrap_or_else(|| Ident::new(&idx.to_string(), Span::call_site()));
+            match field::is_cached_size(&field.attrs) {
+                Ok(false) => (),
+                Ok(true) if cached_size.is_none() => {
+                    cached_size = Some(field_ident);
+                    return None;
+                }
+                Ok(true) => {
+                    return Some(Err(anyhow::anyhow!(
+                        "message {} has multiple cached_size fields",
+                        ident
+                    )))
+                }
+                Err(err) => {
+                    return Some(Err(err.context(format!(
+                        "invalid message field {}.{}",
+                        ident, field_ident
+                    ))))
+                }
+            }
             match Field::new(field.attrs, Some(next_tag)) {
-                Ok(Some(field)) => {
-                    next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
-                    Some(Ok((field_ident, field)))
+                Ok(Some(mut parsed)) => {
+                    if let Field::Flatten(ref mut flatten) = parsed {
+                        flatten.ty = Some(field.ty);
+                    }
+                    next_tag = parsed
+                        .tags()
+                        .iter()
+                        .max()
+                        .map(|t| t + 1)
+                        .unwrap_or(next_tag);
+                    Some(Ok((field_ident, parsed)))
                 }
                 Ok(None) => None,
                 Err(err) => Some(Err(
@@ -70,6 +91,206 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             }
         })
         .collect::<Result<Vec<_>, _>>()?;
+    Ok((fields, cached_size))
+}
+
+/// The halves of `Message` implemented by a derive.
+#[derive(Clone, Copy, PartialEq)]
+enum Halves {
+    Both,
+    Encode,
+    Decode,
+}
+
+impl Halves {
+    fn name(self) -> &'static str {
+        match self {
+            Halves::Both => "Message",
+            Halves::Encode => "EncodeMessage",
+            Halves::Decode => "DecodeMessage",
+        }
+    }
+}
+
+/// Returns the body of `is_singular_field`, the `singular_field_id` method if the message has
+/// oneofs, and the `for_each_required_field` method if the message has required fields.
+///
+/// The fields of flattened messages are checked by the flattened messages, through
+/// `decode_trait`.
+fn field_tags(
+    fields: &[(Ident, Field)],
+    decode_trait: &proc_macro2::TokenStream,
+) -> (
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+) {
+    let flattened = fields
+        .iter()
+        .filter_map(|&(_, ref field)| match *field {
+            Field::Flatten(ref flatten) => Some(flatten),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let singular_tags = fields
+        .iter()
+        .flat_map(|&(_, ref field)| field.singular_tags())
+        .collect::<Vec<_>>();
+    let mut is_singular_field = flattened
+        .iter()
+        .map(|flatten| {
+            let ty = &flatten.ty;
+            quote!(<#ty as #decode_trait>::is_singular_field(tag))
+        })
+        .collect::<Vec<_>>();
+    if !singular_tags.is_empty() {
+        is_singular_field.insert(0, quote!(matches!(tag, #(#singular_tags)|*)));
+    }
+    let is_singular_field = if is_singular_field.is_empty() {
+        quote!(false)
+    } else {
+        quote!(#(#is_singular_field)||*)
+    };
+
+    // The members of a oneof are identified by the first tag of the oneof, with the high bit set
+    // so that it isn't a valid tag.
+    let singular_field_ids = fields
+        .iter()
+        .filter_map(|&(_, ref field)| match *field {
+            Field::Oneof(ref oneof) => {
+                let tags = &oneof.tags;
+                let id = tags[0] | 0x8000_0000;
+                Some(quote!(#(#tags)|* => #id,))
+            }
+            Field::Flatten(ref flatten) => {
+                let tags = &flatten.tags;
+                let ty = &flatten.ty;
+                Some(quote!(#(#tags)|* => <#ty as #decode_trait>::singular_field_id(tag),))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let singular_field_id = if singular_field_ids.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn singular_field_id(tag: u32) -> u32 {
+                match tag {
+                    #(#singular_field_ids)*
+                    _ => tag,
+                }
+            }
+        }
+    };
+
+    let mut required_fields = fields
+        .iter()
+        .filter_map(|&(ref field_ident, ref field)| {
+            let tag = field.required_tag()?;
+            Some(quote!(f(#tag, stringify!(#field_ident));))
+        })
+        .collect::<Vec<_>>();
+    required_fields.extend(flattened.iter().map(|flatten| {
+        let ty = &flatten.ty;
+        quote!(<#ty as #decode_trait>::for_each_required_field(f);)
+    }));
+    let required_fields = if required_fields.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn for_each_required_field(f: &mut dyn FnMut(u32, &'static str)) {
+                #(#required_fields)*
+            }
+        }
+    };
+
+    (is_singular_field, singular_field_id, required_fields)
+}
+
+/// Returns the match arms merging a decoded value into each field, with `merge` returning the
+/// expression which merges the value.
+fn merge_arms(
+    fields: &[(Ident, Field)],
+    merge: fn(&Field, proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .map(|&(ref field_ident, ref field)| {
+            let merge = merge(field, quote!(value));
+            let tags = field.tags().into_iter().map(|tag| quote!(#tag));
+            let tags = Itertools::intersperse(tags, quote!(|));
+            // Elements are only appended once decoded, so the length of a repeated field is the
+            // index of the element which failed to decode.
+            let push = if field.is_repeated() {
+                quote!(error.push_element(STRUCT_NAME, stringify!(#field_ident), value.len()))
+            } else {
+                quote!(error.push(STRUCT_NAME, stringify!(#field_ident)))
+            };
+
+            quote! {
+                #(#tags)* => {
+                    let mut value = &mut self.#field_ident;
+                    #merge.map_err(|mut error| {
+                        #push;
+                        error
+                    })
+                },
+            }
+        })
+        .collect()
+}
+
+/// Returns the `Default` implementation of a message, with the fields set to their default values.
+fn default_impl(
+    ident: &Ident,
+    generics: &syn::Generics,
+    fields: &[(Ident, Field)],
+    cached_size: &Option<Ident>,
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let default = fields.iter().map(|&(ref field_ident, ref field)| {
+        let value = field.default();
+        quote!(#field_ident: #value,)
+    });
+    let cached_size = cached_size
+        .as_ref()
+        .map(|cache| quote!(#cache: ::core::default::Default::default(),));
+    quote! {
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                #ident {
+                    #(#default)*
+                    #cached_size
+                }
+            }
+        }
+    }
+}
+
+fn try_message(input: TokenStream, halves: Halves) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse(input)?;
+
+    let ident = input.ident;
+
+    let variant_data = match input.data {
+        Data::Struct(variant_data) => variant_data,
+        Data::Enum(..) => bail!("{} can not be derived for an enum", halves.name()),
+        Data::Union(..) => bail!("{} can not be derived for a union", halves.name()),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (mut fields, cached_size) = message_fields(&ident, variant_data)?;
+    if halves != Halves::Encode && fields.iter().any(|&(_, ref field)| field.is_borrowed()) {
+        bail!(
+            "{} can not be derived for message {} with borrowed fields, derive EncodeMessage and \
+             DecodeBorrowed instead",
+            halves.name(),
+            ident
+        );
+    }
 
     // We want Debug to be in declaration order
     let unsorted_fields = fields.clone();
@@ -92,30 +313,55 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         bail!("message {} has fields with duplicate tags", ident);
     }
 
+    let (is_singular_field, singular_field_id, required_fields) =
+        field_tags(&fields, &quote!(::prost::DecodeMessage));
+
+    // The tags of a flattened message are checked against those declared by its field when the
+    // message is compiled, as evaluating the constant fails if they differ.
+    let flattened_tags = fields
+        .iter()
+        .filter_map(|&(_, ref field)| match *field {
+            Field::Flatten(ref flatten) => Some(flatten.tags_match()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let (check_flattened_tags, flattened_tags_const) = if flattened_tags.is_empty() {
+        (quote!(), quote!())
+    } else {
+        (
+            quote!(let () = Self::__PROST_FLATTENED_TAGS;),
+            quote! {
+                const __PROST_FLATTENED_TAGS: () = [()][!(#(#flattened_tags)&&*) as usize];
+            },
+        )
+    };
+    let tags_impl = quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #[doc(hidden)]
+            pub const __PROST_TAGS: &'static [u32] = &[#(#tags),*];
+
+            #flattened_tags_const
+        }
+    };
+
     let encoded_len = fields
         .iter()
         .map(|&(ref field_ident, ref field)| field.encoded_len(quote!(self.#field_ident)));
 
-    let encode = fields
+    let encode_deterministic = fields
         .iter()
-        .map(|&(ref field_ident, ref field)| field.encode(quote!(self.#field_ident)));
+        .map(|&(ref field_ident, ref field)| field.encode_deterministic(quote!(self.#field_ident)));
 
-    let merge = fields.iter().map(|&(ref field_ident, ref field)| {
-        let merge = field.merge(quote!(value));
-        let tags = field.tags().into_iter().map(|tag| quote!(#tag));
-        let tags = Itertools::intersperse(tags, quote!(|));
-
-        quote! {
-            #(#tags)* => {
-                let mut value = &mut self.#field_ident;
-                #merge.map_err(|mut error| {
-                    error.push(STRUCT_NAME, stringify!(#field_ident));
-                    error
-                })
-            },
+    let encode = fields.iter().map(|&(ref field_ident, ref field)| {
+        if cached_size.is_some() {
+            field.encode_cached(quote!(self.#field_ident))
+        } else {
+            field.encode(quote!(self.#field_ident))
         }
     });
 
+    let merge = merge_arms(&fields, Field::merge);
+
     let struct_name = if fields.is_empty() {
         quote!()
     } else {
@@ -131,10 +377,97 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         .iter()
         .map(|&(ref field_ident, ref field)| field.clear(quote!(self.#field_ident)));
 
-    let default = fields.iter().map(|&(ref field_ident, ref field)| {
-        let value = field.default();
-        quote!(#field_ident: #value,)
-    });
+    // Repeated message fields keep their elements for reuse when the message is replaced.
+    let reused_fields = fields
+        .iter()
+        .filter(|&&(_, ref field)| field.is_repeated_message())
+        .collect::<Vec<_>>();
+    let replace_from = if reused_fields.is_empty() {
+        quote!()
+    } else {
+        let num_reused = reused_fields.len();
+        let clear_others = fields
+            .iter()
+            .filter(|&&(_, ref field)| !field.is_repeated_message())
+            .map(|&(ref field_ident, ref field)| field.clear(quote!(self.#field_ident)));
+        let replace =
+            reused_fields
+                .iter()
+                .enumerate()
+                .map(|(idx, &&(ref field_ident, ref field))| {
+                    let tag = field.tags()[0];
+                    quote! {
+                        #tag => ::prost::encoding::message::replace_repeated(
+                            wire_type,
+                            &mut self.#field_ident,
+                            &mut reused[#idx],
+                            buf,
+                            ctx.clone(),
+                        )
+                        .map_err(|mut error| {
+                            let index = reused[#idx];
+                            error.push_element(STRUCT_NAME, stringify!(#field_ident), index);
+                            error
+                        })?,
+                    }
+                });
+        let truncate = reused_fields
+            .iter()
+            .enumerate()
+            .map(|(idx, &&(ref field_ident, _))| quote!(self.#field_ident.truncate(reused[#idx]);));
+        quote! {
+            fn replace_from<B>(
+                &mut self,
+                buf: &mut B,
+                limit: usize,
+                ctx: ::prost::encoding::DecodeContext,
+            ) -> ::core::result::Result<(), ::prost::DecodeError>
+            where B: ::prost::bytes::Buf {
+                #struct_name
+                #(#clear_others;)*
+                let mut reused = [0usize; #num_reused];
+                while buf.remaining() > limit {
+                    let (tag, wire_type) = match ctx.decode_key(buf)? {
+                        ::core::option::Option::Some(key) => key,
+                        ::core::option::Option::None => continue,
+                    };
+                    match tag {
+                        #(#replace)*
+                        _ => ::prost::DecodeMessage::merge_field(self, tag, wire_type, buf, ctx.clone())?,
+                    }
+                }
+                ::prost::encoding::check_limit(buf, limit)?;
+                #(#truncate)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+
+    // The encoded length is stored in the cache whenever it's computed, and nested messages
+    // caching their lengths are encoded with the cached lengths.
+    let (encoded_len, cached_size_methods) = match cached_size {
+        Some(ref cache) => (
+            quote! {
+                let len = 0 #(+ #encoded_len)*;
+                self.#cache.set(len);
+                len
+            },
+            quote! {
+                fn cached_encoded_len(&self) -> usize {
+                    self.#cache
+                        .get()
+                        .unwrap_or_else(|| ::prost::EncodeMessage::encoded_len(self))
+                }
+
+                fn has_cached_size() -> bool {
+                    true
+                }
+            },
+        ),
+        None => (quote!(0 #(+ #encoded_len)*), quote!()),
+    };
+
+    let default_impl = default_impl(&ident, generics, &fields, &cached_size);
 
     let methods = fields
         .iter()
@@ -171,22 +504,171 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(f.debug_tuple(stringify!(#ident)))
     };
 
+    let encode_methods = quote! {
+        #[allow(unused_variables)]
+        fn encode_raw<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
+            #check_flattened_tags
+            #(#encode)*
+        }
+
+        #[allow(unused_variables)]
+        fn encode_raw_deterministic<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
+            #check_flattened_tags
+            #(#encode_deterministic)*
+        }
+
+        #[inline]
+        fn encoded_len(&self) -> usize {
+            #encoded_len
+        }
+
+        #cached_size_methods
+    };
+
+    let decode_methods = quote! {
+        #[allow(unused_variables)]
+        fn merge_field<B>(
+            &mut self,
+            tag: u32,
+            wire_type: ::prost::encoding::WireType,
+            buf: &mut B,
+            ctx: ::prost::encoding::DecodeContext,
+        ) -> ::core::result::Result<(), ::prost::DecodeError>
+        where B: ::prost::bytes::Buf {
+            #check_flattened_tags
+            #struct_name
+            match tag {
+                #(#merge)*
+                _ => ::prost::encoding::skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+
+        #replace_from
+
+        #[allow(unused_variables)]
+        fn is_singular_field(tag: u32) -> bool {
+            #is_singular_field
+        }
+
+        #singular_field_id
+
+        #required_fields
+
+        fn clear(&mut self) {
+            #(#clear;)*
+        }
+    };
+
+    let message_impl = match halves {
+        Halves::Both => quote! {
+            impl #impl_generics ::prost::EncodeMessage for #ident #ty_generics #where_clause {
+                #encode_methods
+            }
+
+            impl #impl_generics ::prost::DecodeMessage for #ident #ty_generics #where_clause {
+                #decode_methods
+            }
+
+            #default_impl
+        },
+        Halves::Encode => quote! {
+            impl #impl_generics ::prost::EncodeMessage for #ident #ty_generics #where_clause {
+                #encode_methods
+            }
+        },
+        Halves::Decode => quote! {
+            impl #impl_generics ::prost::DecodeMessage for #ident #ty_generics #where_clause {
+                #decode_methods
+            }
+
+            #default_impl
+        },
+    };
+
     let expanded = quote! {
-        impl #impl_generics ::prost::Message for #ident #ty_generics #where_clause {
-            #[allow(unused_variables)]
-            fn encode_raw<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
-                #(#encode)*
+        #message_impl
+
+        #tags_impl
+
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                let mut builder = #debug_builder;
+                #(#debugs;)*
+                builder.finish()
             }
+        }
+
+        #methods
+    };
+
+    Ok(expanded.into())
+}
+
+#[proc_macro_derive(Message, attributes(prost))]
+pub fn message(input: TokenStream) -> TokenStream {
+    try_message(input, Halves::Both).unwrap()
+}
+
+#[proc_macro_derive(EncodeMessage, attributes(prost))]
+pub fn encode_message(input: TokenStream) -> TokenStream {
+    try_message(input, Halves::Encode).unwrap()
+}
+
+#[proc_macro_derive(DecodeMessage, attributes(prost))]
+pub fn decode_message(input: TokenStream) -> TokenStream {
+    try_message(input, Halves::Decode).unwrap()
+}
+
+fn try_decode_borrowed(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse(input)?;
+
+    let ident = input.ident;
+
+    let variant_data = match input.data {
+        Data::Struct(variant_data) => variant_data,
+        Data::Enum(..) => bail!("DecodeBorrowed can not be derived for an enum"),
+        Data::Union(..) => bail!("DecodeBorrowed can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // The first lifetime parameter of the message is the lifetime of the decoded buffer.
+    let lifetime = match generics.lifetimes().next() {
+        Some(def) => &def.lifetime,
+        None => bail!(
+            "DecodeBorrowed can only be derived for a message with a lifetime parameter, \
+             derive Message for message {} instead",
+            ident
+        ),
+    };
+
+    let (fields, cached_size) = message_fields(&ident, variant_data)?;
+
+    let (is_singular_field, singular_field_id, required_fields) =
+        field_tags(&fields, &quote!(::prost::DecodeBorrowed<#lifetime>));
+    let merge = merge_arms(&fields, Field::merge_borrowed);
+
+    let struct_name = if fields.is_empty() {
+        quote!()
+    } else {
+        quote!(
+            const STRUCT_NAME: &'static str = stringify!(#ident);
+        )
+    };
 
+    let default_impl = default_impl(&ident, generics, &fields, &cached_size);
+
+    let expanded = quote! {
+        impl #impl_generics ::prost::DecodeBorrowed<#lifetime> for #ident #ty_generics #where_clause {
             #[allow(unused_variables)]
-            fn merge_field<B>(
+            fn merge_field_borrowed(
                 &mut self,
                 tag: u32,
                 wire_type: ::prost::encoding::WireType,
-                buf: &mut B,
+                buf: &mut &#lifetime [u8],
                 ctx: ::prost::encoding::DecodeContext,
-            ) -> ::core::result::Result<(), ::prost::DecodeError>
-            where B: ::prost::bytes::Buf {
+            ) -> ::core::result::Result<(), ::prost::DecodeError> {
                 #struct_name
                 match tag {
                     #(#merge)*
@@ -194,41 +676,86 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
-            #[inline]
-            fn encoded_len(&self) -> usize {
-                0 #(+ #encoded_len)*
+            #[allow(unused_variables)]
+            fn is_singular_field(tag: u32) -> bool {
+                #is_singular_field
             }
 
-            fn clear(&mut self) {
-                #(#clear;)*
-            }
+            #singular_field_id
+
+            #required_fields
         }
 
-        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
-            fn default() -> Self {
-                #ident {
-                    #(#default)*
-                }
+        #default_impl
+    };
+
+    Ok(expanded.into())
+}
+
+#[proc_macro_derive(DecodeBorrowed, attributes(prost))]
+pub fn decode_borrowed(input: TokenStream) -> TokenStream {
+    try_decode_borrowed(input).unwrap()
+}
+
+fn try_reflect_message(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse(input)?;
+
+    let ident = input.ident;
+
+    let variant_data = match input.data {
+        Data::Struct(variant_data) => variant_data,
+        Data::Enum(..) => bail!("ReflectMessage can not be derived for an enum"),
+        Data::Union(..) => bail!("ReflectMessage can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (fields, _) = message_fields(&ident, variant_data)?;
+    let names = fields
+        .iter()
+        .map(|&(ref field_ident, _)| field_ident.to_string().trim_start_matches("r#").to_string())
+        .collect::<Vec<_>>();
+
+    let infos = fields
+        .iter()
+        .zip(&names)
+        .map(|(&(_, ref field), name)| field.reflect_info(name));
+
+    let values = fields
+        .iter()
+        .zip(&names)
+        .map(|(&(ref field_ident, ref field), name)| {
+            let value = field.reflect_value(quote!(self.#field_ident));
+            quote!(#name => #value,)
+        });
+
+    let expanded = quote! {
+        impl #impl_generics ::prost::reflect::ReflectMessage for #ident #ty_generics #where_clause {
+            fn message_name(&self) -> &'static str {
+                stringify!(#ident)
             }
-        }
 
-        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
-            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                let mut builder = #debug_builder;
-                #(#debugs;)*
-                builder.finish()
+            fn fields(&self) -> &'static [::prost::reflect::FieldInfo] {
+                &[#(#infos),*]
             }
-        }
 
-        #methods
+            #[allow(unused_variables)]
+            fn get(&self, name: &str) -> ::core::option::Option<::prost::reflect::Value<'_>> {
+                match name {
+                    #(#values)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
     };
 
     Ok(expanded.into())
 }
 
-#[proc_macro_derive(Message, attributes(prost))]
-pub fn message(input: TokenStream) -> TokenStream {
-    try_message(input).unwrap()
+#[proc_macro_derive(ReflectMessage, attributes(prost))]
+pub fn reflect_message(input: TokenStream) -> TokenStream {
+    try_reflect_message(input).unwrap()
 }
 
 fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
@@ -325,21 +852,10 @@ pub fn enumeration(input: TokenStream) -> TokenStream {
     try_enumeration(input).unwrap()
 }
 
-fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
-    let input: DeriveInput = syn::parse(input)?;
-
-    let ident = input.ident;
-
-    let variants = match input.data {
-        Data::Enum(DataEnum { variants, .. }) => variants,
-        Data::Struct(..) => bail!("Oneof can not be derived for a struct"),
-        Data::Union(..) => bail!("Oneof can not be derived for a union"),
-    };
-
-    let generics = &input.generics;
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-
-    // Map the variants into 'fields'.
+/// Parses the variants of a oneof enum.
+fn oneof_fields(
+    variants: Punctuated<Variant, syn::token::Comma>,
+) -> Result<Vec<(Ident, Field)>, Error> {
     let mut fields: Vec<(Ident, Field)> = Vec::new();
     for Variant {
         attrs,
@@ -363,6 +879,24 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
             None => bail!("invalid oneof variant: oneof variants may not be ignored"),
         }
     }
+    Ok(fields)
+}
+
+fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse(input)?;
+
+    let ident = input.ident;
+
+    let variants = match input.data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        Data::Struct(..) => bail!("Oneof can not be derived for a struct"),
+        Data::Union(..) => bail!("Oneof can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = oneof_fields(variants)?;
 
     let mut tags = fields
         .iter()
@@ -388,6 +922,11 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(#ident::#variant_ident(ref value) => { #encode })
     });
 
+    let encode_deterministic = fields.iter().map(|&(ref variant_ident, ref field)| {
+        let encode = field.encode_deterministic(quote!(*value));
+        quote!(#ident::#variant_ident(ref value) => { #encode })
+    });
+
     let merge = fields.iter().map(|&(ref variant_ident, ref field)| {
         let tag = field.tags()[0];
         let merge = field.merge(quote!(value));
@@ -430,6 +969,12 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
+            pub fn encode_deterministic<B>(&self, buf: &mut B) where B: ::prost::bytes::BufMut {
+                match *self {
+                    #(#encode_deterministic,)*
+                }
+            }
+
             pub fn merge<B>(
                 field: &mut ::core::option::Option<#ident #ty_generics>,
                 tag: u32,
@@ -468,3 +1013,42 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
 pub fn oneof(input: TokenStream) -> TokenStream {
     try_oneof(input).unwrap()
 }
+
+fn try_reflect_oneof(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse(input)?;
+
+    let ident = input.ident;
+
+    let variants = match input.data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        Data::Struct(..) => bail!("ReflectOneof can not be derived for a struct"),
+        Data::Union(..) => bail!("ReflectOneof can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = oneof_fields(variants)?;
+    let reflect = fields.iter().map(|&(ref variant_ident, ref field)| {
+        let tag = field.tags()[0];
+        let value = field.reflect_oneof_value();
+        quote!(#ident::#variant_ident(ref value) => (#tag, #value))
+    });
+
+    let expanded = quote! {
+        impl #impl_generics ::prost::reflect::ReflectOneof for #ident #ty_generics #where_clause {
+            fn reflect(&self) -> (u32, ::prost::reflect::Value<'_>) {
+                match *self {
+                    #(#reflect,)*
+                }
+            }
+        }
+    };
+
+    Ok(expanded.into())
+}
+
+#[proc_macro_derive(ReflectOneof, attributes(prost))]
+pub fn reflect_oneof(input: TokenStream) -> TokenStream {
+    try_reflect_oneof(input).unwrap()
+}
@@ -0,0 +1,341 @@
+//! Anonymization of encoded messages.
+
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::bytes::Buf;
+use prost::encoding::{
+    decode_varint, encode_key, encode_varint, skip_field, DecodeContext, WireType,
+};
+use prost::DecodeError;
+
+use crate::field_descriptor_proto::Type;
+use crate::walk::{length_delimited, trim_dot, Messages, Rewrite};
+use crate::{FieldDescriptorProto, FileDescriptorSet};
+
+/// The value an [`Anonymizer`] puts in place of the value of a field.
+///
+/// Placeholders preserve the type of the field, so anonymized payloads still decode as the same
+/// message type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    /// The default value of the field type: zero, `false`, an empty string or an empty message.
+    Zero,
+    /// A fixed string, for `string` and `bytes` fields; fields of other types are zeroed.
+    Fixed(String),
+    /// A hash of the original value, so that equal values remain equal after anonymization:
+    /// hexadecimal digits for `string` fields, eight bytes for `bytes` fields, and a non-negative
+    /// number for numeric fields. Message fields are emptied.
+    ///
+    /// The hash (64-bit FNV-1a) is stable, but not cryptographic: short or guessable values can
+    /// be recovered from it.
+    Hash,
+}
+
+/// Replaces the values of selected fields of encoded messages with placeholders.
+///
+/// The anonymizer walks a payload with the message descriptors of a `FileDescriptorSet`, such as
+/// the one written by `prost_build::Config::file_descriptor_set_path`, so payloads can be
+/// sanitized for bug reports and test corpora without the generated message types. Fields are
+/// selected by their fully qualified name (e.g. `acme.User.email`), and are replaced wherever
+/// their message appears, including in nested messages, groups and map entries. Other fields,
+/// including the fields unknown to the descriptors, are copied unchanged.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let file_descriptor_set = FileDescriptorSet::decode(&descriptor_bytes[..])?;
+///
+/// let mut anonymizer = Anonymizer::new(&file_descriptor_set);
+/// anonymizer
+///     .field("acme.User.email", Placeholder::Fixed("user@example.com".to_string()))
+///     .field("acme.User.id", Placeholder::Hash);
+/// let sanitized = anonymizer.anonymize("acme.User", &payload)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Anonymizer<'a> {
+    messages: Messages<'a>,
+    fields: BTreeMap<String, Placeholder>,
+}
+
+impl<'a> Anonymizer<'a> {
+    /// Creates an anonymizer for the messages of a `FileDescriptorSet`, which replaces no fields.
+    pub fn new(file_descriptor_set: &'a FileDescriptorSet) -> Anonymizer<'a> {
+        Anonymizer {
+            messages: Messages::new(file_descriptor_set),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the values of a field, given by its fully qualified name, with a placeholder.
+    pub fn field<S>(&mut self, name: S, placeholder: Placeholder) -> &mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.fields
+            .insert(trim_dot(name.as_ref()).into(), placeholder);
+        self
+    }
+
+    /// Anonymizes an encoded message, given the fully qualified name of its type.
+    ///
+    /// An error is returned if the message type is unknown, or if the payload can't be decoded.
+    pub fn anonymize(&self, message: &str, buf: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        self.messages.rewrite(message, buf, self)
+    }
+}
+
+impl<'a> Rewrite for Anonymizer<'a> {
+    /// Replaces the value of a known field if the field is selected.
+    fn rewrite_field(
+        &self,
+        message: &str,
+        field: &FieldDescriptorProto,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<bool, DecodeError> {
+        let placeholder = match self
+            .fields
+            .get(format!("{}.{}", message, field.name()).as_str())
+        {
+            Some(placeholder) => placeholder,
+            None => return Ok(false),
+        };
+        let ty = field.r#type();
+
+        match (ty, wire_type) {
+            (Type::Group, WireType::StartGroup) => {
+                skip_field(wire_type, tag, buf, DecodeContext::default())?;
+                encode_key(tag, WireType::StartGroup, out);
+                encode_key(tag, WireType::EndGroup, out);
+            }
+            (_, WireType::LengthDelimited) => {
+                let value = length_delimited(buf)?;
+                let replaced = match packed_wire_type(ty) {
+                    Some(element_wire_type) => {
+                        let mut elements = value;
+                        let mut replaced = Vec::with_capacity(value.len());
+                        while elements.has_remaining() {
+                            replace_scalar(
+                                placeholder,
+                                ty,
+                                element_wire_type,
+                                &mut elements,
+                                &mut replaced,
+                            )?;
+                        }
+                        replaced
+                    }
+                    None => replace_bytes(placeholder, ty, value),
+                };
+                encode_key(tag, wire_type, out);
+                encode_varint(replaced.len() as u64, out);
+                out.extend_from_slice(&replaced);
+            }
+            _ => {
+                encode_key(tag, wire_type, out);
+                replace_scalar(placeholder, ty, wire_type, buf, out)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the wire type of the elements of a packed field of the type, if it can be packed.
+fn packed_wire_type(ty: Type) -> Option<WireType> {
+    match ty {
+        Type::Int32
+        | Type::Int64
+        | Type::Uint32
+        | Type::Uint64
+        | Type::Sint32
+        | Type::Sint64
+        | Type::Bool
+        | Type::Enum => Some(WireType::Varint),
+        Type::Fixed32 | Type::Sfixed32 | Type::Float => Some(WireType::ThirtyTwoBit),
+        Type::Fixed64 | Type::Sfixed64 | Type::Double => Some(WireType::SixtyFourBit),
+        Type::String | Type::Bytes | Type::Message | Type::Group => None,
+    }
+}
+
+/// Replaces the value of a `string`, `bytes` or message field.
+fn replace_bytes(placeholder: &Placeholder, ty: Type, value: &[u8]) -> Vec<u8> {
+    match (placeholder, ty) {
+        (Placeholder::Fixed(text), Type::String) | (Placeholder::Fixed(text), Type::Bytes) => {
+            text.as_bytes().to_vec()
+        }
+        (Placeholder::Hash, Type::String) => format!("{:016x}", fnv1a(value)).into_bytes(),
+        (Placeholder::Hash, Type::Bytes) => fnv1a(value).to_be_bytes().to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// Replaces a varint, 32-bit or 64-bit value.
+fn replace_scalar(
+    placeholder: &Placeholder,
+    ty: Type,
+    wire_type: WireType,
+    buf: &mut &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), DecodeError> {
+    let (value, width) = match wire_type {
+        WireType::Varint => (decode_varint(buf)?, 0),
+        WireType::ThirtyTwoBit if buf.len() >= 4 => (u64::from(buf.get_u32_le()), 4),
+        WireType::SixtyFourBit if buf.len() >= 8 => (buf.get_u64_le(), 8),
+        WireType::ThirtyTwoBit | WireType::SixtyFourBit => {
+            return Err(DecodeError::new("buffer underflow"))
+        }
+        _ => {
+            return Err(DecodeError::new(format!(
+                "invalid wire type: {:?}",
+                wire_type
+            )))
+        }
+    };
+
+    let replaced = match placeholder {
+        Placeholder::Hash => {
+            let hash = fnv1a(&value.to_le_bytes());
+            match ty {
+                Type::Bool => hash & 1,
+                // Non-negative, and in the range of every varint type.
+                _ if width == 0 => hash & 0x7fff_ffff,
+                _ => hash,
+            }
+        }
+        Placeholder::Zero | Placeholder::Fixed(_) => 0,
+    };
+
+    match width {
+        0 => encode_varint(replaced, out),
+        4 => out.extend_from_slice(&(replaced as u32).to_le_bytes()),
+        _ => out.extend_from_slice(&replaced.to_le_bytes()),
+    }
+    Ok(())
+}
+
+/// The 64-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+    use prost::{DecodeMessage, EncodeMessage, Message};
+
+    use super::*;
+    use crate::field_descriptor_proto::Label;
+    use crate::walk::fixtures::{self, field, message};
+
+    #[derive(Clone, PartialEq, Message)]
+    struct User {
+        #[prost(string, tag = "1")]
+        email: String,
+        #[prost(uint32, tag = "2")]
+        age: u32,
+        #[prost(message, optional, tag = "3")]
+        address: Option<Address>,
+        #[prost(sint32, repeated, tag = "4")]
+        scores: Vec<i32>,
+        #[prost(string, tag = "5")]
+        name: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Address {
+        #[prost(string, tag = "1")]
+        street: String,
+        #[prost(double, tag = "2")]
+        latitude: f64,
+    }
+
+    fn file_descriptor_set() -> FileDescriptorSet {
+        let address = message(
+            "Address",
+            vec![
+                field("street", 1, Type::String, Label::Optional, None),
+                field("latitude", 2, Type::Double, Label::Optional, None),
+            ],
+            vec![],
+        );
+        let user = message(
+            "User",
+            vec![
+                field("email", 1, Type::String, Label::Optional, None),
+                field("age", 2, Type::Uint32, Label::Optional, None),
+                field(
+                    "address",
+                    3,
+                    Type::Message,
+                    Label::Optional,
+                    Some(".acme.User.Address"),
+                ),
+                field("scores", 4, Type::Sint32, Label::Repeated, None),
+            ],
+            vec![address],
+        );
+        fixtures::file_descriptor_set(user)
+    }
+
+    #[test]
+    fn anonymize() {
+        let user = User {
+            email: "jane@example.com".to_string(),
+            age: 42,
+            address: Some(Address {
+                street: "1 Main St".to_string(),
+                latitude: 51.5,
+            }),
+            scores: vec![-3, 7, 12],
+            name: "Jane".to_string(),
+        };
+        let payload = user.encode_to_vec();
+
+        let file_descriptor_set = file_descriptor_set();
+        let mut anonymizer = Anonymizer::new(&file_descriptor_set);
+        assert_eq!(
+            anonymizer.anonymize(".acme.User", &payload).unwrap(),
+            payload
+        );
+
+        anonymizer
+            .field(
+                ".acme.User.email",
+                Placeholder::Fixed("user@example.com".to_string()),
+            )
+            .field("acme.User.age", Placeholder::Zero)
+            .field("acme.User.Address.street", Placeholder::Hash)
+            .field("acme.User.Address.latitude", Placeholder::Zero)
+            .field("acme.User.scores", Placeholder::Hash);
+        let anonymized =
+            User::decode(&anonymizer.anonymize("acme.User", &payload).unwrap()[..]).unwrap();
+
+        assert_eq!(anonymized.email, "user@example.com");
+        assert_eq!(anonymized.age, 0);
+        let address = anonymized.address.unwrap();
+        assert_eq!(address.street.len(), 16);
+        assert_ne!(address.street, "1 Main St");
+        assert_eq!(address.latitude, 0.0);
+        assert_eq!(anonymized.scores.len(), 3);
+        assert_ne!(anonymized.scores, user.scores);
+        // Fields unknown to the descriptors are copied.
+        assert_eq!(anonymized.name, "Jane");
+
+        // Equal values have equal hashes.
+        let again =
+            User::decode(&anonymizer.anonymize("acme.User", &payload).unwrap()[..]).unwrap();
+        assert_eq!(again.address.unwrap().street, address.street);
+
+        assert!(anonymizer.anonymize("acme.Unknown", &payload).is_err());
+        assert!(anonymizer
+            .anonymize("acme.User", &payload[..payload.len() - 1])
+            .is_err());
+    }
+}
@@ -0,0 +1,24 @@
+//! HTTP bindings of Protobuf service methods.
+//!
+//! `prost-build` can be configured to emit the `google.api.http` method options of a service as
+//! tables of [`HttpBinding`]s, which REST gateways and clients can use without parsing method
+//! options at runtime. See `prost_build::Config::http_bindings` for more information.
+
+/// An HTTP binding of an RPC method, as declared by a `google.api.http` method option.
+///
+/// A method may have more than one binding: the primary rule followed by its
+/// `additional_bindings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HttpBinding {
+    /// The HTTP verb (`GET`, `PUT`, `POST`, `DELETE` or `PATCH`), or the verb of a `custom`
+    /// pattern.
+    pub verb: &'static str,
+    /// The URL path template, e.g. `/v1/{name=messages/*}`.
+    pub path: &'static str,
+    /// The request field mapped to the HTTP request body; `*` maps the whole request, and an empty
+    /// string means the request has no body.
+    pub body: &'static str,
+    /// The response field mapped to the HTTP response body; an empty string maps the whole
+    /// response.
+    pub response_body: &'static str,
+}
@@ -7,27 +7,63 @@
 //!
 //! See the [Protobuf reference][1] for more information about well-known types.
 //!
+//! # Features
+//!
+//! All the types are built by default. Users needing only one group of types can leave the other
+//! out with one of these features:
+//!
+//! - `no-well-known-types`: leaves out the well-known types, such as `Timestamp`, `Duration` and
+//!   `Any`.
+//! - `no-descriptor`: leaves out the types of `descriptor.proto` (`FileDescriptorSet` and
+//!   friends) and the `compiler` module, which are used by code generators and reflection.
+//!
+//! For example, an embedded application using timestamps depends on
+//! `prost-types = { version = "0.8", default-features = false, features = ["no-descriptor"] }`.
+//!
 //! [1]: https://developers.google.com/protocol-buffers/docs/reference/google.protobuf
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(not(feature = "no-well-known-types"))]
 use core::convert::TryFrom;
+#[cfg(not(feature = "no-well-known-types"))]
 use core::i32;
+#[cfg(not(feature = "no-well-known-types"))]
 use core::i64;
+#[cfg(not(feature = "no-well-known-types"))]
 use core::time;
 
+#[cfg(not(feature = "no-descriptor"))]
+include!("descriptor.rs");
+#[cfg(not(feature = "no-well-known-types"))]
 include!("protobuf.rs");
+#[cfg(not(feature = "no-descriptor"))]
 pub mod compiler {
     include!("compiler.rs");
 }
+pub mod http;
+
+#[cfg(not(feature = "no-descriptor"))]
+mod anonymize;
+#[cfg(not(feature = "no-descriptor"))]
+pub use crate::anonymize::{Anonymizer, Placeholder};
+#[cfg(not(feature = "no-descriptor"))]
+mod renumber;
+#[cfg(not(feature = "no-descriptor"))]
+pub use crate::renumber::Renumbering;
+#[cfg(not(feature = "no-descriptor"))]
+mod walk;
 
 // The Protobuf `Duration` and `Timestamp` types can't delegate to the standard library equivalents
 // because the Protobuf versions are signed. To make them easier to work with, `From` conversions
 // are defined in both directions.
 
+#[cfg(not(feature = "no-well-known-types"))]
 const NANOS_PER_SECOND: i32 = 1_000_000_000;
+#[cfg(not(feature = "no-well-known-types"))]
 const NANOS_MAX: i32 = NANOS_PER_SECOND - 1;
 
+#[cfg(not(feature = "no-well-known-types"))]
 impl Duration {
     /// Normalizes the duration to a canonical format.
     ///
@@ -80,6 +116,7 @@ impl Duration {
 }
 
 /// Converts a `std::time::Duration` to a `Duration`.
+#[cfg(not(feature = "no-well-known-types"))]
 impl From<time::Duration> for Duration {
     fn from(duration: time::Duration) -> Duration {
         let seconds = duration.as_secs();
@@ -100,6 +137,7 @@ impl From<time::Duration> for Duration {
     }
 }
 
+#[cfg(not(feature = "no-well-known-types"))]
 impl TryFrom<Duration> for time::Duration {
     type Error = time::Duration;
 
@@ -121,6 +159,7 @@ impl TryFrom<Duration> for time::Duration {
     }
 }
 
+#[cfg(not(feature = "no-well-known-types"))]
 impl Timestamp {
     /// Normalizes the timestamp to a canonical format.
     ///
@@ -167,10 +206,10 @@ impl Timestamp {
 
 /// Implements the unstable/naive version of `Eq`: a basic equality check on the internal fields of the `Timestamp`.
 /// This implies that `normalized_ts != non_normalized_ts` even if `normalized_ts == non_normalized_ts.normalized()`.
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 impl Eq for Timestamp {}
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 #[allow(clippy::derive_hash_xor_eq)] // Derived logic is correct: comparing the 2 feilds for equality
 impl std::hash::Hash for Timestamp {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
@@ -179,7 +218,7 @@ impl std::hash::Hash for Timestamp {
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 impl From<std::time::SystemTime> for Timestamp {
     fn from(system_time: std::time::SystemTime) -> Timestamp {
         let (seconds, nanos) = match system_time.duration_since(std::time::UNIX_EPOCH) {
@@ -209,14 +248,14 @@ impl From<std::time::SystemTime> for Timestamp {
 /// All `Timestamp`s are likely representable on 64-bit Unix-like platforms, but
 /// other platforms, such as Windows and 32-bit Linux, may not be able to represent
 /// the full range of `Timestamp`s.
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct TimestampOutOfSystemRangeError {
     pub timestamp: Timestamp,
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 impl core::fmt::Display for TimestampOutOfSystemRangeError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
@@ -227,10 +266,10 @@ impl core::fmt::Display for TimestampOutOfSystemRangeError {
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 impl std::error::Error for TimestampOutOfSystemRangeError {}
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "no-well-known-types")))]
 impl TryFrom<Timestamp> for std::time::SystemTime {
     type Error = TimestampOutOfSystemRangeError;
 
@@ -255,7 +294,135 @@ impl TryFrom<Timestamp> for std::time::SystemTime {
     }
 }
 
-#[cfg(test)]
+#[cfg(not(feature = "no-well-known-types"))]
+impl Struct {
+    /// Merges a patch into the struct, following the semantics of [JSON Merge Patch][1].
+    ///
+    /// Each field of the patch replaces the field of the same name, except that a null value
+    /// removes the field, and that a struct value is merged recursively into the struct value of
+    /// the field (or into an empty struct, if the field isn't a struct).
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc7396
+    pub fn merge_patch(&mut self, patch: Struct) {
+        for (name, value) in patch.fields {
+            match value.kind {
+                Some(value::Kind::NullValue(_)) => {
+                    self.fields.remove(&name);
+                }
+                Some(value::Kind::StructValue(patch)) => {
+                    let field = self.fields.entry(name).or_default();
+                    match field.kind {
+                        Some(value::Kind::StructValue(ref mut target)) => target.merge_patch(patch),
+                        _ => {
+                            let mut target = Struct::default();
+                            target.merge_patch(patch);
+                            field.kind = Some(value::Kind::StructValue(target));
+                        }
+                    }
+                }
+                _ => {
+                    self.fields.insert(name, value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "no-well-known-types"))]
+impl Value {
+    /// Looks up a value nested in struct and list values by a [JSON Pointer][1], such as
+    /// `/users/0/name`.
+    ///
+    /// Returns `None` if the pointer is malformed, or if no value is found at its location.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub fn pointer(&self, pointer: &str) -> ::core::option::Option<&Value> {
+        pointer_tokens(pointer)?.try_fold(self, |value, token| match value.kind {
+            Some(value::Kind::StructValue(ref fields)) => fields.fields.get(&token),
+            Some(value::Kind::ListValue(ref list)) => list.values.get(list_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Looks up a value nested in struct and list values by a [JSON Pointer][1], such as
+    /// `/users/0/name`, for modification.
+    ///
+    /// Returns `None` if the pointer is malformed, or if no value is found at its location; use
+    /// [`pointer_entry`](Value::pointer_entry) to create missing values.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub fn pointer_mut(&mut self, pointer: &str) -> ::core::option::Option<&mut Value> {
+        pointer_tokens(pointer)?.try_fold(self, |value, token| match value.kind {
+            Some(value::Kind::StructValue(ref mut fields)) => fields.fields.get_mut(&token),
+            Some(value::Kind::ListValue(ref mut list)) => list.values.get_mut(list_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Looks up a value nested in struct and list values by a [JSON Pointer][1] for
+    /// modification, creating it and the values leading to it if they are missing.
+    ///
+    /// Missing struct fields are inserted as unset values, and unset or null values on the path
+    /// are replaced by empty structs. The index one past the end of a list, or `-`, appends an
+    /// unset value to the list.
+    ///
+    /// Returns `None` if the pointer is malformed, if a list index is further past the end of
+    /// the list, or if a value on the path is neither a struct, a list nor null. The values
+    /// created before the failing token are kept.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc6901
+    pub fn pointer_entry(&mut self, pointer: &str) -> ::core::option::Option<&mut Value> {
+        pointer_tokens(pointer)?.try_fold(self, |value, token| {
+            if matches!(value.kind, None | Some(value::Kind::NullValue(_))) {
+                value.kind = Some(value::Kind::StructValue(Struct::default()));
+            }
+            match value.kind {
+                Some(value::Kind::StructValue(ref mut fields)) => {
+                    Some(fields.fields.entry(token).or_default())
+                }
+                Some(value::Kind::ListValue(ref mut list)) => {
+                    let index = if token == "-" {
+                        list.values.len()
+                    } else {
+                        list_index(&token)?
+                    };
+                    if index == list.values.len() {
+                        list.values.push(Value::default());
+                    }
+                    list.values.get_mut(index)
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Splits a JSON Pointer into its unescaped reference tokens.
+#[cfg(not(feature = "no-well-known-types"))]
+fn pointer_tokens(
+    pointer: &str,
+) -> ::core::option::Option<impl Iterator<Item = ::prost::alloc::string::String> + '_> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~")),
+    )
+}
+
+/// Parses a JSON Pointer reference token as a list index, which has no leading zeros.
+#[cfg(not(feature = "no-well-known-types"))]
+fn list_index(token: &str) -> ::core::option::Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(all(test, not(feature = "no-well-known-types")))]
 mod tests {
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -505,4 +672,109 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn struct_merge_patch() {
+        fn string(value: &str) -> Value {
+            Value {
+                kind: Some(value::Kind::StringValue(value.to_string())),
+            }
+        }
+        fn object(fields: Vec<(&str, Value)>) -> Struct {
+            Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            }
+        }
+        fn nested(fields: Vec<(&str, Value)>) -> Value {
+            Value {
+                kind: Some(value::Kind::StructValue(object(fields))),
+            }
+        }
+        let null = Value {
+            kind: Some(value::Kind::NullValue(NullValue::NullValue as i32)),
+        };
+
+        let mut target = object(vec![
+            ("title", string("Goodbye!")),
+            (
+                "author",
+                nested(vec![("given", string("John")), ("family", string("Doe"))]),
+            ),
+            ("tags", string("example")),
+        ]);
+        target.merge_patch(object(vec![
+            ("title", string("Hello!")),
+            ("author", nested(vec![("family", null.clone())])),
+            (
+                "tags",
+                nested(vec![("first", string("a")), ("second", null)]),
+            ),
+        ]));
+        assert_eq!(
+            target,
+            object(vec![
+                ("title", string("Hello!")),
+                ("author", nested(vec![("given", string("John"))])),
+                ("tags", nested(vec![("first", string("a"))])),
+            ])
+        );
+    }
+
+    #[test]
+    fn value_pointer() {
+        let mut value = Value {
+            kind: Some(value::Kind::StructValue(Struct {
+                fields: vec![(
+                    "a/b".to_string(),
+                    Value {
+                        kind: Some(value::Kind::ListValue(ListValue {
+                            values: vec![Value {
+                                kind: Some(value::Kind::BoolValue(true)),
+                            }],
+                        })),
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            })),
+        };
+
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/a~1b/0").unwrap().kind,
+            Some(value::Kind::BoolValue(true))
+        );
+        assert_eq!(value.pointer("/a~1b/00"), None);
+        assert_eq!(value.pointer("/a~1b/1"), None);
+        assert_eq!(value.pointer("/a"), None);
+        assert_eq!(value.pointer("a~1b"), None);
+
+        value.pointer_mut("/a~1b/0").unwrap().kind = Some(value::Kind::NumberValue(1.0));
+        assert_eq!(
+            value.pointer("/a~1b/0").unwrap().kind,
+            Some(value::Kind::NumberValue(1.0))
+        );
+        assert_eq!(value.pointer_mut("/users/0/name"), None);
+
+        value.pointer_entry("/users/name").unwrap().kind =
+            Some(value::Kind::StringValue("Ada".to_string()));
+        assert_eq!(
+            value.pointer("/users/name").unwrap().kind,
+            Some(value::Kind::StringValue("Ada".to_string()))
+        );
+        value.pointer_entry("/a~1b/-").unwrap().kind = Some(value::Kind::BoolValue(false));
+        value.pointer_entry("/a~1b/2").unwrap().kind = Some(value::Kind::BoolValue(false));
+        assert_eq!(
+            value.pointer("/a~1b/2").unwrap().kind,
+            Some(value::Kind::BoolValue(false))
+        );
+        assert_eq!(value.pointer_entry("/a~1b/4"), None);
+        assert_eq!(value.pointer_entry("/a~1b/0/x"), None);
+        assert_eq!(value.pointer_entry("users"), None);
+        let copy = value.clone();
+        assert_eq!(value.pointer_entry(""), Some(&mut copy.clone()));
+    }
 }
@@ -1,918 +1,3 @@
-/// The protocol compiler can output a FileDescriptorSet containing the .proto
-/// files it parses.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct FileDescriptorSet {
-    #[prost(message, repeated, tag="1")]
-    pub file: ::prost::alloc::vec::Vec<FileDescriptorProto>,
-}
-/// Describes a complete .proto file.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct FileDescriptorProto {
-    /// file name, relative to root of source tree
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    /// e.g. "foo", "foo.bar", etc.
-    #[prost(string, optional, tag="2")]
-    pub package: ::core::option::Option<::prost::alloc::string::String>,
-    /// Names of files imported by this file.
-    #[prost(string, repeated, tag="3")]
-    pub dependency: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-    /// Indexes of the public imported files in the dependency list above.
-    #[prost(int32, repeated, packed="false", tag="10")]
-    pub public_dependency: ::prost::alloc::vec::Vec<i32>,
-    /// Indexes of the weak imported files in the dependency list.
-    /// For Google-internal migration only. Do not use.
-    #[prost(int32, repeated, packed="false", tag="11")]
-    pub weak_dependency: ::prost::alloc::vec::Vec<i32>,
-    /// All top-level definitions in this file.
-    #[prost(message, repeated, tag="4")]
-    pub message_type: ::prost::alloc::vec::Vec<DescriptorProto>,
-    #[prost(message, repeated, tag="5")]
-    pub enum_type: ::prost::alloc::vec::Vec<EnumDescriptorProto>,
-    #[prost(message, repeated, tag="6")]
-    pub service: ::prost::alloc::vec::Vec<ServiceDescriptorProto>,
-    #[prost(message, repeated, tag="7")]
-    pub extension: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, optional, tag="8")]
-    pub options: ::core::option::Option<FileOptions>,
-    /// This field contains optional information about the original source code.
-    /// You may safely remove this entire field without harming runtime
-    /// functionality of the descriptors -- the information is needed only by
-    /// development tools.
-    #[prost(message, optional, tag="9")]
-    pub source_code_info: ::core::option::Option<SourceCodeInfo>,
-    /// The syntax of the proto file.
-    /// The supported values are "proto2" and "proto3".
-    #[prost(string, optional, tag="12")]
-    pub syntax: ::core::option::Option<::prost::alloc::string::String>,
-}
-/// Describes a message type.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct DescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
-    pub field: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, repeated, tag="6")]
-    pub extension: ::prost::alloc::vec::Vec<FieldDescriptorProto>,
-    #[prost(message, repeated, tag="3")]
-    pub nested_type: ::prost::alloc::vec::Vec<DescriptorProto>,
-    #[prost(message, repeated, tag="4")]
-    pub enum_type: ::prost::alloc::vec::Vec<EnumDescriptorProto>,
-    #[prost(message, repeated, tag="5")]
-    pub extension_range: ::prost::alloc::vec::Vec<descriptor_proto::ExtensionRange>,
-    #[prost(message, repeated, tag="8")]
-    pub oneof_decl: ::prost::alloc::vec::Vec<OneofDescriptorProto>,
-    #[prost(message, optional, tag="7")]
-    pub options: ::core::option::Option<MessageOptions>,
-    #[prost(message, repeated, tag="9")]
-    pub reserved_range: ::prost::alloc::vec::Vec<descriptor_proto::ReservedRange>,
-    /// Reserved field names, which may not be used by fields in the same message.
-    /// A given name may only be reserved once.
-    #[prost(string, repeated, tag="10")]
-    pub reserved_name: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-}
-/// Nested message and enum types in `DescriptorProto`.
-pub mod descriptor_proto {
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct ExtensionRange {
-        /// Inclusive.
-        #[prost(int32, optional, tag="1")]
-        pub start: ::core::option::Option<i32>,
-        /// Exclusive.
-        #[prost(int32, optional, tag="2")]
-        pub end: ::core::option::Option<i32>,
-        #[prost(message, optional, tag="3")]
-        pub options: ::core::option::Option<super::ExtensionRangeOptions>,
-    }
-    /// Range of reserved tag numbers. Reserved tag numbers may not be used by
-    /// fields or extension ranges in the same message. Reserved ranges may
-    /// not overlap.
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct ReservedRange {
-        /// Inclusive.
-        #[prost(int32, optional, tag="1")]
-        pub start: ::core::option::Option<i32>,
-        /// Exclusive.
-        #[prost(int32, optional, tag="2")]
-        pub end: ::core::option::Option<i32>,
-    }
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ExtensionRangeOptions {
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-/// Describes a field within a message.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct FieldDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(int32, optional, tag="3")]
-    pub number: ::core::option::Option<i32>,
-    #[prost(enumeration="field_descriptor_proto::Label", optional, tag="4")]
-    pub label: ::core::option::Option<i32>,
-    /// If type_name is set, this need not be set.  If both this and type_name
-    /// are set, this must be one of TYPE_ENUM, TYPE_MESSAGE or TYPE_GROUP.
-    #[prost(enumeration="field_descriptor_proto::Type", optional, tag="5")]
-    pub r#type: ::core::option::Option<i32>,
-    /// For message and enum types, this is the name of the type.  If the name
-    /// starts with a '.', it is fully-qualified.  Otherwise, C++-like scoping
-    /// rules are used to find the type (i.e. first the nested types within this
-    /// message are searched, then within the parent, on up to the root
-    /// namespace).
-    #[prost(string, optional, tag="6")]
-    pub type_name: ::core::option::Option<::prost::alloc::string::String>,
-    /// For extensions, this is the name of the type being extended.  It is
-    /// resolved in the same manner as type_name.
-    #[prost(string, optional, tag="2")]
-    pub extendee: ::core::option::Option<::prost::alloc::string::String>,
-    /// For numeric types, contains the original text representation of the value.
-    /// For booleans, "true" or "false".
-    /// For strings, contains the default text contents (not escaped in any way).
-    /// For bytes, contains the C escaped value.  All bytes >= 128 are escaped.
-    /// TODO(kenton):  Base-64 encode?
-    #[prost(string, optional, tag="7")]
-    pub default_value: ::core::option::Option<::prost::alloc::string::String>,
-    /// If set, gives the index of a oneof in the containing type's oneof_decl
-    /// list.  This field is a member of that oneof.
-    #[prost(int32, optional, tag="9")]
-    pub oneof_index: ::core::option::Option<i32>,
-    /// JSON name of this field. The value is set by protocol compiler. If the
-    /// user has set a "json_name" option on this field, that option's value
-    /// will be used. Otherwise, it's deduced from the field's name by converting
-    /// it to camelCase.
-    #[prost(string, optional, tag="10")]
-    pub json_name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="8")]
-    pub options: ::core::option::Option<FieldOptions>,
-    /// If true, this is a proto3 "optional". When a proto3 field is optional, it
-    /// tracks presence regardless of field type.
-    ///
-    /// When proto3_optional is true, this field must be belong to a oneof to
-    /// signal to old proto3 clients that presence is tracked for this field. This
-    /// oneof is known as a "synthetic" oneof, and this field must be its sole
-    /// member (each proto3 optional field gets its own synthetic oneof). Synthetic
-    /// oneofs exist in the descriptor only, and do not generate any API. Synthetic
-    /// oneofs must be ordered after all "real" oneofs.
-    ///
-    /// For message fields, proto3_optional doesn't create any semantic change,
-    /// since non-repeated message fields always track presence. However it still
-    /// indicates the semantic detail of whether the user wrote "optional" or not.
-    /// This can be useful for round-tripping the .proto file. For consistency we
-    /// give message fields a synthetic oneof also, even though it is not required
-    /// to track presence. This is especially important because the parser can't
-    /// tell if a field is a message or an enum, so it must always create a
-    /// synthetic oneof.
-    ///
-    /// Proto2 optional fields do not set this flag, because they already indicate
-    /// optional with `LABEL_OPTIONAL`.
-    #[prost(bool, optional, tag="17")]
-    pub proto3_optional: ::core::option::Option<bool>,
-}
-/// Nested message and enum types in `FieldDescriptorProto`.
-pub mod field_descriptor_proto {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum Type {
-        /// 0 is reserved for errors.
-        /// Order is weird for historical reasons.
-        Double = 1,
-        Float = 2,
-        /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT64 if
-        /// negative values are likely.
-        Int64 = 3,
-        Uint64 = 4,
-        /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT32 if
-        /// negative values are likely.
-        Int32 = 5,
-        Fixed64 = 6,
-        Fixed32 = 7,
-        Bool = 8,
-        String = 9,
-        /// Tag-delimited aggregate.
-        /// Group type is deprecated and not supported in proto3. However, Proto3
-        /// implementations should still be able to parse the group wire format and
-        /// treat group fields as unknown fields.
-        Group = 10,
-        /// Length-delimited aggregate.
-        Message = 11,
-        /// New in version 2.
-        Bytes = 12,
-        Uint32 = 13,
-        Enum = 14,
-        Sfixed32 = 15,
-        Sfixed64 = 16,
-        /// Uses ZigZag encoding.
-        Sint32 = 17,
-        /// Uses ZigZag encoding.
-        Sint64 = 18,
-    }
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum Label {
-        /// 0 is reserved for errors
-        Optional = 1,
-        Required = 2,
-        Repeated = 3,
-    }
-}
-/// Describes a oneof.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct OneofDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="2")]
-    pub options: ::core::option::Option<OneofOptions>,
-}
-/// Describes an enum type.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct EnumDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
-    pub value: ::prost::alloc::vec::Vec<EnumValueDescriptorProto>,
-    #[prost(message, optional, tag="3")]
-    pub options: ::core::option::Option<EnumOptions>,
-    /// Range of reserved numeric values. Reserved numeric values may not be used
-    /// by enum values in the same enum declaration. Reserved ranges may not
-    /// overlap.
-    #[prost(message, repeated, tag="4")]
-    pub reserved_range: ::prost::alloc::vec::Vec<enum_descriptor_proto::EnumReservedRange>,
-    /// Reserved enum value names, which may not be reused. A given name may only
-    /// be reserved once.
-    #[prost(string, repeated, tag="5")]
-    pub reserved_name: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-}
-/// Nested message and enum types in `EnumDescriptorProto`.
-pub mod enum_descriptor_proto {
-    /// Range of reserved numeric values. Reserved values may not be used by
-    /// entries in the same enum. Reserved ranges may not overlap.
-    ///
-    /// Note that this is distinct from DescriptorProto.ReservedRange in that it
-    /// is inclusive such that it can appropriately represent the entire int32
-    /// domain.
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct EnumReservedRange {
-        /// Inclusive.
-        #[prost(int32, optional, tag="1")]
-        pub start: ::core::option::Option<i32>,
-        /// Inclusive.
-        #[prost(int32, optional, tag="2")]
-        pub end: ::core::option::Option<i32>,
-    }
-}
-/// Describes a value within an enum.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct EnumValueDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(int32, optional, tag="2")]
-    pub number: ::core::option::Option<i32>,
-    #[prost(message, optional, tag="3")]
-    pub options: ::core::option::Option<EnumValueOptions>,
-}
-/// Describes a service.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ServiceDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, repeated, tag="2")]
-    pub method: ::prost::alloc::vec::Vec<MethodDescriptorProto>,
-    #[prost(message, optional, tag="3")]
-    pub options: ::core::option::Option<ServiceOptions>,
-}
-/// Describes a method of a service.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct MethodDescriptorProto {
-    #[prost(string, optional, tag="1")]
-    pub name: ::core::option::Option<::prost::alloc::string::String>,
-    /// Input and output type names.  These are resolved in the same way as
-    /// FieldDescriptorProto.type_name, but must refer to a message type.
-    #[prost(string, optional, tag="2")]
-    pub input_type: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(string, optional, tag="3")]
-    pub output_type: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(message, optional, tag="4")]
-    pub options: ::core::option::Option<MethodOptions>,
-    /// Identifies if client streams multiple client messages
-    #[prost(bool, optional, tag="5", default="false")]
-    pub client_streaming: ::core::option::Option<bool>,
-    /// Identifies if server streams multiple server messages
-    #[prost(bool, optional, tag="6", default="false")]
-    pub server_streaming: ::core::option::Option<bool>,
-}
-// ===================================================================
-// Options
-
-// Each of the definitions above may have "options" attached.  These are
-// just annotations which may cause code to be generated slightly differently
-// or may contain hints for code that manipulates protocol messages.
-//
-// Clients may define custom options as extensions of the *Options messages.
-// These extensions may not yet be known at parsing time, so the parser cannot
-// store the values in them.  Instead it stores them in a field in the *Options
-// message called uninterpreted_option. This field must have the same name
-// across all *Options messages. We then use this field to populate the
-// extensions when we build a descriptor, at which point all protos have been
-// parsed and so all extensions are known.
-//
-// Extension numbers for custom options may be chosen as follows:
-// * For options which will only be used within a single application or
-//   organization, or for experimental options, use field numbers 50000
-//   through 99999.  It is up to you to ensure that you do not use the
-//   same number for multiple options.
-// * For options which will be published and used publicly by multiple
-//   independent entities, e-mail protobuf-global-extension-registry@google.com
-//   to reserve extension numbers. Simply provide your project name (e.g.
-//   Objective-C plugin) and your project website (if available) -- there's no
-//   need to explain how you intend to use them. Usually you only need one
-//   extension number. You can declare multiple options with only one extension
-//   number by putting them in a sub-message. See the Custom Options section of
-//   the docs for examples:
-//   <https://developers.google.com/protocol-buffers/docs/proto#options>
-//   If this turns out to be popular, a web service will be set up
-//   to automatically assign option numbers.
-
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct FileOptions {
-    /// Sets the Java package where classes generated from this .proto will be
-    /// placed.  By default, the proto package is used, but this is often
-    /// inappropriate because proto packages do not normally start with backwards
-    /// domain names.
-    #[prost(string, optional, tag="1")]
-    pub java_package: ::core::option::Option<::prost::alloc::string::String>,
-    /// If set, all the classes from the .proto file are wrapped in a single
-    /// outer class with the given name.  This applies to both Proto1
-    /// (equivalent to the old "--one_java_file" option) and Proto2 (where
-    /// a .proto always translates to a single class, but you may want to
-    /// explicitly choose the class name).
-    #[prost(string, optional, tag="8")]
-    pub java_outer_classname: ::core::option::Option<::prost::alloc::string::String>,
-    /// If set true, then the Java code generator will generate a separate .java
-    /// file for each top-level message, enum, and service defined in the .proto
-    /// file.  Thus, these types will *not* be nested inside the outer class
-    /// named by java_outer_classname.  However, the outer class will still be
-    /// generated to contain the file's getDescriptor() method as well as any
-    /// top-level extensions defined in the file.
-    #[prost(bool, optional, tag="10", default="false")]
-    pub java_multiple_files: ::core::option::Option<bool>,
-    /// This option does nothing.
-    #[deprecated]
-    #[prost(bool, optional, tag="20")]
-    pub java_generate_equals_and_hash: ::core::option::Option<bool>,
-    /// If set true, then the Java2 code generator will generate code that
-    /// throws an exception whenever an attempt is made to assign a non-UTF-8
-    /// byte sequence to a string field.
-    /// Message reflection will do the same.
-    /// However, an extension field still accepts non-UTF-8 byte sequences.
-    /// This option has no effect on when used with the lite runtime.
-    #[prost(bool, optional, tag="27", default="false")]
-    pub java_string_check_utf8: ::core::option::Option<bool>,
-    #[prost(enumeration="file_options::OptimizeMode", optional, tag="9", default="Speed")]
-    pub optimize_for: ::core::option::Option<i32>,
-    /// Sets the Go package where structs generated from this .proto will be
-    /// placed. If omitted, the Go package will be derived from the following:
-    ///   - The basename of the package import path, if provided.
-    ///   - Otherwise, the package statement in the .proto file, if present.
-    ///   - Otherwise, the basename of the .proto file, without extension.
-    #[prost(string, optional, tag="11")]
-    pub go_package: ::core::option::Option<::prost::alloc::string::String>,
-    /// Should generic services be generated in each language?  "Generic" services
-    /// are not specific to any particular RPC system.  They are generated by the
-    /// main code generators in each language (without additional plugins).
-    /// Generic services were the only kind of service generation supported by
-    /// early versions of google.protobuf.
-    ///
-    /// Generic services are now considered deprecated in favor of using plugins
-    /// that generate code specific to your particular RPC system.  Therefore,
-    /// these default to false.  Old code which depends on generic services should
-    /// explicitly set them to true.
-    #[prost(bool, optional, tag="16", default="false")]
-    pub cc_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="17", default="false")]
-    pub java_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="18", default="false")]
-    pub py_generic_services: ::core::option::Option<bool>,
-    #[prost(bool, optional, tag="42", default="false")]
-    pub php_generic_services: ::core::option::Option<bool>,
-    /// Is this file deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for everything in the file, or it will be completely ignored; in the very
-    /// least, this is a formalization for deprecating files.
-    #[prost(bool, optional, tag="23", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// Enables the use of arenas for the proto messages in this file. This applies
-    /// only to generated classes for C++.
-    #[prost(bool, optional, tag="31", default="true")]
-    pub cc_enable_arenas: ::core::option::Option<bool>,
-    /// Sets the objective c class prefix which is prepended to all objective c
-    /// generated classes from this .proto. There is no default.
-    #[prost(string, optional, tag="36")]
-    pub objc_class_prefix: ::core::option::Option<::prost::alloc::string::String>,
-    /// Namespace for generated classes; defaults to the package.
-    #[prost(string, optional, tag="37")]
-    pub csharp_namespace: ::core::option::Option<::prost::alloc::string::String>,
-    /// By default Swift generators will take the proto package and CamelCase it
-    /// replacing '.' with underscore and use that to prefix the types/symbols
-    /// defined. When this options is provided, they will use this value instead
-    /// to prefix the types/symbols defined.
-    #[prost(string, optional, tag="39")]
-    pub swift_prefix: ::core::option::Option<::prost::alloc::string::String>,
-    /// Sets the php class prefix which is prepended to all php generated classes
-    /// from this .proto. Default is empty.
-    #[prost(string, optional, tag="40")]
-    pub php_class_prefix: ::core::option::Option<::prost::alloc::string::String>,
-    /// Use this option to change the namespace of php generated classes. Default
-    /// is empty. When this option is empty, the package name will be used for
-    /// determining the namespace.
-    #[prost(string, optional, tag="41")]
-    pub php_namespace: ::core::option::Option<::prost::alloc::string::String>,
-    /// Use this option to change the namespace of php generated metadata classes.
-    /// Default is empty. When this option is empty, the proto file name will be
-    /// used for determining the namespace.
-    #[prost(string, optional, tag="44")]
-    pub php_metadata_namespace: ::core::option::Option<::prost::alloc::string::String>,
-    /// Use this option to change the package of ruby generated classes. Default
-    /// is empty. When this option is not set, the package name will be used for
-    /// determining the ruby package.
-    #[prost(string, optional, tag="45")]
-    pub ruby_package: ::core::option::Option<::prost::alloc::string::String>,
-    /// The parser stores options it doesn't recognize here.
-    /// See the documentation for the "Options" section above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-/// Nested message and enum types in `FileOptions`.
-pub mod file_options {
-    /// Generated classes can be optimized for speed or code size.
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum OptimizeMode {
-        /// Generate complete code for parsing, serialization,
-        Speed = 1,
-        /// etc.
-        ///
-        /// Use ReflectionOps to implement these methods.
-        CodeSize = 2,
-        /// Generate code using MessageLite and the lite runtime.
-        LiteRuntime = 3,
-    }
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct MessageOptions {
-    /// Set true to use the old proto1 MessageSet wire format for extensions.
-    /// This is provided for backwards-compatibility with the MessageSet wire
-    /// format.  You should not use this for any other reason:  It's less
-    /// efficient, has fewer features, and is more complicated.
-    ///
-    /// The message must be defined exactly as follows:
-    ///   message Foo {
-    ///     option message_set_wire_format = true;
-    ///     extensions 4 to max;
-    ///   }
-    /// Note that the message cannot have any defined fields; MessageSets only
-    /// have extensions.
-    ///
-    /// All extensions of your type must be singular messages; e.g. they cannot
-    /// be int32s, enums, or repeated messages.
-    ///
-    /// Because this is an option, the above two restrictions are not enforced by
-    /// the protocol compiler.
-    #[prost(bool, optional, tag="1", default="false")]
-    pub message_set_wire_format: ::core::option::Option<bool>,
-    /// Disables the generation of the standard "descriptor()" accessor, which can
-    /// conflict with a field of the same name.  This is meant to make migration
-    /// from proto1 easier; new code should avoid fields named "descriptor".
-    #[prost(bool, optional, tag="2", default="false")]
-    pub no_standard_descriptor_accessor: ::core::option::Option<bool>,
-    /// Is this message deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for the message, or it will be completely ignored; in the very least,
-    /// this is a formalization for deprecating messages.
-    #[prost(bool, optional, tag="3", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// Whether the message is an automatically generated map entry type for the
-    /// maps field.
-    ///
-    /// For maps fields:
-    ///     map<KeyType, ValueType> map_field = 1;
-    /// The parsed descriptor looks like:
-    ///     message MapFieldEntry {
-    ///         option map_entry = true;
-    ///         optional KeyType key = 1;
-    ///         optional ValueType value = 2;
-    ///     }
-    ///     repeated MapFieldEntry map_field = 1;
-    ///
-    /// Implementations may choose not to generate the map_entry=true message, but
-    /// use a native map in the target language to hold the keys and values.
-    /// The reflection APIs in such implementations still need to work as
-    /// if the field is a repeated message field.
-    ///
-    /// NOTE: Do not set the option in .proto files. Always use the maps syntax
-    /// instead. The option should only be implicitly set by the proto compiler
-    /// parser.
-    #[prost(bool, optional, tag="7")]
-    pub map_entry: ::core::option::Option<bool>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct FieldOptions {
-    /// The ctype option instructs the C++ code generator to use a different
-    /// representation of the field than it normally would.  See the specific
-    /// options below.  This option is not yet implemented in the open source
-    /// release -- sorry, we'll try to include it in a future version!
-    #[prost(enumeration="field_options::CType", optional, tag="1", default="String")]
-    pub ctype: ::core::option::Option<i32>,
-    /// The packed option can be enabled for repeated primitive fields to enable
-    /// a more efficient representation on the wire. Rather than repeatedly
-    /// writing the tag and type for each element, the entire array is encoded as
-    /// a single length-delimited blob. In proto3, only explicit setting it to
-    /// false will avoid using packed encoding.
-    #[prost(bool, optional, tag="2")]
-    pub packed: ::core::option::Option<bool>,
-    /// The jstype option determines the JavaScript type used for values of the
-    /// field.  The option is permitted only for 64 bit integral and fixed types
-    /// (int64, uint64, sint64, fixed64, sfixed64).  A field with jstype JS_STRING
-    /// is represented as JavaScript string, which avoids loss of precision that
-    /// can happen when a large value is converted to a floating point JavaScript.
-    /// Specifying JS_NUMBER for the jstype causes the generated JavaScript code to
-    /// use the JavaScript "number" type.  The behavior of the default option
-    /// JS_NORMAL is implementation dependent.
-    ///
-    /// This option is an enum to permit additional types to be added, e.g.
-    /// goog.math.Integer.
-    #[prost(enumeration="field_options::JsType", optional, tag="6", default="JsNormal")]
-    pub jstype: ::core::option::Option<i32>,
-    /// Should this field be parsed lazily?  Lazy applies only to message-type
-    /// fields.  It means that when the outer message is initially parsed, the
-    /// inner message's contents will not be parsed but instead stored in encoded
-    /// form.  The inner message will actually be parsed when it is first accessed.
-    ///
-    /// This is only a hint.  Implementations are free to choose whether to use
-    /// eager or lazy parsing regardless of the value of this option.  However,
-    /// setting this option true suggests that the protocol author believes that
-    /// using lazy parsing on this field is worth the additional bookkeeping
-    /// overhead typically needed to implement it.
-    ///
-    /// This option does not affect the public interface of any generated code;
-    /// all method signatures remain the same.  Furthermore, thread-safety of the
-    /// interface is not affected by this option; const methods remain safe to
-    /// call from multiple threads concurrently, while non-const methods continue
-    /// to require exclusive access.
-    ///
-    ///
-    /// Note that implementations may choose not to check required fields within
-    /// a lazy sub-message.  That is, calling IsInitialized() on the outer message
-    /// may return true even if the inner message has missing required fields.
-    /// This is necessary because otherwise the inner message would have to be
-    /// parsed in order to perform the check, defeating the purpose of lazy
-    /// parsing.  An implementation which chooses not to check required fields
-    /// must be consistent about it.  That is, for any particular sub-message, the
-    /// implementation must either *always* check its required fields, or *never*
-    /// check its required fields, regardless of whether or not the message has
-    /// been parsed.
-    #[prost(bool, optional, tag="5", default="false")]
-    pub lazy: ::core::option::Option<bool>,
-    /// Is this field deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for accessors, or it will be completely ignored; in the very least, this
-    /// is a formalization for deprecating fields.
-    #[prost(bool, optional, tag="3", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// For Google-internal migration only. Do not use.
-    #[prost(bool, optional, tag="10", default="false")]
-    pub weak: ::core::option::Option<bool>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-/// Nested message and enum types in `FieldOptions`.
-pub mod field_options {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum CType {
-        /// Default mode.
-        String = 0,
-        Cord = 1,
-        StringPiece = 2,
-    }
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum JsType {
-        /// Use the default type.
-        JsNormal = 0,
-        /// Use JavaScript strings.
-        JsString = 1,
-        /// Use JavaScript numbers.
-        JsNumber = 2,
-    }
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct OneofOptions {
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct EnumOptions {
-    /// Set this option to true to allow mapping different tag names to the same
-    /// value.
-    #[prost(bool, optional, tag="2")]
-    pub allow_alias: ::core::option::Option<bool>,
-    /// Is this enum deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for the enum, or it will be completely ignored; in the very least, this
-    /// is a formalization for deprecating enums.
-    #[prost(bool, optional, tag="3", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct EnumValueOptions {
-    /// Is this enum value deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for the enum value, or it will be completely ignored; in the very least,
-    /// this is a formalization for deprecating enum values.
-    #[prost(bool, optional, tag="1", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ServiceOptions {
-    // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
-    //   framework.  We apologize for hoarding these numbers to ourselves, but
-    //   we were already using them long before we decided to release Protocol
-    //   Buffers.
-
-    /// Is this service deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for the service, or it will be completely ignored; in the very least,
-    /// this is a formalization for deprecating services.
-    #[prost(bool, optional, tag="33", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct MethodOptions {
-    // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
-    //   framework.  We apologize for hoarding these numbers to ourselves, but
-    //   we were already using them long before we decided to release Protocol
-    //   Buffers.
-
-    /// Is this method deprecated?
-    /// Depending on the target platform, this can emit Deprecated annotations
-    /// for the method, or it will be completely ignored; in the very least,
-    /// this is a formalization for deprecating methods.
-    #[prost(bool, optional, tag="33", default="false")]
-    pub deprecated: ::core::option::Option<bool>,
-    #[prost(enumeration="method_options::IdempotencyLevel", optional, tag="34", default="IdempotencyUnknown")]
-    pub idempotency_level: ::core::option::Option<i32>,
-    /// The parser stores options it doesn't recognize here. See above.
-    #[prost(message, repeated, tag="999")]
-    pub uninterpreted_option: ::prost::alloc::vec::Vec<UninterpretedOption>,
-}
-/// Nested message and enum types in `MethodOptions`.
-pub mod method_options {
-    /// Is this method side-effect-free (or safe in HTTP parlance), or idempotent,
-    /// or neither? HTTP based RPC implementation may choose GET verb for safe
-    /// methods, and PUT verb for idempotent methods instead of the default POST.
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-    #[repr(i32)]
-    pub enum IdempotencyLevel {
-        IdempotencyUnknown = 0,
-        /// implies idempotent
-        NoSideEffects = 1,
-        /// idempotent, but may have side effects
-        Idempotent = 2,
-    }
-}
-/// A message representing a option the parser does not recognize. This only
-/// appears in options protos created by the compiler::Parser class.
-/// DescriptorPool resolves these when building Descriptor objects. Therefore,
-/// options protos in descriptor objects (e.g. returned by Descriptor::options(),
-/// or produced by Descriptor::CopyTo()) will never have UninterpretedOptions
-/// in them.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct UninterpretedOption {
-    #[prost(message, repeated, tag="2")]
-    pub name: ::prost::alloc::vec::Vec<uninterpreted_option::NamePart>,
-    /// The value of the uninterpreted option, in whatever type the tokenizer
-    /// identified it as during parsing. Exactly one of these should be set.
-    #[prost(string, optional, tag="3")]
-    pub identifier_value: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(uint64, optional, tag="4")]
-    pub positive_int_value: ::core::option::Option<u64>,
-    #[prost(int64, optional, tag="5")]
-    pub negative_int_value: ::core::option::Option<i64>,
-    #[prost(double, optional, tag="6")]
-    pub double_value: ::core::option::Option<f64>,
-    #[prost(bytes="vec", optional, tag="7")]
-    pub string_value: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
-    #[prost(string, optional, tag="8")]
-    pub aggregate_value: ::core::option::Option<::prost::alloc::string::String>,
-}
-/// Nested message and enum types in `UninterpretedOption`.
-pub mod uninterpreted_option {
-    /// The name of the uninterpreted option.  Each string represents a segment in
-    /// a dot-separated name.  is_extension is true iff a segment represents an
-    /// extension (denoted with parentheses in options specs in .proto files).
-    /// E.g.,{ ["foo", false], ["bar.baz", true], ["qux", false] } represents
-    /// "foo.(bar.baz).qux".
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct NamePart {
-        #[prost(string, required, tag="1")]
-        pub name_part: ::prost::alloc::string::String,
-        #[prost(bool, required, tag="2")]
-        pub is_extension: bool,
-    }
-}
-// ===================================================================
-// Optional source code info
-
-/// Encapsulates information about the original source file from which a
-/// FileDescriptorProto was generated.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct SourceCodeInfo {
-    /// A Location identifies a piece of source code in a .proto file which
-    /// corresponds to a particular definition.  This information is intended
-    /// to be useful to IDEs, code indexers, documentation generators, and similar
-    /// tools.
-    ///
-    /// For example, say we have a file like:
-    ///   message Foo {
-    ///     optional string foo = 1;
-    ///   }
-    /// Let's look at just the field definition:
-    ///   optional string foo = 1;
-    ///   ^       ^^     ^^  ^  ^^^
-    ///   a       bc     de  f  ghi
-    /// We have the following locations:
-    ///   span   path               represents
-    ///   [a,i)  [ 4, 0, 2, 0 ]     The whole field definition.
-    ///   [a,b)  [ 4, 0, 2, 0, 4 ]  The label (optional).
-    ///   [c,d)  [ 4, 0, 2, 0, 5 ]  The type (string).
-    ///   [e,f)  [ 4, 0, 2, 0, 1 ]  The name (foo).
-    ///   [g,h)  [ 4, 0, 2, 0, 3 ]  The number (1).
-    ///
-    /// Notes:
-    /// - A location may refer to a repeated field itself (i.e. not to any
-    ///   particular index within it).  This is used whenever a set of elements are
-    ///   logically enclosed in a single code segment.  For example, an entire
-    ///   extend block (possibly containing multiple extension definitions) will
-    ///   have an outer location whose path refers to the "extensions" repeated
-    ///   field without an index.
-    /// - Multiple locations may have the same path.  This happens when a single
-    ///   logical declaration is spread out across multiple places.  The most
-    ///   obvious example is the "extend" block again -- there may be multiple
-    ///   extend blocks in the same scope, each of which will have the same path.
-    /// - A location's span is not always a subset of its parent's span.  For
-    ///   example, the "extendee" of an extension declaration appears at the
-    ///   beginning of the "extend" block and is shared by all extensions within
-    ///   the block.
-    /// - Just because a location's span is a subset of some other location's span
-    ///   does not mean that it is a descendant.  For example, a "group" defines
-    ///   both a type and a field in a single declaration.  Thus, the locations
-    ///   corresponding to the type and field and their components will overlap.
-    /// - Code which tries to interpret locations should probably be designed to
-    ///   ignore those that it doesn't understand, as more types of locations could
-    ///   be recorded in the future.
-    #[prost(message, repeated, tag="1")]
-    pub location: ::prost::alloc::vec::Vec<source_code_info::Location>,
-}
-/// Nested message and enum types in `SourceCodeInfo`.
-pub mod source_code_info {
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct Location {
-        /// Identifies which part of the FileDescriptorProto was defined at this
-        /// location.
-        ///
-        /// Each element is a field number or an index.  They form a path from
-        /// the root FileDescriptorProto to the place where the definition.  For
-        /// example, this path:
-        ///   [ 4, 3, 2, 7, 1 ]
-        /// refers to:
-        ///   file.message_type(3)  // 4, 3
-        ///       .field(7)         // 2, 7
-        ///       .name()           // 1
-        /// This is because FileDescriptorProto.message_type has field number 4:
-        ///   repeated DescriptorProto message_type = 4;
-        /// and DescriptorProto.field has field number 2:
-        ///   repeated FieldDescriptorProto field = 2;
-        /// and FieldDescriptorProto.name has field number 1:
-        ///   optional string name = 1;
-        ///
-        /// Thus, the above path gives the location of a field name.  If we removed
-        /// the last element:
-        ///   [ 4, 3, 2, 7 ]
-        /// this path refers to the whole field declaration (from the beginning
-        /// of the label to the terminating semicolon).
-        #[prost(int32, repeated, tag="1")]
-        pub path: ::prost::alloc::vec::Vec<i32>,
-        /// Always has exactly three or four elements: start line, start column,
-        /// end line (optional, otherwise assumed same as start line), end column.
-        /// These are packed into a single field for efficiency.  Note that line
-        /// and column numbers are zero-based -- typically you will want to add
-        /// 1 to each before displaying to a user.
-        #[prost(int32, repeated, tag="2")]
-        pub span: ::prost::alloc::vec::Vec<i32>,
-        /// If this SourceCodeInfo represents a complete declaration, these are any
-        /// comments appearing before and after the declaration which appear to be
-        /// attached to the declaration.
-        ///
-        /// A series of line comments appearing on consecutive lines, with no other
-        /// tokens appearing on those lines, will be treated as a single comment.
-        ///
-        /// leading_detached_comments will keep paragraphs of comments that appear
-        /// before (but not connected to) the current element. Each paragraph,
-        /// separated by empty lines, will be one comment element in the repeated
-        /// field.
-        ///
-        /// Only the comment content is provided; comment markers (e.g. //) are
-        /// stripped out.  For block comments, leading whitespace and an asterisk
-        /// will be stripped from the beginning of each line other than the first.
-        /// Newlines are included in the output.
-        ///
-        /// Examples:
-        ///
-        ///   optional int32 foo = 1;  // Comment attached to foo.
-        ///   // Comment attached to bar.
-        ///   optional int32 bar = 2;
-        ///
-        ///   optional string baz = 3;
-        ///   // Comment attached to baz.
-        ///   // Another line attached to baz.
-        ///
-        ///   // Comment attached to qux.
-        ///   //
-        ///   // Another line attached to qux.
-        ///   optional double qux = 4;
-        ///
-        ///   // Detached comment for corge. This is not leading or trailing comments
-        ///   // to qux or corge because there are blank lines separating it from
-        ///   // both.
-        ///
-        ///   // Detached comment for corge paragraph 2.
-        ///
-        ///   optional string corge = 5;
-        ///   /* Block comment attached
-        ///    * to corge.  Leading asterisks
-        ///    * will be removed. */
-        ///   /* Block comment attached to
-        ///    * grault. */
-        ///   optional int32 grault = 6;
-        ///
-        ///   // ignored detached comments.
-        #[prost(string, optional, tag="3")]
-        pub leading_comments: ::core::option::Option<::prost::alloc::string::String>,
-        #[prost(string, optional, tag="4")]
-        pub trailing_comments: ::core::option::Option<::prost::alloc::string::String>,
-        #[prost(string, repeated, tag="6")]
-        pub leading_detached_comments: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-    }
-}
-/// Describes the relationship between generated code and its original source
-/// file. A GeneratedCodeInfo message is associated with only one generated
-/// source file, but may contain references to different source .proto files.
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GeneratedCodeInfo {
-    /// An Annotation connects some span of text in generated code to an element
-    /// of its generating .proto file.
-    #[prost(message, repeated, tag="1")]
-    pub annotation: ::prost::alloc::vec::Vec<generated_code_info::Annotation>,
-}
-/// Nested message and enum types in `GeneratedCodeInfo`.
-pub mod generated_code_info {
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct Annotation {
-        /// Identifies the element in the original source .proto file. This field
-        /// is formatted the same as SourceCodeInfo.Location.path.
-        #[prost(int32, repeated, tag="1")]
-        pub path: ::prost::alloc::vec::Vec<i32>,
-        /// Identifies the filesystem path to the original source .proto.
-        #[prost(string, optional, tag="2")]
-        pub source_file: ::core::option::Option<::prost::alloc::string::String>,
-        /// Identifies the starting offset in bytes in the generated code
-        /// that relates to the identified object.
-        #[prost(int32, optional, tag="3")]
-        pub begin: ::core::option::Option<i32>,
-        /// Identifies the ending offset in bytes in the generated code that
-        /// relates to the identified offset. The end offset should be one past
-        /// the last relevant byte (so the length of the text = end - begin).
-        #[prost(int32, optional, tag="4")]
-        pub end: ::core::option::Option<i32>,
-    }
-}
 /// `Any` contains an arbitrary serialized protocol buffer message along with a
 /// URL that describes the type of the serialized message.
 ///
@@ -0,0 +1,263 @@
+//! Renumbering of the fields of encoded messages.
+
+use prost::alloc::collections::{BTreeMap, BTreeSet};
+use prost::alloc::format;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::encoding::{MAX_TAG, MIN_TAG};
+use prost::DecodeError;
+
+use crate::walk::{trim_dot, Messages, Rewrite};
+use crate::{DescriptorProto, FileDescriptorSet};
+
+/// Re-encodes encoded messages with some of their fields moved to new field numbers.
+///
+/// Field numbers are part of the wire format, so a schema migration which changes them needs the
+/// payloads crossing the boundary between the old and new schema to be translated. A
+/// `Renumbering` walks a payload with the message descriptors of the old schema, and rewrites the
+/// keys of the selected fields wherever their message appears, including in nested messages,
+/// groups and map entries. Field values, and the fields which aren't selected, including the
+/// fields unknown to the descriptors, are copied unchanged.
+///
+/// Fields are selected by their fully qualified name (e.g. `acme.User.email`).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let file_descriptor_set = FileDescriptorSet::decode(&descriptor_bytes[..])?;
+///
+/// let mut renumbering = Renumbering::new(&file_descriptor_set);
+/// renumbering
+///     .field("acme.User.email", 12)
+///     .field("acme.User.Address.street", 3);
+/// let migrated = renumbering.renumber("acme.User", &payload)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Renumbering<'a> {
+    messages: Messages<'a>,
+    fields: BTreeMap<String, u32>,
+}
+
+impl<'a> Renumbering<'a> {
+    /// Creates a renumbering for the messages of a `FileDescriptorSet`, which moves no fields.
+    pub fn new(file_descriptor_set: &'a FileDescriptorSet) -> Renumbering<'a> {
+        Renumbering {
+            messages: Messages::new(file_descriptor_set),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Moves a field, given by its fully qualified name, to a new field number.
+    pub fn field<S>(&mut self, name: S, tag: u32) -> &mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.fields.insert(trim_dot(name.as_ref()).into(), tag);
+        self
+    }
+
+    /// Renumbers the fields of an encoded message, given the fully qualified name of its type.
+    ///
+    /// An error is returned if the message type is unknown, if the payload can't be decoded, or
+    /// if a message encountered in the payload would be left with an invalid field number, or
+    /// with two fields sharing a number, including a field unknown to the descriptors.
+    pub fn renumber(&self, message: &str, buf: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        self.messages.rewrite(message, buf, self)
+    }
+}
+
+impl<'a> Rewrite for Renumbering<'a> {
+    /// Returns the new field numbers of the fields of a message, in declaration order.
+    fn tags(
+        &self,
+        message: &str,
+        descriptor: &DescriptorProto,
+    ) -> Result<Option<Vec<u32>>, DecodeError> {
+        let mut tags = Vec::with_capacity(descriptor.field.len());
+        let mut unique = BTreeSet::new();
+        for field in &descriptor.field {
+            let tag = self
+                .fields
+                .get(format!("{}.{}", message, field.name()).as_str())
+                .copied()
+                .unwrap_or(field.number() as u32);
+            if !(MIN_TAG..=MAX_TAG).contains(&tag) {
+                return Err(DecodeError::new(format!(
+                    "invalid field number for {}.{}: {}",
+                    message,
+                    field.name(),
+                    tag
+                )));
+            }
+            if !unique.insert(tag) {
+                return Err(DecodeError::new(format!(
+                    "field number {} is used twice in {}",
+                    tag, message
+                )));
+            }
+            tags.push(tag);
+        }
+        Ok(Some(tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+    use prost::encoding::{encode_key, encode_varint, WireType};
+    use prost::{DecodeMessage, EncodeMessage, Message};
+
+    use super::*;
+    use crate::field_descriptor_proto::{Label, Type};
+    use crate::walk::fixtures::{self, field, message};
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Old {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(message, optional, tag = "2")]
+        child: Option<Child>,
+        #[prost(int32, repeated, tag = "3")]
+        values: Vec<i32>,
+        #[prost(string, tag = "9")]
+        unknown: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct New {
+        #[prost(string, tag = "4")]
+        name: String,
+        #[prost(message, optional, tag = "2")]
+        child: Option<NewChild>,
+        #[prost(int32, repeated, tag = "5")]
+        values: Vec<i32>,
+        #[prost(string, tag = "9")]
+        unknown: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Child {
+        #[prost(uint64, tag = "1")]
+        id: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct NewChild {
+        #[prost(uint64, tag = "7")]
+        id: u64,
+    }
+
+    fn file_descriptor_set() -> FileDescriptorSet {
+        let child = message(
+            "Child",
+            vec![field("id", 1, Type::Uint64, Label::Optional, None)],
+            vec![],
+        );
+        let old = message(
+            "Old",
+            vec![
+                field("name", 1, Type::String, Label::Optional, None),
+                field(
+                    "child",
+                    2,
+                    Type::Message,
+                    Label::Optional,
+                    Some(".acme.Old.Child"),
+                ),
+                field("values", 3, Type::Int32, Label::Repeated, None),
+                field(
+                    "legacy",
+                    6,
+                    Type::Group,
+                    Label::Optional,
+                    Some(".acme.Old.Child"),
+                ),
+            ],
+            vec![child],
+        );
+        fixtures::file_descriptor_set(old)
+    }
+
+    #[test]
+    fn renumber() {
+        let old = Old {
+            name: "widget".to_string(),
+            child: Some(Child { id: 300 }),
+            values: vec![1, -2, 3],
+            unknown: "kept".to_string(),
+        };
+        let payload = old.encode_to_vec();
+
+        let file_descriptor_set = file_descriptor_set();
+        let mut renumbering = Renumbering::new(&file_descriptor_set);
+        assert_eq!(renumbering.renumber("acme.Old", &payload).unwrap(), payload);
+
+        renumbering
+            .field("acme.Old.name", 4)
+            .field(".acme.Old.values", 5)
+            .field("acme.Old.Child.id", 7);
+        let renumbered = renumbering.renumber("acme.Old", &payload).unwrap();
+
+        let new = New::decode(&renumbered[..]).unwrap();
+        assert_eq!(new.name, old.name);
+        assert_eq!(new.values, old.values);
+        assert_eq!(new.unknown, old.unknown);
+        // Fields of nested messages are renumbered too.
+        assert_eq!(new.child, Some(NewChild { id: 300 }));
+
+        renumbering.field("acme.Old.values", 2);
+        assert!(renumbering.renumber("acme.Old", &payload).is_err());
+        renumbering.field("acme.Old.values", 0);
+        assert!(renumbering.renumber("acme.Old", &payload).is_err());
+    }
+
+    #[test]
+    fn unknown_field_collision() {
+        let old = Old {
+            unknown: "kept".to_string(),
+            ..Old::default()
+        };
+        let payload = old.encode_to_vec();
+
+        let file_descriptor_set = file_descriptor_set();
+        let mut renumbering = Renumbering::new(&file_descriptor_set);
+        renumbering.field("acme.Old.name", 9);
+        let error = renumbering.renumber("acme.Old", &payload).unwrap_err();
+        assert_eq!(
+            format!("{}", error),
+            "failed to decode Protobuf message: \
+             field number 9 of acme.Old is taken by a field unknown to the descriptor"
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let mut payload = Vec::new();
+        encode_key(6, WireType::StartGroup, &mut payload);
+        encode_key(1, WireType::Varint, &mut payload);
+        encode_varint(300, &mut payload);
+        encode_key(6, WireType::EndGroup, &mut payload);
+
+        let file_descriptor_set = file_descriptor_set();
+        let mut renumbering = Renumbering::new(&file_descriptor_set);
+        renumbering
+            .field("acme.Old.legacy", 8)
+            .field("acme.Old.Child.id", 7);
+        let mut expected = Vec::new();
+        encode_key(8, WireType::StartGroup, &mut expected);
+        encode_key(7, WireType::Varint, &mut expected);
+        encode_varint(300, &mut expected);
+        encode_key(8, WireType::EndGroup, &mut expected);
+        assert_eq!(
+            renumbering.renumber("acme.Old", &payload).unwrap(),
+            expected
+        );
+
+        // A group must be closed by an end group tag with its own field number.
+        let last = payload.len() - 1;
+        payload.truncate(last);
+        encode_key(5, WireType::EndGroup, &mut payload);
+        assert!(renumbering.renumber("acme.Old", &payload).is_err());
+    }
+}
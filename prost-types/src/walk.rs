@@ -0,0 +1,291 @@
+//! Walking of encoded messages with their descriptors, shared by [`Anonymizer`] and
+//! [`Renumbering`].
+//!
+//! [`Anonymizer`]: crate::Anonymizer
+//! [`Renumbering`]: crate::Renumbering
+
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::format;
+use prost::alloc::rc::Rc;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::bytes::Buf;
+use prost::encoding::{
+    decode_key, decode_varint, encode_key, encode_varint, skip_field, DecodeContext, WireType,
+};
+use prost::DecodeError;
+
+use crate::field_descriptor_proto::Type;
+use crate::{DescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+
+/// The maximum nesting depth of the messages walked through.
+const DEPTH_LIMIT: u32 = 100;
+
+/// A rewriting of the fields of encoded messages, applied by [`Messages::rewrite`].
+pub(crate) trait Rewrite {
+    /// Returns the field numbers the fields of a message are written with, in declaration order,
+    /// or `None` if they keep their numbers.
+    ///
+    /// Called once per message type encountered in a payload.
+    fn tags(
+        &self,
+        _message: &str,
+        _descriptor: &DescriptorProto,
+    ) -> Result<Option<Vec<u32>>, DecodeError> {
+        Ok(None)
+    }
+
+    /// Writes a known field of a message, whose key has been decoded, with the field number
+    /// `tag`, or returns `false` without consuming anything to have it copied as usual.
+    fn rewrite_field(
+        &self,
+        _message: &str,
+        _field: &FieldDescriptorProto,
+        _tag: u32,
+        _wire_type: WireType,
+        _buf: &mut &[u8],
+        _out: &mut Vec<u8>,
+    ) -> Result<bool, DecodeError> {
+        Ok(false)
+    }
+}
+
+/// The message descriptors of a `FileDescriptorSet`, by fully qualified name.
+#[derive(Clone, Debug)]
+pub(crate) struct Messages<'a> {
+    messages: BTreeMap<String, &'a DescriptorProto>,
+}
+
+impl<'a> Messages<'a> {
+    pub(crate) fn new(file_descriptor_set: &'a FileDescriptorSet) -> Messages<'a> {
+        let mut messages = BTreeMap::new();
+        for file in &file_descriptor_set.file {
+            for message in &file.message_type {
+                index_messages(file.package(), message, &mut messages);
+            }
+        }
+        Messages { messages }
+    }
+
+    /// Copies an encoded message, given the fully qualified name of its type, with its fields
+    /// and the fields of the messages nested in it rewritten.
+    ///
+    /// Fields unknown to the descriptors are copied unchanged.
+    pub(crate) fn rewrite<R>(
+        &self,
+        message: &str,
+        mut buf: &[u8],
+        rewrite: &R,
+    ) -> Result<Vec<u8>, DecodeError>
+    where
+        R: Rewrite,
+    {
+        let mut walk = Walk {
+            messages: self,
+            rewrite,
+            tags: BTreeMap::new(),
+        };
+        let mut out = Vec::with_capacity(buf.len());
+        walk.message(message, &mut buf, &mut out, None, 0)?;
+        Ok(out)
+    }
+}
+
+/// A walk through an encoded message.
+struct Walk<'m, 'a, R> {
+    messages: &'m Messages<'a>,
+    rewrite: &'m R,
+    /// The field numbers the fields of each message type walked through are written with.
+    tags: BTreeMap<&'m str, Option<Rc<[u32]>>>,
+}
+
+impl<'m, 'a, R> Walk<'m, 'a, R>
+where
+    R: Rewrite,
+{
+    /// Copies a message, or the contents of a group if `group` is its field number before and
+    /// after rewriting, with its fields rewritten.
+    fn message(
+        &mut self,
+        name: &str,
+        buf: &mut &[u8],
+        out: &mut Vec<u8>,
+        group: Option<(u32, u32)>,
+        depth: u32,
+    ) -> Result<(), DecodeError> {
+        if depth >= DEPTH_LIMIT {
+            return Err(DecodeError::new("recursion limit reached"));
+        }
+        let name = trim_dot(name);
+        let messages = self.messages;
+        let (name, descriptor) = match messages.messages.get_key_value(name) {
+            Some((name, &descriptor)) => (name.as_str(), descriptor),
+            None => return Err(DecodeError::new(format!("unknown message type: {}", name))),
+        };
+        let tags = match self.tags.get(name) {
+            Some(tags) => tags.clone(),
+            None => {
+                let tags = self.rewrite.tags(name, descriptor)?.map(Rc::from);
+                self.tags.insert(name, tags.clone());
+                tags
+            }
+        };
+
+        while buf.has_remaining() {
+            let start = *buf;
+            let (tag, wire_type) = decode_key(buf)?;
+            if wire_type == WireType::EndGroup {
+                return match group {
+                    Some((group, new_group)) if group == tag => {
+                        encode_key(new_group, wire_type, out);
+                        Ok(())
+                    }
+                    _ => Err(DecodeError::new("unexpected end group tag")),
+                };
+            }
+
+            let index = match descriptor
+                .field
+                .iter()
+                .position(|field| field.number() as u32 == tag)
+            {
+                Some(index) => index,
+                None => {
+                    if let Some(ref tags) = tags {
+                        if tags.contains(&tag) {
+                            return Err(DecodeError::new(format!(
+                                "field number {} of {} is taken by a field unknown to the descriptor",
+                                tag, name
+                            )));
+                        }
+                    }
+                    skip_field(wire_type, tag, buf, DecodeContext::default())?;
+                    out.extend_from_slice(&start[..start.len() - buf.len()]);
+                    continue;
+                }
+            };
+            let field = &descriptor.field[index];
+            let new_tag = tags.as_ref().map_or(tag, |tags| tags[index]);
+            if self
+                .rewrite
+                .rewrite_field(name, field, new_tag, wire_type, buf, out)?
+            {
+                continue;
+            }
+
+            match (field.r#type(), wire_type) {
+                (Type::Message, WireType::LengthDelimited) => {
+                    let mut value = length_delimited(buf)?;
+                    let mut nested = Vec::with_capacity(value.len());
+                    self.message(field.type_name(), &mut value, &mut nested, None, depth + 1)?;
+                    encode_key(new_tag, wire_type, out);
+                    encode_varint(nested.len() as u64, out);
+                    out.extend_from_slice(&nested);
+                }
+                (Type::Group, WireType::StartGroup) => {
+                    encode_key(new_tag, wire_type, out);
+                    self.message(field.type_name(), buf, out, Some((tag, new_tag)), depth + 1)?;
+                }
+                _ => {
+                    let value = *buf;
+                    skip_field(wire_type, tag, buf, DecodeContext::default())?;
+                    encode_key(new_tag, wire_type, out);
+                    out.extend_from_slice(&value[..value.len() - buf.len()]);
+                }
+            }
+        }
+
+        if group.is_some() {
+            return Err(DecodeError::new("unexpected end of group"));
+        }
+        Ok(())
+    }
+}
+
+/// Records the descriptors of a message and its nested messages by fully qualified name.
+fn index_messages<'a>(
+    scope: &str,
+    message: &'a DescriptorProto,
+    messages: &mut BTreeMap<String, &'a DescriptorProto>,
+) {
+    let name = if scope.is_empty() {
+        message.name().into()
+    } else {
+        format!("{}.{}", scope, message.name())
+    };
+    for nested in &message.nested_type {
+        index_messages(&name, nested, messages);
+    }
+    messages.insert(name, message);
+}
+
+/// Strips the leading dot of a fully qualified name, as found in descriptors.
+pub(crate) fn trim_dot(name: &str) -> &str {
+    name.strip_prefix('.').unwrap_or(name)
+}
+
+/// Decodes the value of a length-delimited field.
+pub(crate) fn length_delimited<'b>(buf: &mut &'b [u8]) -> Result<&'b [u8], DecodeError> {
+    let len = decode_varint(buf)?;
+    if len > buf.len() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    let (value, rest) = buf.split_at(len as usize);
+    *buf = rest;
+    Ok(value)
+}
+
+/// Builders of the descriptors used by the tests.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+
+    use super::*;
+    use crate::field_descriptor_proto::Label;
+    use crate::FileDescriptorProto;
+
+    /// Returns the descriptor of a field, whose type is `type_name` if it's a message or group.
+    pub(crate) fn field(
+        name: &str,
+        number: i32,
+        ty: Type,
+        label: Label,
+        type_name: Option<&str>,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(ty as i32),
+            label: Some(label as i32),
+            type_name: type_name.map(ToString::to_string),
+            ..FieldDescriptorProto::default()
+        }
+    }
+
+    /// Returns the descriptor of a message.
+    pub(crate) fn message(
+        name: &str,
+        field: Vec<FieldDescriptorProto>,
+        nested_type: Vec<DescriptorProto>,
+    ) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_string()),
+            field,
+            nested_type,
+            ..DescriptorProto::default()
+        }
+    }
+
+    /// Returns a descriptor set with a single file declaring `message` in the `acme` package.
+    pub(crate) fn file_descriptor_set(message: DescriptorProto) -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("acme.proto".to_string()),
+                package: Some("acme".to_string()),
+                message_type: vec![message],
+                ..FileDescriptorProto::default()
+            }],
+        }
+    }
+}
@@ -5,7 +5,7 @@ use std::path::Path;
 
 use cfg_if::cfg_if;
 use criterion::{criterion_group, criterion_main, Criterion};
-use prost::Message;
+use prost::{DecodeMessage, EncodeMessage};
 
 use protobuf::benchmarks::{
     dataset, google_message3::GoogleMessage3, google_message4::GoogleMessage4, proto2, proto3,
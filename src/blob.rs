@@ -0,0 +1,248 @@
+//! A Protobuf `bytes` value which shares the buffer it was decoded from.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use ::bytes::{Buf, BufMut, Bytes};
+
+use crate::encoding::BytesAdapter;
+
+/// The value of a Protobuf `bytes` field, which is only copied when it's modified.
+///
+/// When a message is decoded from a [`Bytes`] buffer, a `Blob` field refers to the range of the
+/// buffer holding its value, so multi-megabyte attachments aren't copied during decoding. The
+/// value is copied into a vector of its own the first time it's accessed mutably, with
+/// [`Blob::to_mut`]. Decoding from other buffers copies the value, as for `Vec<u8>` fields.
+///
+/// `prost-build` generates `Blob` fields with `Config::blob`.
+#[derive(Clone)]
+pub struct Blob {
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    Shared(Bytes),
+    /// A value copied out of the buffer it was decoded from, which nothing else refers to.
+    Copied(Bytes),
+    Owned(Vec<u8>),
+}
+
+impl Blob {
+    /// Creates an empty `Blob`.
+    pub const fn new() -> Blob {
+        Blob {
+            repr: Repr::Owned(Vec::new()),
+        }
+    }
+
+    /// Returns the bytes of the value.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self.repr {
+            Repr::Shared(ref bytes) | Repr::Copied(ref bytes) => bytes,
+            Repr::Owned(ref vec) => vec,
+        }
+    }
+
+    /// Returns the bytes of the value for modification, copying them if they are shared.
+    pub fn to_mut(&mut self) -> &mut Vec<u8> {
+        if let Repr::Shared(ref bytes) | Repr::Copied(ref bytes) = self.repr {
+            self.repr = Repr::Owned(bytes.to_vec());
+        }
+        match self.repr {
+            Repr::Owned(ref mut vec) => vec,
+            Repr::Shared(_) | Repr::Copied(_) => unreachable!(),
+        }
+    }
+
+    /// Converts the value into a `Bytes`, without copying it.
+    pub fn into_bytes(self) -> Bytes {
+        match self.repr {
+            Repr::Shared(bytes) | Repr::Copied(bytes) => bytes,
+            Repr::Owned(vec) => Bytes::from(vec),
+        }
+    }
+
+    /// Converts the value into a vector, copying it if it's shared.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.repr {
+            Repr::Shared(bytes) | Repr::Copied(bytes) => bytes.to_vec(),
+            Repr::Owned(vec) => vec,
+        }
+    }
+
+    /// Returns `true` if the value refers to a shared buffer, rather than to a copy of its own.
+    ///
+    /// Values decoded from a [`Bytes`] buffer, or converted from a `Bytes`, are shared; values
+    /// decoded from other buffers aren't.
+    pub fn is_shared(&self) -> bool {
+        matches!(self.repr, Repr::Shared(_))
+    }
+
+    /// Returns the length of the value in bytes.
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// Returns `true` if the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+}
+
+impl Default for Blob {
+    fn default() -> Blob {
+        Blob::new()
+    }
+}
+
+/// Only the length is shown, since blobs are usually too large to print.
+impl fmt::Debug for Blob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blob").field("len", &self.len()).finish()
+    }
+}
+
+impl PartialEq for Blob {
+    fn eq(&self, other: &Blob) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for Blob {}
+
+impl PartialOrd for Blob {
+    fn partial_cmp(&self, other: &Blob) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Blob {
+    fn cmp(&self, other: &Blob) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for Blob {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl AsRef<[u8]> for Blob {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<Bytes> for Blob {
+    fn from(bytes: Bytes) -> Blob {
+        Blob {
+            repr: Repr::Shared(bytes),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Blob {
+    fn from(vec: Vec<u8>) -> Blob {
+        Blob {
+            repr: Repr::Owned(vec),
+        }
+    }
+}
+
+impl From<&[u8]> for Blob {
+    fn from(bytes: &[u8]) -> Blob {
+        Blob::from(bytes.to_vec())
+    }
+}
+
+impl BytesAdapter for Blob {
+    fn len(&self) -> usize {
+        Blob::len(self)
+    }
+
+    fn replace_with<B>(&mut self, mut buf: B)
+    where
+        B: Buf,
+    {
+        let chunk = buf.chunk().as_ptr();
+        let bytes = buf.copy_to_bytes(buf.remaining());
+        // `Bytes` buffers return a range of themselves, and other buffers a copy.
+        self.repr = if !bytes.is_empty() && bytes.as_ptr() == chunk {
+            Repr::Shared(bytes)
+        } else {
+            Repr::Copied(bytes)
+        };
+    }
+
+    fn append_to<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        buf.put_slice(self.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::format;
+
+    use super::*;
+
+    use crate::encoding::{bytes, DecodeContext, WireType};
+
+    #[test]
+    fn shared_until_modified() {
+        let mut buf = Vec::new();
+        bytes::encode(1, &b"attachment".to_vec(), &mut buf);
+        let mut input = Bytes::from(buf.clone());
+        input.advance(1);
+        let data = input.slice(1..).as_ptr();
+
+        let mut value = Blob::new();
+        bytes::merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut input,
+            DecodeContext::default(),
+        )
+        .unwrap();
+        assert!(value.is_shared());
+        assert_eq!(value.as_bytes().as_ptr(), data);
+        assert_eq!(value, Blob::from(&b"attachment"[..]));
+        assert_eq!(format!("{:?}", value), "Blob { len: 10 }");
+
+        let mut reencoded = Vec::new();
+        bytes::encode(1, &value, &mut reencoded);
+        assert_eq!(reencoded, buf);
+
+        value.to_mut().extend_from_slice(b"!");
+        assert!(!value.is_shared());
+        assert_eq!(value.into_vec(), b"attachment!");
+    }
+
+    #[test]
+    fn copied_from_slice() {
+        let mut buf = Vec::new();
+        bytes::encode(1, &b"attachment".to_vec(), &mut buf);
+
+        let mut value = Blob::new();
+        bytes::merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut &buf[1..],
+            DecodeContext::default(),
+        )
+        .unwrap();
+        assert!(!value.is_shared());
+        assert_ne!(value.as_bytes().as_ptr(), buf[2..].as_ptr());
+        assert_eq!(value, Blob::from(&b"attachment"[..]));
+        assert_eq!(value.into_bytes(), &b"attachment"[..]);
+    }
+}
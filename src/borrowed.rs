@@ -0,0 +1,166 @@
+//! Zero-copy decoding of messages borrowing from their encoded buffer.
+
+use crate::encoding::{DecodeContext, SeenFields, WireType};
+use crate::{DecodeConfig, DecodeError, DecodeMessage};
+
+/// A message which can be decoded from a byte slice, borrowing its `string` and `bytes` fields
+/// from the slice rather than copying them.
+///
+/// Decoding a `String` or `Vec<u8>` field copies its value out of the buffer. When the buffer is
+/// kept alive anyway, as with a memory-mapped file or a received frame, huge messages can be
+/// decoded without copying their payloads by declaring the fields as `Cow<'a, str>` or
+/// `&'a [u8]` with the `borrowed` attribute, and deriving `DecodeBorrowed` alongside
+/// `EncodeMessage`:
+///
+/// ```rust,ignore
+/// #[derive(Clone, PartialEq, prost::EncodeMessage, prost::DecodeBorrowed)]
+/// struct Document<'a> {
+///     #[prost(string, borrowed, tag = "1")]
+///     title: Cow<'a, str>,
+///     #[prost(bytes, borrowed, tag = "2")]
+///     body: &'a [u8],
+///     #[prost(message, repeated, tag = "3")]
+///     sections: Vec<Section<'a>>,
+/// }
+///
+/// let document = Document::decode_borrowed(&buf[..])?;
+/// ```
+///
+/// The derived message must have a lifetime parameter, which is the lifetime of the buffer. A
+/// `Cow<'a, str>` field only owns its value if invalid UTF-8 was replaced, following
+/// `InvalidUtf8Policy::Replace`. Nested messages must implement `DecodeBorrowed` as well, which
+/// all implementations of [`DecodeMessage`] do. Borrowed fields can't be members of a `oneof`.
+pub trait DecodeBorrowed<'a>: Sized {
+    /// Decodes a field from a buffer, and merges it into `self`.
+    ///
+    /// Meant to be used only by `DecodeBorrowed` implementations.
+    #[doc(hidden)]
+    fn merge_field_borrowed(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>;
+
+    /// Returns `true` if `tag` is the tag of a non-repeated field of the message, including the
+    /// members of a `oneof`.
+    ///
+    /// Meant to be used only by `DecodeBorrowed` implementations.
+    #[doc(hidden)]
+    fn is_singular_field(_tag: u32) -> bool {
+        false
+    }
+
+    /// Returns the identifier of the non-repeated field with tag `tag` when checking for
+    /// duplicate fields, which is shared by the members of a `oneof`.
+    ///
+    /// Meant to be used only by `DecodeBorrowed` implementations.
+    #[doc(hidden)]
+    fn singular_field_id(tag: u32) -> u32 {
+        tag
+    }
+
+    /// Calls `f` with the tag and name of each proto2 `required` field of the message, including
+    /// those of flattened messages.
+    ///
+    /// Meant to be used only by `DecodeBorrowed` implementations.
+    #[doc(hidden)]
+    fn for_each_required_field(_f: &mut dyn FnMut(u32, &'static str)) {}
+
+    /// Decodes the fields of the message up to the end of `buf`, and merges them into `self`.
+    ///
+    /// Meant to be used only by `DecodeBorrowed` implementations.
+    #[doc(hidden)]
+    fn merge_fields_borrowed(
+        &mut self,
+        buf: &mut &'a [u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        let mut seen = SeenFields::new(&ctx);
+        while !buf.is_empty() {
+            if let Some((tag, wire_type)) = ctx.decode_key(buf)? {
+                let id = if Self::is_singular_field(tag) {
+                    Some(Self::singular_field_id(tag))
+                } else {
+                    None
+                };
+                seen.insert_tag(tag, id)?;
+                self.merge_field_borrowed(tag, wire_type, buf, ctx.clone())?;
+            }
+        }
+        seen.check_required_fields(Self::for_each_required_field)
+    }
+
+    /// Decodes an instance of the message from a buffer, borrowing from it.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_borrowed(buf: &'a [u8]) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        Self::decode_borrowed_with_config(buf, DecodeConfig::default())
+    }
+
+    /// Decodes an instance of the message from a buffer, borrowing from it, with the given
+    /// decode options.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_borrowed_with_config(buf: &'a [u8], config: DecodeConfig) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        let mut message = Self::default();
+        message.merge_borrowed_with_config(buf, config)?;
+        Ok(message)
+    }
+
+    /// Decodes an instance of the message from a buffer, borrowing from it, and merges it into
+    /// `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_borrowed(&mut self, buf: &'a [u8]) -> Result<(), DecodeError> {
+        self.merge_borrowed_with_config(buf, DecodeConfig::default())
+    }
+
+    /// Decodes an instance of the message from a buffer, borrowing from it, with the given decode
+    /// options, and merges it into `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_borrowed_with_config(
+        &mut self,
+        mut buf: &'a [u8],
+        config: DecodeConfig,
+    ) -> Result<(), DecodeError> {
+        config.check_message_size(buf.len() as u64)?;
+        self.merge_fields_borrowed(&mut buf, DecodeContext::with_config(config))
+    }
+}
+
+/// Messages which own their fields are decoded as usual, and can be nested in borrowed messages.
+impl<'a, M> DecodeBorrowed<'a> for M
+where
+    M: DecodeMessage,
+{
+    fn merge_field_borrowed(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        self.merge_field(tag, wire_type, buf, ctx)
+    }
+
+    fn is_singular_field(tag: u32) -> bool {
+        M::is_singular_field(tag)
+    }
+
+    fn singular_field_id(tag: u32) -> u32 {
+        M::singular_field_id(tag)
+    }
+
+    fn for_each_required_field(f: &mut dyn FnMut(u32, &'static str)) {
+        M::for_each_required_field(f)
+    }
+}
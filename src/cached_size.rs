@@ -0,0 +1,86 @@
+//! Memoization of the encoded length of messages.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+/// Marks a cache which holds no length.
+const UNSET: usize = usize::MAX;
+
+/// The encoded length of a message, cached between sizing the message and writing it.
+///
+/// Encoding a message writes a length before each nested message, so the length of a message
+/// nested `n` levels deep is computed `n` times. A message with a `CachedSize` field, declared
+/// with `#[prost(cached_size)]` and generated by `prost_build::Config::cached_size`, stores its
+/// length whenever [`EncodeMessage::encoded_len`](crate::EncodeMessage::encoded_len) computes
+/// it, and the length is reused for the length prefix when the message is written as a nested
+/// message field of a message which caches its length too, as the C++ implementation does.
+///
+/// The encoding methods of `Message` size the message before writing it, so the cached lengths
+/// are always up to date while writing. The cache is invisible otherwise: all caches compare
+/// equal, and hash to nothing, so they don't affect the derived `PartialEq` or `Hash` of the
+/// message.
+pub struct CachedSize(AtomicUsize);
+
+impl CachedSize {
+    /// Creates an empty cache.
+    pub const fn new() -> CachedSize {
+        CachedSize(AtomicUsize::new(UNSET))
+    }
+
+    /// Returns the cached length, if any.
+    pub fn get(&self) -> Option<usize> {
+        match self.0.load(AtomicOrdering::Relaxed) {
+            UNSET => None,
+            len => Some(len),
+        }
+    }
+
+    /// Caches a length.
+    pub fn set(&self, len: usize) {
+        self.0.store(len, AtomicOrdering::Relaxed);
+    }
+}
+
+impl Default for CachedSize {
+    fn default() -> CachedSize {
+        CachedSize::new()
+    }
+}
+
+impl Clone for CachedSize {
+    fn clone(&self) -> CachedSize {
+        CachedSize(AtomicUsize::new(self.0.load(AtomicOrdering::Relaxed)))
+    }
+}
+
+impl fmt::Debug for CachedSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CachedSize").field(&self.get()).finish()
+    }
+}
+
+impl PartialEq for CachedSize {
+    fn eq(&self, _: &CachedSize) -> bool {
+        true
+    }
+}
+
+impl Eq for CachedSize {}
+
+impl PartialOrd for CachedSize {
+    fn partial_cmp(&self, other: &CachedSize) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CachedSize {
+    fn cmp(&self, _: &CachedSize) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Hash for CachedSize {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
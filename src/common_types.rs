@@ -0,0 +1,541 @@
+//! Message implementations for common standard library and ecosystem types.
+//!
+//! Addresses, paths and UUIDs are otherwise carried in ad-hoc `string` or `bytes` fields, with
+//! conversion code in every project. Each type here is encoded as a small message, whose schema
+//! is given in the documentation of its implementation, so other languages can decode it.
+//!
+//! The implementations are opt-in: the `std-types` feature enables the standard library types,
+//! and the `uuid` feature enables `uuid::Uuid`. IP and socket addresses have no default value, so
+//! they are wrapped in [`IpAddress`] and [`SocketAddress`].
+
+#[cfg(feature = "std-types")]
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ::bytes::{Buf, BufMut};
+
+use crate::encoding::{
+    bytes, encode_key, encode_varint, encoded_len_varint, key_len, skip_field, DecodeContext,
+    WireType,
+};
+use crate::{DecodeError, DecodeMessage, EncodeMessage};
+
+/// Encodes a `bytes` field from a slice.
+fn encode_slice<B>(tag: u32, value: &[u8], buf: &mut B)
+where
+    B: BufMut,
+{
+    encode_key(tag, WireType::LengthDelimited, buf);
+    encode_varint(value.len() as u64, buf);
+    buf.put_slice(value);
+}
+
+/// Returns the encoded length of a `bytes` field holding a slice.
+fn encoded_len_slice(tag: u32, value: &[u8]) -> usize {
+    key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+}
+
+#[cfg(feature = "std-types")]
+mod net {
+    use core::cmp::Ordering;
+    use core::fmt;
+    use core::hash::{Hash, Hasher};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    use super::*;
+    use crate::encoding::uint32;
+
+    /// An [`IpAddr`] which implements `Message`, as the message:
+    ///
+    /// ```proto
+    /// message IpAddr {
+    ///   // The address in network byte order: 4 bytes for IPv4, or 16 bytes for IPv6.
+    ///   bytes address = 1;
+    /// }
+    /// ```
+    ///
+    /// The address is always encoded, so that the unspecified IPv4 and IPv6 addresses remain
+    /// distinct. The default value is the unspecified IPv4 address, so unlike `IpAddr`, an
+    /// `IpAddress` can be decoded with [`DecodeMessage::decode`] and used as a field of a derived
+    /// message.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct IpAddress(pub IpAddr);
+
+    impl Default for IpAddress {
+        fn default() -> IpAddress {
+            IpAddress(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        }
+    }
+
+    impl From<IpAddr> for IpAddress {
+        fn from(ip: IpAddr) -> IpAddress {
+            IpAddress(ip)
+        }
+    }
+
+    impl From<IpAddress> for IpAddr {
+        fn from(ip: IpAddress) -> IpAddr {
+            ip.0
+        }
+    }
+
+    impl EncodeMessage for IpAddress {
+        fn encode_raw<B>(&self, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_ip(&self.0, buf)
+        }
+        fn encoded_len(&self) -> usize {
+            encoded_len_ip(&self.0)
+        }
+    }
+
+    impl DecodeMessage for IpAddress {
+        fn merge_field<B>(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut B,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            B: Buf,
+        {
+            if tag == 1 {
+                self.0 = merge_ip(wire_type, buf, ctx)?;
+                Ok(())
+            } else {
+                skip_field(wire_type, tag, buf, ctx)
+            }
+        }
+        fn is_singular_field(tag: u32) -> bool {
+            tag == 1
+        }
+        fn clear(&mut self) {
+            *self = IpAddress::default();
+        }
+    }
+
+    /// A [`SocketAddr`] which implements `Message`, as the message:
+    ///
+    /// ```proto
+    /// message SocketAddr {
+    ///   // The IP address in network byte order: 4 bytes for IPv4, or 16 bytes for IPv6.
+    ///   bytes address = 1;
+    ///   // The port number.
+    ///   uint32 port = 2;
+    ///   // The scope ID of an IPv6 address, ignored for IPv4 addresses.
+    ///   uint32 scope_id = 3;
+    /// }
+    /// ```
+    ///
+    /// The fields may be decoded in any order: a scope ID decoded while the address is IPv4 is
+    /// kept aside, and given to an IPv6 address decoded later. The scope ID of an IPv6 address is
+    /// always encoded, even when it's zero, so that merging a socket address replaces the scope ID
+    /// along with the address. The flow information of IPv6 socket addresses isn't encoded.
+    ///
+    /// The default value is the unspecified IPv4 address with port zero.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct SocketAddress(pub SocketAddr, PendingScopeId);
+
+    /// The scope ID decoded while the address of a [`SocketAddress`] is IPv4, which doesn't take
+    /// part in comparisons.
+    #[derive(Clone, Copy, Default)]
+    struct PendingScopeId(u32);
+
+    impl PartialEq for PendingScopeId {
+        fn eq(&self, _: &PendingScopeId) -> bool {
+            true
+        }
+    }
+
+    impl Eq for PendingScopeId {}
+
+    impl PartialOrd for PendingScopeId {
+        fn partial_cmp(&self, other: &PendingScopeId) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for PendingScopeId {
+        fn cmp(&self, _: &PendingScopeId) -> Ordering {
+            Ordering::Equal
+        }
+    }
+
+    impl Hash for PendingScopeId {
+        fn hash<H: Hasher>(&self, _: &mut H) {}
+    }
+
+    impl fmt::Debug for SocketAddress {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("SocketAddress").field(&self.0).finish()
+        }
+    }
+
+    impl Default for SocketAddress {
+        fn default() -> SocketAddress {
+            SocketAddress::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))
+        }
+    }
+
+    impl From<SocketAddr> for SocketAddress {
+        fn from(addr: SocketAddr) -> SocketAddress {
+            SocketAddress(addr, PendingScopeId::default())
+        }
+    }
+
+    impl From<SocketAddress> for SocketAddr {
+        fn from(addr: SocketAddress) -> SocketAddr {
+            addr.0
+        }
+    }
+
+    impl EncodeMessage for SocketAddress {
+        fn encode_raw<B>(&self, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_ip(&self.0.ip(), buf);
+            if self.0.port() != 0 {
+                uint32::encode(2, &u32::from(self.0.port()), buf);
+            }
+            if let SocketAddr::V6(ref addr) = self.0 {
+                uint32::encode(3, &addr.scope_id(), buf);
+            }
+        }
+        fn encoded_len(&self) -> usize {
+            let mut len = encoded_len_ip(&self.0.ip());
+            if self.0.port() != 0 {
+                len += uint32::encoded_len(2, &u32::from(self.0.port()));
+            }
+            if let SocketAddr::V6(ref addr) = self.0 {
+                len += uint32::encoded_len(3, &addr.scope_id());
+            }
+            len
+        }
+    }
+
+    impl DecodeMessage for SocketAddress {
+        fn merge_field<B>(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut B,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            B: Buf,
+        {
+            match tag {
+                1 => {
+                    let ip = merge_ip(wire_type, buf, ctx)?;
+                    let scope_id = match self.0 {
+                        SocketAddr::V4(_) => (self.1).0,
+                        SocketAddr::V6(ref addr) => addr.scope_id(),
+                    };
+                    self.0.set_ip(ip);
+                    match self.0 {
+                        SocketAddr::V4(_) => self.1 = PendingScopeId(scope_id),
+                        SocketAddr::V6(ref mut addr) => {
+                            addr.set_scope_id(scope_id);
+                            self.1 = PendingScopeId::default();
+                        }
+                    }
+                    Ok(())
+                }
+                2 => {
+                    let mut port = 0;
+                    uint32::merge(wire_type, &mut port, buf, ctx)?;
+                    if port > u32::from(u16::MAX) {
+                        return Err(DecodeError::new("invalid port number"));
+                    }
+                    self.0.set_port(port as u16);
+                    Ok(())
+                }
+                3 => {
+                    let mut scope_id = 0;
+                    uint32::merge(wire_type, &mut scope_id, buf, ctx)?;
+                    match self.0 {
+                        SocketAddr::V4(_) => self.1 = PendingScopeId(scope_id),
+                        SocketAddr::V6(ref mut addr) => addr.set_scope_id(scope_id),
+                    }
+                    Ok(())
+                }
+                _ => skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+        fn is_singular_field(tag: u32) -> bool {
+            matches!(tag, 1..=3)
+        }
+        fn clear(&mut self) {
+            *self = SocketAddress::default();
+        }
+    }
+
+    fn encode_ip<B>(ip: &IpAddr, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        match *ip {
+            IpAddr::V4(ref ip) => encode_slice(1, &ip.octets(), buf),
+            IpAddr::V6(ref ip) => encode_slice(1, &ip.octets(), buf),
+        }
+    }
+
+    fn encoded_len_ip(ip: &IpAddr) -> usize {
+        match *ip {
+            IpAddr::V4(ref ip) => encoded_len_slice(1, &ip.octets()),
+            IpAddr::V6(ref ip) => encoded_len_slice(1, &ip.octets()),
+        }
+    }
+
+    fn merge_ip<B>(
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<IpAddr, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut value = Vec::new();
+        bytes::merge(wire_type, &mut value, buf, ctx)?;
+        match value.len() {
+            4 => Ok(IpAddr::V4(Ipv4Addr::new(
+                value[0], value[1], value[2], value[3],
+            ))),
+            16 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(&value);
+                Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => Err(DecodeError::new("invalid IP address length")),
+        }
+    }
+}
+
+#[cfg(feature = "std-types")]
+pub use self::net::{IpAddress, SocketAddress};
+
+#[cfg(feature = "std-types")]
+mod path {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::encoding::string;
+
+    /// A file system path, as the message:
+    ///
+    /// ```proto
+    /// message Path {
+    ///   // The path, as UTF-8.
+    ///   string path = 1;
+    /// }
+    /// ```
+    ///
+    /// Paths which aren't valid Unicode are encoded lossily, with invalid sequences replaced by
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    impl EncodeMessage for PathBuf {
+        fn encode_raw<B>(&self, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            if !self.as_os_str().is_empty() {
+                encode_slice(1, self.to_string_lossy().as_bytes(), buf)
+            }
+        }
+        fn encoded_len(&self) -> usize {
+            if !self.as_os_str().is_empty() {
+                encoded_len_slice(1, self.to_string_lossy().as_bytes())
+            } else {
+                0
+            }
+        }
+    }
+
+    impl DecodeMessage for PathBuf {
+        fn merge_field<B>(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut B,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            B: Buf,
+        {
+            if tag == 1 {
+                let mut path = String::new();
+                string::merge(wire_type, &mut path, buf, ctx)?;
+                *self = PathBuf::from(path);
+                Ok(())
+            } else {
+                skip_field(wire_type, tag, buf, ctx)
+            }
+        }
+        fn is_singular_field(tag: u32) -> bool {
+            tag == 1
+        }
+        fn clear(&mut self) {
+            *self = PathBuf::new();
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+mod uuid {
+    use ::uuid::Uuid;
+
+    use super::*;
+
+    /// A UUID, as the message:
+    ///
+    /// ```proto
+    /// message Uuid {
+    ///   // The 16 bytes of the UUID, in the byte order of RFC 4122.
+    ///   bytes value = 1;
+    /// }
+    /// ```
+    ///
+    /// The nil UUID is the default value, and is encoded as an empty message.
+    impl EncodeMessage for Uuid {
+        fn encode_raw<B>(&self, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            if !self.is_nil() {
+                encode_slice(1, self.as_bytes(), buf)
+            }
+        }
+        fn encoded_len(&self) -> usize {
+            if !self.is_nil() {
+                encoded_len_slice(1, self.as_bytes())
+            } else {
+                0
+            }
+        }
+    }
+
+    impl DecodeMessage for Uuid {
+        fn merge_field<B>(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut B,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            B: Buf,
+        {
+            if tag == 1 {
+                let mut value = Vec::new();
+                bytes::merge(wire_type, &mut value, buf, ctx)?;
+                *self = Uuid::from_slice(&value)
+                    .map_err(|_| DecodeError::new("invalid UUID length"))?;
+                Ok(())
+            } else {
+                skip_field(wire_type, tag, buf, ctx)
+            }
+        }
+        fn is_singular_field(tag: u32) -> bool {
+            tag == 1
+        }
+        fn clear(&mut self) {
+            *self = Uuid::nil();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "std-types")]
+    #[test]
+    fn std_types() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+        use std::path::PathBuf;
+
+        let ips = [
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ];
+        for &ip in &ips {
+            let ip = IpAddress(ip);
+            let buf = ip.encode_to_vec();
+            assert_eq!(buf.len(), ip.encoded_len());
+            assert_eq!(IpAddress::decode(&*buf).unwrap(), ip);
+        }
+        assert_eq!(IpAddress::decode(&[][..]).unwrap(), IpAddress::default());
+        assert!(IpAddress::decode(&[0x0a, 0x02, 0x7f, 0x00][..]).is_err());
+
+        let v6 = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 443, 0, 3);
+        let addrs = [
+            SocketAddr::new(ips[1], 8080),
+            SocketAddr::V6(v6),
+            SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 443, 0, 0)),
+        ];
+        for &addr in &addrs {
+            let addr = SocketAddress::from(addr);
+            let buf = addr.encode_to_vec();
+            assert_eq!(buf.len(), addr.encoded_len());
+            assert_eq!(SocketAddress::decode(&*buf).unwrap(), addr);
+        }
+
+        // The scope ID may come before the address.
+        let mut buf = Vec::new();
+        crate::encoding::uint32::encode(3, &3, &mut buf);
+        crate::encoding::uint32::encode(2, &443, &mut buf);
+        encode_slice(1, &Ipv6Addr::LOCALHOST.octets(), &mut buf);
+        assert_eq!(
+            SocketAddress::decode(&*buf).unwrap(),
+            SocketAddress::from(SocketAddr::V6(v6))
+        );
+
+        // The scope ID of an IPv4 address is ignored, whether it comes before or after it.
+        let ipv4 = SocketAddress::from(SocketAddr::new(ips[1], 80));
+        let mut buf = Vec::new();
+        encode_slice(1, &[192, 168, 0, 1], &mut buf);
+        crate::encoding::uint32::encode(2, &80, &mut buf);
+        crate::encoding::uint32::encode(3, &5, &mut buf);
+        assert_eq!(SocketAddress::decode(&*buf).unwrap(), ipv4);
+        let mut buf = Vec::new();
+        crate::encoding::uint32::encode(3, &5, &mut buf);
+        crate::encoding::uint32::encode(2, &80, &mut buf);
+        encode_slice(1, &[192, 168, 0, 1], &mut buf);
+        assert_eq!(SocketAddress::decode(&*buf).unwrap(), ipv4);
+
+        // Merging replaces the scope ID along with the address.
+        let mut addr = SocketAddress::from(SocketAddr::V6(v6));
+        addr.merge(&*SocketAddress::from(addrs[2]).encode_to_vec())
+            .unwrap();
+        assert_eq!(addr, SocketAddress::from(addrs[2]));
+        addr.merge(&*SocketAddress::from(addrs[0]).encode_to_vec())
+            .unwrap();
+        assert_eq!(addr, SocketAddress::from(addrs[0]));
+
+        let path = PathBuf::from("/var/log/app.log");
+        let buf = path.encode_to_vec();
+        assert_eq!(buf.len(), path.encoded_len());
+        assert_eq!(PathBuf::decode(&*buf).unwrap(), path);
+        assert!(PathBuf::new().encode_to_vec().is_empty());
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn uuid() {
+        use ::uuid::Uuid;
+
+        let uuid = Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+        let buf = uuid.encode_to_vec();
+        assert_eq!(buf.len(), uuid.encoded_len());
+        assert_eq!(&buf[2..], uuid.as_bytes());
+        assert_eq!(Uuid::decode(&*buf).unwrap(), uuid);
+        assert!(Uuid::nil().encode_to_vec().is_empty());
+        assert!(Uuid::decode(&buf[..10]).is_err());
+        assert!(Uuid::decode(&[0x0a, 0x01, 0x00][..]).is_err());
+    }
+}
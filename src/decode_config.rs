@@ -0,0 +1,342 @@
+//! Options controlling how messages are decoded.
+
+use alloc::format;
+
+use crate::{DecodeError, DecodeErrorKind};
+
+/// Options controlling how messages are decoded, for use with
+/// [`DecodeMessage::decode_with_config`](crate::DecodeMessage::decode_with_config).
+///
+/// The default configuration decodes messages as described by the Protobuf specification, which
+/// is also how [`DecodeMessage::decode`](crate::DecodeMessage::decode) behaves.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::{DecodeConfig, DecodeMessage, DuplicateFieldPolicy};
+///
+/// let config = DecodeConfig::new().duplicate_fields(DuplicateFieldPolicy::Reject);
+///
+/// // Field 1 (a `uint32`) appears twice.
+/// let buf = [0x08, 0x01, 0x08, 0x02];
+/// assert_eq!(u32::decode(&buf[..]).unwrap(), 2);
+/// assert!(u32::decode_with_config(&buf[..], config).is_err());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeConfig {
+    pub(crate) duplicate_fields: DuplicateFieldPolicy,
+    pub(crate) reserved_wire_types: ReservedWireTypePolicy,
+    pub(crate) unknown_field_hook: Option<fn(u32, usize)>,
+    pub(crate) duplicate_field_hook: Option<fn(u32)>,
+    pub(crate) recursion_limit: u32,
+    pub(crate) max_message_size: usize,
+    pub(crate) max_field_size: usize,
+    pub(crate) unknown_fields: UnknownFieldPolicy,
+    pub(crate) invalid_utf8: InvalidUtf8Policy,
+    pub(crate) required_fields: RequiredFieldPolicy,
+}
+
+impl DecodeConfig {
+    /// Creates the default decode configuration.
+    pub const fn new() -> DecodeConfig {
+        DecodeConfig {
+            duplicate_fields: DuplicateFieldPolicy::LastWins,
+            reserved_wire_types: ReservedWireTypePolicy::Reject,
+            unknown_field_hook: None,
+            duplicate_field_hook: None,
+            recursion_limit: crate::RECURSION_LIMIT,
+            max_message_size: usize::MAX,
+            max_field_size: usize::MAX,
+            unknown_fields: UnknownFieldPolicy::Skip,
+            invalid_utf8: InvalidUtf8Policy::Reject,
+            required_fields: RequiredFieldPolicy::Ignore,
+        }
+    }
+
+    /// Sets how non-repeated fields which appear more than once in a message are handled.
+    ///
+    /// Defaults to [`DuplicateFieldPolicy::LastWins`].
+    pub fn duplicate_fields(mut self, policy: DuplicateFieldPolicy) -> DecodeConfig {
+        self.duplicate_fields = policy;
+        self
+    }
+
+    /// Sets how field keys with one of the reserved wire types 6 and 7 are handled.
+    ///
+    /// Defaults to [`ReservedWireTypePolicy::Reject`].
+    pub fn reserved_wire_types(mut self, policy: ReservedWireTypePolicy) -> DecodeConfig {
+        self.reserved_wire_types = policy;
+        self
+    }
+
+    /// Sets how deeply messages and groups may be nested in the decoded message.
+    ///
+    /// Decoding nested messages recurses, so the limit protects the stack from maliciously deep
+    /// input. Trusted data with deeper nesting can raise it, and applications running on small
+    /// stacks can lower it. The limit has no effect if the `no-recursion-limit` feature is
+    /// enabled.
+    ///
+    /// Defaults to 100, the default limit of the C++ implementation.
+    pub fn recursion_limit(mut self, limit: u32) -> DecodeConfig {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Sets the maximum size in bytes of the decoded message.
+    ///
+    /// Larger input is rejected before any of it is decoded, and length-delimited messages, as
+    /// read by [`MessageStreamDecoder`](crate::MessageStreamDecoder), are rejected as soon as
+    /// their length-delimiter is read. A [`PartialDecoder`](crate::PartialDecoder) rejects a
+    /// message once the bytes pushed, or the length of its last field, exceed the limit, after the
+    /// fields before it have been decoded. Defaults to no limit.
+    pub fn max_message_size(mut self, size: usize) -> DecodeConfig {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a single length-delimited value, such as a string, a
+    /// nested message or a packed repeated field, anywhere in the decoded message.
+    ///
+    /// Larger values are rejected as soon as their length is decoded.
+    ///
+    /// Defaults to no limit.
+    pub fn max_field_size(mut self, size: usize) -> DecodeConfig {
+        self.max_field_size = size;
+        self
+    }
+
+    /// Sets how fields which the message doesn't know are handled.
+    ///
+    /// Defaults to [`UnknownFieldPolicy::Skip`].
+    pub fn unknown_fields(mut self, policy: UnknownFieldPolicy) -> DecodeConfig {
+        self.unknown_fields = policy;
+        self
+    }
+
+    /// Sets how `string` values which aren't valid UTF-8 are handled.
+    ///
+    /// Defaults to [`InvalidUtf8Policy::Reject`].
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> DecodeConfig {
+        self.invalid_utf8 = policy;
+        self
+    }
+
+    /// Sets how proto2 `required` fields which are missing from a message are handled.
+    ///
+    /// Defaults to [`RequiredFieldPolicy::Ignore`].
+    pub fn required_fields(mut self, policy: RequiredFieldPolicy) -> DecodeConfig {
+        self.required_fields = policy;
+        self
+    }
+
+    /// Sets a function called with the tag and the encoded length (key included) of each field
+    /// skipped because the message doesn't know its tag.
+    ///
+    /// Unknown fields are a sign that a peer uses a newer schema, so counting them is an easy way
+    /// to detect schema drift. The hook is a plain function, so it records its observations in
+    /// global state, such as an atomic counter or a metrics registry.
+    ///
+    /// Fields nested in a skipped group aren't reported separately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// use prost::{DecodeConfig, DecodeMessage};
+    ///
+    /// static UNKNOWN_BYTES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let config = DecodeConfig::new().on_unknown_field(|_tag, len| {
+    ///     UNKNOWN_BYTES.fetch_add(len, Ordering::Relaxed);
+    /// });
+    ///
+    /// // Field 1 is known to `u32`, field 2 isn't.
+    /// let buf = [0x08, 0x01, 0x10, 0x96, 0x01];
+    /// assert_eq!(u32::decode_with_config(&buf[..], config).unwrap(), 1);
+    /// assert_eq!(UNKNOWN_BYTES.load(Ordering::Relaxed), 3);
+    /// ```
+    pub fn on_unknown_field(mut self, hook: fn(u32, usize)) -> DecodeConfig {
+        self.unknown_field_hook = Some(hook);
+        self
+    }
+
+    /// Sets a function called with the tag of each non-repeated field which appears again in a
+    /// message, including a second member of a `oneof`.
+    ///
+    /// Counting duplicates is a way to find out whether rejecting them with
+    /// [`DuplicateFieldPolicy::Reject`] would break existing peers. The duplicates are still
+    /// decoded as the [`DuplicateFieldPolicy`] says, and the hook isn't called when they are
+    /// rejected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// use prost::{DecodeConfig, DecodeMessage};
+    ///
+    /// static DUPLICATES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let config = DecodeConfig::new().on_duplicate_field(|_tag| {
+    ///     DUPLICATES.fetch_add(1, Ordering::Relaxed);
+    /// });
+    ///
+    /// // Field 1 (a `uint32`) appears three times.
+    /// let buf = [0x08, 0x01, 0x08, 0x02, 0x08, 0x03];
+    /// assert_eq!(u32::decode_with_config(&buf[..], config).unwrap(), 3);
+    /// assert_eq!(DUPLICATES.load(Ordering::Relaxed), 2);
+    /// ```
+    pub fn on_duplicate_field(mut self, hook: fn(u32)) -> DecodeConfig {
+        self.duplicate_field_hook = Some(hook);
+        self
+    }
+
+    /// Checks the size of a message, or a lower bound of it, against the message size limit.
+    pub(crate) fn check_message_size(&self, size: u64) -> Result<(), DecodeError> {
+        if size > self.max_message_size as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::LengthOverflow,
+                format!(
+                    "message of {} bytes exceeds the limit of {} bytes",
+                    size, self.max_message_size
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the size of a length-delimited value against the field size limit.
+    pub(crate) fn check_field_size(&self, size: u64) -> Result<(), DecodeError> {
+        if size > self.max_field_size as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::LengthOverflow,
+                format!(
+                    "length-delimited value of {} bytes exceeds the limit of {} bytes",
+                    size, self.max_field_size
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Hooks are compared by address, so configurations built separately from the same function may
+// still compare unequal.
+impl PartialEq for DecodeConfig {
+    fn eq(&self, other: &DecodeConfig) -> bool {
+        self.duplicate_fields == other.duplicate_fields
+            && self.reserved_wire_types == other.reserved_wire_types
+            && self.recursion_limit == other.recursion_limit
+            && self.max_message_size == other.max_message_size
+            && self.max_field_size == other.max_field_size
+            && self.unknown_fields == other.unknown_fields
+            && self.invalid_utf8 == other.invalid_utf8
+            && self.required_fields == other.required_fields
+            && self.unknown_field_hook.map(|hook| hook as usize)
+                == other.unknown_field_hook.map(|hook| hook as usize)
+            && self.duplicate_field_hook.map(|hook| hook as usize)
+                == other.duplicate_field_hook.map(|hook| hook as usize)
+    }
+}
+
+impl Eq for DecodeConfig {}
+
+impl Default for DecodeConfig {
+    fn default() -> DecodeConfig {
+        DecodeConfig::new()
+    }
+}
+
+/// How non-repeated fields which appear more than once in a message are handled.
+///
+/// The Protobuf specification requires parsers to accept duplicate occurrences of a non-repeated
+/// field: the last value of a scalar field wins, and the values of a message field are merged.
+/// Parsers which don't follow the rule exactly see different values in the same message, which
+/// can be abused to smuggle a value past a validating proxy. Applications which never expect
+/// duplicates can reject them instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateFieldPolicy {
+    /// Follow the Protobuf specification: the last value wins, or is merged into the previous
+    /// ones.
+    LastWins,
+    /// Fail to decode messages in which a non-repeated field appears more than once, or in which
+    /// more than one member of a `oneof` appears.
+    ///
+    /// The error is of kind
+    /// [`DecodeErrorKind::DuplicateField`](crate::DecodeErrorKind::DuplicateField).
+    /// Only the fields known to the message are checked; duplicate unknown fields are skipped as
+    /// usual.
+    Reject,
+}
+
+/// How field keys with one of the reserved wire types 6 and 7 are handled.
+///
+/// Reserved wire types never appear in valid Protobuf messages, and since the length of their
+/// value is unknown, the bytes following such a key can't be decoded reliably.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReservedWireTypePolicy {
+    /// Fail to decode the message with an error of kind
+    /// [`DecodeErrorKind::ReservedWireType`](crate::DecodeErrorKind::ReservedWireType).
+    Reject,
+    /// Drop the key and decode the following bytes as the next key, until a key with a valid wire
+    /// type is found.
+    ///
+    /// This is meant for tooling recovering what it can from corrupted input: the decoded message
+    /// may contain garbage fields, and decoding may still fail further on.
+    Skip,
+}
+
+/// How fields which the decoded message doesn't know are handled.
+///
+/// Unknown fields are normally skipped, so that messages written with a newer schema can be
+/// read. Applications which share their schema with all of their peers can reject them instead,
+/// to catch mismatched schemas and misdirected messages early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownFieldPolicy {
+    /// Skip unknown fields.
+    Skip,
+    /// Fail to decode messages, including nested messages, which contain an unknown field.
+    ///
+    /// An [`UnknownFieldSet`](crate::UnknownFieldSet) knows all fields, so it still decodes any
+    /// message.
+    Reject,
+}
+
+/// How `string` values which aren't valid UTF-8 are handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidUtf8Policy {
+    /// Fail to decode the message, as the Protobuf specification requires.
+    Reject,
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`, as
+    /// [`String::from_utf8_lossy`](alloc::string::String::from_utf8_lossy) does.
+    ///
+    /// This is meant for reading data written by implementations which don't validate strings,
+    /// such as proto2 implementations of some languages. Re-encoding the message doesn't restore
+    /// the original bytes.
+    Replace,
+}
+
+/// How proto2 `required` fields which are missing from a message are handled.
+///
+/// A required field is decoded into a plain value, rather than an `Option`, so a message can't
+/// tell whether a required field was present in its encoding once it is decoded. Other Protobuf
+/// implementations fail to decode such messages; applications exchanging proto2 messages with
+/// them can do the same.
+///
+/// For the same reason, messages have no `CheckInitialized`-style method telling whether their
+/// required fields are set, as messages of other implementations do: a message value always
+/// holds its required fields, so their presence can only be checked while decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequiredFieldPolicy {
+    /// Leave missing required fields set to their default values.
+    Ignore,
+    /// Fail to decode messages, including nested messages, which lack one of their required
+    /// fields, with an error of kind
+    /// [`DecodeErrorKind::MissingRequiredFields`](crate::DecodeErrorKind::MissingRequiredFields)
+    /// listing the missing fields.
+    ///
+    /// The fields of the decoded bytes are checked, so merging into a message which already
+    /// holds a required field still requires the field to be present in the merged bytes.
+    Reject,
+}
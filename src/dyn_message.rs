@@ -0,0 +1,112 @@
+//! Object-safe access to messages.
+
+use alloc::vec::Vec;
+use core::any::Any;
+
+use ::bytes::{Buf, BufMut};
+
+use crate::{DecodeError, EncodeError, Message};
+
+/// The object-safe part of [`Message`], for messages held as trait objects.
+///
+/// The encoding and decoding methods of `Message` are generic over the buffer type, so they
+/// can't be called on a `dyn Message`. `DynMessage` provides equivalents taking buffer trait
+/// objects, and is implemented for all messages, so heterogeneous collections of messages and
+/// plugin systems can hold a `Box<dyn DynMessage>`. The methods of `EncodeMessage` and
+/// `DecodeMessage` which are object safe, such as `encoded_len` and `clear`, can be called on it
+/// as well.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::DynMessage;
+///
+/// let mut messages: Vec<Box<dyn DynMessage>> = vec![Box::new(7u32), Box::new(String::new())];
+/// messages[1].merge_from_slice(&[0x0a, 0x02, b'h', b'i']).unwrap();
+///
+/// let lens = messages.iter().map(|message| message.encoded_len()).collect::<Vec<_>>();
+/// assert_eq!(lens, [2, 4]);
+/// assert_eq!(messages[1].encode_dyn_to_vec(), [0x0a, 0x02, b'h', b'i']);
+/// assert_eq!(messages[0].as_any().downcast_ref::<u32>(), Some(&7));
+/// ```
+pub trait DynMessage: Message {
+    /// Encodes the message to a buffer, as
+    /// [`EncodeMessage::encode`](crate::EncodeMessage::encode) does.
+    fn encode_dyn(&self, buf: &mut dyn BufMut) -> Result<(), EncodeError>;
+
+    /// Encodes the message to a newly allocated buffer.
+    fn encode_dyn_to_vec(&self) -> Vec<u8>;
+
+    /// Decodes an instance of the message from a buffer, and merges it into `self`, as
+    /// [`DecodeMessage::merge`](crate::DecodeMessage::merge) does.
+    fn merge_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError>;
+
+    /// Decodes an instance of the message from a byte slice, and merges it into `self`.
+    fn merge_from_slice(&mut self, buf: &[u8]) -> Result<(), DecodeError>;
+
+    /// Returns the message as `Any`, to downcast it to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the message as mutable `Any`, to downcast it to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<M> DynMessage for M
+where
+    M: Message + 'static,
+{
+    fn encode_dyn(&self, mut buf: &mut dyn BufMut) -> Result<(), EncodeError> {
+        self.encode(&mut buf)
+    }
+
+    fn encode_dyn_to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_raw(&mut buf);
+        buf
+    }
+
+    fn merge_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError> {
+        self.merge(buf)
+    }
+
+    fn merge_from_slice(&mut self, buf: &[u8]) -> Result<(), DecodeError> {
+        self.merge(buf)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn trait_objects() {
+        let mut messages: Vec<Box<dyn DynMessage>> = vec![Box::new(5u64), Box::new(String::new())];
+
+        let mut buf = Vec::new();
+        for message in &messages {
+            message.encode_dyn(&mut buf).unwrap();
+        }
+        assert_eq!(buf, [0x08, 0x05]);
+
+        let mut input = &[0x0a, 0x01, b'a'][..];
+        messages[1].merge_dyn(&mut input).unwrap();
+        assert_eq!(messages[1].encode_dyn_to_vec(), [0x0a, 0x01, b'a']);
+
+        messages[1].clear();
+        assert_eq!(messages[1].encoded_len(), 0);
+        *messages[0].as_any_mut().downcast_mut::<u64>().unwrap() = 6;
+        assert_eq!(messages[0].encode_dyn_to_vec(), [0x08, 0x06]);
+    }
+}
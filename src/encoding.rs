@@ -4,12 +4,13 @@
 
 #![allow(clippy::implicit_hasher, clippy::ptr_arg)]
 
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::cmp::min;
 use core::convert::TryFrom;
+use core::fmt;
 use core::mem;
 use core::str;
 use core::u32;
@@ -18,7 +19,11 @@ use core::usize;
 use ::bytes::{Buf, BufMut, Bytes};
 
 use crate::DecodeError;
-use crate::Message;
+use crate::{
+    DecodeConfig, DecodeErrorKind, DuplicateFieldPolicy, InvalidUtf8Policy, RequiredFieldPolicy,
+    ReservedWireTypePolicy, UnknownFieldPolicy,
+};
+use crate::{DecodeMessage, EncodeMessage};
 
 /// Encodes an integer value into LEB128 variable length format, and writes it to the buffer.
 /// The buffer must have enough remaining space (maximum 10 bytes).
@@ -47,7 +52,10 @@ where
     let bytes = buf.chunk();
     let len = bytes.len();
     if len == 0 {
-        return Err(DecodeError::new("invalid varint"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::Truncated,
+            "invalid varint",
+        ));
     }
 
     let byte = bytes[0];
@@ -156,37 +164,86 @@ fn decode_varint_slice(bytes: &[u8]) -> Result<(u64, usize), DecodeError> {
 
     // We have overrun the maximum size of a varint (10 bytes) or the final byte caused an overflow.
     // Assume the data is corrupt.
-    Err(DecodeError::new("invalid varint"))
+    Err(DecodeError::with_kind(
+        DecodeErrorKind::InvalidVarint,
+        "invalid varint",
+    ))
 }
 
 /// Decodes a LEB128-encoded variable length integer from the buffer, advancing the buffer as
 /// necessary.
 ///
+/// This is the path of varints which may span several chunks of a non-contiguous buffer, such as
+/// a `Chain` or a rope of network buffers: the bytes are read from each chunk in turn, rather
+/// than with a `get_u8` call per byte.
+///
 /// Contains a varint overflow check from [`ConsumeVarint`][1].
 ///
 /// [1]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
 #[inline(never)]
-#[cold]
 fn decode_varint_slow<B>(buf: &mut B) -> Result<u64, DecodeError>
 where
     B: Buf,
 {
+    /// Returns the value of a varint ending with `byte`, its `count`th byte.
+    fn last_byte(value: u64, count: usize, byte: u8) -> Result<u64, DecodeError> {
+        // Check for u64::MAX overflow. See [`ConsumeVarint`][1] for details.
+        // [1]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
+        if count == 10 && byte >= 0x02 {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::InvalidVarint,
+                "invalid varint",
+            ))
+        } else {
+            Ok(value)
+        }
+    }
+
     let mut value = 0;
-    for count in 0..min(10, buf.remaining()) {
-        let byte = buf.get_u8();
-        value |= u64::from(byte & 0x7F) << (count * 7);
-        if byte <= 0x7F {
-            // Check for u64::MAX overflow. See [`ConsumeVarint`][1] for details.
-            // [1]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
-            if count == 9 && byte >= 0x02 {
-                return Err(DecodeError::new("invalid varint"));
-            } else {
-                return Ok(value);
+    let max_len = min(10, buf.remaining());
+    let mut count = 0;
+    while count < max_len {
+        if buf.chunk().is_empty() {
+            // A `Buf` may return an empty chunk before its end, in which case reading it a byte
+            // at a time still makes progress.
+            let byte = buf.get_u8();
+            value |= u64::from(byte & 0x7F) << (count * 7);
+            count += 1;
+            if byte <= 0x7F {
+                return last_byte(value, count, byte);
+            }
+            continue;
+        }
+
+        let chunk = buf.chunk();
+        let chunk = &chunk[..min(chunk.len(), max_len - count)];
+        let mut last = None;
+        for (read, &byte) in chunk.iter().enumerate() {
+            value |= u64::from(byte & 0x7F) << (count * 7);
+            count += 1;
+            if byte <= 0x7F {
+                last = Some((read + 1, byte));
+                break;
+            }
+        }
+        match last {
+            Some((read, byte)) => {
+                buf.advance(read);
+                return last_byte(value, count, byte);
+            }
+            None => {
+                let read = chunk.len();
+                buf.advance(read);
             }
         }
     }
 
-    Err(DecodeError::new("invalid varint"))
+    let kind = if max_len < 10 {
+        DecodeErrorKind::Truncated
+    } else {
+        DecodeErrorKind::InvalidVarint
+    };
+    Err(DecodeError::with_kind(kind, "invalid varint"))
 }
 
 /// Additional information passed to every decode/merge function.
@@ -198,30 +255,45 @@ pub struct DecodeContext {
     /// How many times we can recurse in the current decode stack before we hit
     /// the recursion limit.
     ///
-    /// The recursion limit is defined by `RECURSION_LIMIT` and cannot be
-    /// customized. The recursion limit can be ignored by building the Prost
-    /// crate with the `no-recursion-limit` feature.
+    /// The recursion limit is set by `DecodeConfig::recursion_limit`. The
+    /// recursion limit can be ignored by building the Prost crate with the
+    /// `no-recursion-limit` feature.
     #[cfg(not(feature = "no-recursion-limit"))]
     recurse_count: u32,
+
+    /// The options of the decode.
+    config: DecodeConfig,
 }
 
 impl Default for DecodeContext {
-    #[cfg(not(feature = "no-recursion-limit"))]
     #[inline]
     fn default() -> DecodeContext {
+        DecodeContext::with_config(DecodeConfig::default())
+    }
+}
+
+impl DecodeContext {
+    /// Creates the context of a decode with the given options.
+    #[cfg(not(feature = "no-recursion-limit"))]
+    #[inline]
+    pub(crate) fn with_config(config: DecodeConfig) -> DecodeContext {
         DecodeContext {
-            recurse_count: crate::RECURSION_LIMIT,
+            recurse_count: config.recursion_limit,
+            config,
         }
     }
 
     #[cfg(feature = "no-recursion-limit")]
     #[inline]
-    fn default() -> DecodeContext {
-        DecodeContext {}
+    pub(crate) fn with_config(config: DecodeConfig) -> DecodeContext {
+        DecodeContext { config }
+    }
+
+    /// Returns the options of the decode.
+    pub(crate) fn config(&self) -> &DecodeConfig {
+        &self.config
     }
-}
 
-impl DecodeContext {
     /// Call this function before recursively decoding.
     ///
     /// There is no `exit` function since this function creates a new `DecodeContext`
@@ -232,13 +304,16 @@ impl DecodeContext {
     pub(crate) fn enter_recursion(&self) -> DecodeContext {
         DecodeContext {
             recurse_count: self.recurse_count - 1,
+            config: self.config,
         }
     }
 
     #[cfg(feature = "no-recursion-limit")]
     #[inline]
     pub(crate) fn enter_recursion(&self) -> DecodeContext {
-        DecodeContext {}
+        DecodeContext {
+            config: self.config,
+        }
     }
 
     /// Checks whether the recursion limit has been reached in the stack of
@@ -250,7 +325,10 @@ impl DecodeContext {
     #[inline]
     pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
         if self.recurse_count == 0 {
-            Err(DecodeError::new("recursion limit reached"))
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::RecursionLimit,
+                "recursion limit reached",
+            ))
         } else {
             Ok(())
         }
@@ -262,10 +340,160 @@ impl DecodeContext {
     pub(crate) fn limit_reached(&self) -> Result<(), DecodeError> {
         Ok(())
     }
+
+    /// Decodes the length of a length-delimited value, and checks that the value fits in the
+    /// buffer and in the field size limit of the decode.
+    #[inline]
+    pub(crate) fn decode_len<B>(&self, buf: &mut B) -> Result<usize, DecodeError>
+    where
+        B: Buf,
+    {
+        let len = decode_varint(buf)?;
+        if len > buf.remaining() as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::Truncated,
+                "buffer underflow",
+            ));
+        }
+        self.config.check_field_size(len)?;
+        Ok(len as usize)
+    }
+
+    /// Decodes a field key, following the reserved wire type policy of the decode.
+    ///
+    /// Returns `None` if a key with a reserved wire type was skipped, in which case the caller
+    /// should decode the next key.
+    #[inline]
+    pub fn decode_key<B>(&self, buf: &mut B) -> Result<Option<(u32, WireType)>, DecodeError>
+    where
+        B: Buf,
+    {
+        match decode_key(buf) {
+            Ok(key) => Ok(Some(key)),
+            Err(error) => match (error.kind(), self.config.reserved_wire_types) {
+                (DecodeErrorKind::ReservedWireType { .. }, ReservedWireTypePolicy::Skip) => {
+                    Ok(None)
+                }
+                _ => Err(error),
+            },
+        }
+    }
+}
+
+/// The tags of the non-repeated fields merged into a message so far, or rather their
+/// identifiers, which the members of a `oneof` share.
+///
+/// The tags are only tracked when the decode checks for duplicate or missing fields.
+#[derive(Debug)]
+pub(crate) struct SeenFields {
+    tags: Option<BTreeSet<u32>>,
+    reject_duplicates: bool,
+    duplicate_hook: Option<fn(u32)>,
+    check_required: bool,
+}
+
+impl SeenFields {
+    #[inline]
+    pub(crate) fn new(ctx: &DecodeContext) -> SeenFields {
+        let reject_duplicates = ctx.config.duplicate_fields == DuplicateFieldPolicy::Reject;
+        let duplicate_hook = ctx.config.duplicate_field_hook;
+        let check_required = ctx.config.required_fields == RequiredFieldPolicy::Reject;
+        let tags = if reject_duplicates || duplicate_hook.is_some() || check_required {
+            Some(BTreeSet::new())
+        } else {
+            None
+        };
+        SeenFields {
+            tags,
+            reject_duplicates,
+            duplicate_hook,
+            check_required,
+        }
+    }
+
+    /// Records a field of message `M`, failing if it is non-repeated, was already seen, and
+    /// duplicates are rejected.
+    #[inline]
+    pub(crate) fn insert<M>(&mut self, tag: u32) -> Result<(), DecodeError>
+    where
+        M: DecodeMessage,
+    {
+        if self.tags.is_none() || !M::is_singular_field(tag) {
+            return Ok(());
+        }
+        self.insert_tag(tag, Some(M::singular_field_id(tag)))
+    }
+
+    /// Records a field, with the identifier `id` if it is non-repeated, failing if a field with
+    /// the same identifier was already seen and duplicates are rejected.
+    #[inline]
+    pub(crate) fn insert_tag(&mut self, tag: u32, id: Option<u32>) -> Result<(), DecodeError> {
+        let (tags, id) = match (&mut self.tags, id) {
+            (Some(tags), Some(id)) => (tags, id),
+            _ => return Ok(()),
+        };
+        if tags.insert(id) {
+            return Ok(());
+        }
+        if self.reject_duplicates {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::DuplicateField { tag },
+                if id == tag {
+                    format!("duplicate non-repeated field: tag {}", tag)
+                } else {
+                    format!("duplicate member of a oneof: tag {}", tag)
+                },
+            ));
+        }
+        if let Some(hook) = self.duplicate_hook {
+            hook(tag);
+        }
+        Ok(())
+    }
+
+    /// Checks that all required fields of message `M` were seen, if missing required fields are
+    /// rejected.
+    #[inline]
+    pub(crate) fn check_required<M>(&self) -> Result<(), DecodeError>
+    where
+        M: DecodeMessage,
+    {
+        self.check_required_fields(M::for_each_required_field)
+    }
+
+    /// Checks that all of the `required` fields listed by `for_each_required_field` were seen, if
+    /// missing required fields are rejected.
+    #[inline]
+    pub(crate) fn check_required_fields(
+        &self,
+        for_each_required_field: fn(&mut dyn FnMut(u32, &'static str)),
+    ) -> Result<(), DecodeError> {
+        let tags = match self.tags {
+            Some(ref tags) if self.check_required => tags,
+            _ => return Ok(()),
+        };
+        let mut missing = Vec::new();
+        for_each_required_field(&mut |tag, name| {
+            if !tags.contains(&tag) {
+                missing.push(name);
+            }
+        });
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::MissingRequiredFields,
+                format!("missing required fields: {}", missing.join(", ")),
+            ))
+        }
+    }
 }
 
 /// Returns the encoded length of the value in LEB128 variable length format.
 /// The returned value will be between 1 and 10, inclusive.
+///
+/// This is the size of varint fields, keys and length delimiters, for computing the size of
+/// encoded data without encoding it.
 #[inline]
 pub fn encoded_len_varint(value: u64) -> usize {
     // Based on [VarintSize64][1].
@@ -273,17 +501,51 @@ pub fn encoded_len_varint(value: u64) -> usize {
     ((((value | 1).leading_zeros() ^ 63) * 9 + 73) / 64) as usize
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// The wire type of an encoded Protobuf field, stored in the low three bits of its key.
+///
+/// See the [encoding guide][1] for the Protobuf types using each wire type. Wire types 6 and 7
+/// are reserved, and fail to convert from their numeric value.
+///
+/// [1]: https://developers.google.com/protocol-buffers/docs/encoding#structure
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum WireType {
+    /// A variable-length integer: `int32`, `int64`, `uint32`, `uint64`, `sint32`, `sint64`,
+    /// `bool` and `enum`.
     Varint = 0,
+    /// A fixed 64-bit value: `fixed64`, `sfixed64` and `double`.
     SixtyFourBit = 1,
+    /// A length-prefixed value: `string`, `bytes`, messages and packed repeated fields.
     LengthDelimited = 2,
+    /// The start of a group (deprecated).
     StartGroup = 3,
+    /// The end of a group (deprecated).
     EndGroup = 4,
+    /// A fixed 32-bit value: `fixed32`, `sfixed32` and `float`.
     ThirtyTwoBit = 5,
 }
 
+impl WireType {
+    /// Returns `true` if values of the wire type are prefixed by their length.
+    #[inline]
+    pub fn is_length_delimited(self) -> bool {
+        self == WireType::LengthDelimited
+    }
+}
+
+impl fmt::Display for WireType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WireType::Varint => "varint",
+            WireType::SixtyFourBit => "64-bit",
+            WireType::LengthDelimited => "length-delimited",
+            WireType::StartGroup => "start group",
+            WireType::EndGroup => "end group",
+            WireType::ThirtyTwoBit => "32-bit",
+        })
+    }
+}
+
 pub const MIN_TAG: u32 = 1;
 pub const MAX_TAG: u32 = (1 << 29) - 1;
 
@@ -330,8 +592,19 @@ where
     if key > u64::from(u32::MAX) {
         return Err(DecodeError::new(format!("invalid key value: {}", key)));
     }
-    let wire_type = WireType::try_from(key & 0x07)?;
     let tag = key as u32 >> 3;
+    let wire_type = match WireType::try_from(key & 0x07) {
+        Ok(wire_type) => wire_type,
+        Err(_) => {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::ReservedWireType {
+                    tag,
+                    wire_type: (key & 0x07) as u8,
+                },
+                format!("reserved wire type {} (tag {})", key & 0x07, tag),
+            ))
+        }
+    };
 
     if tag < MIN_TAG {
         return Err(DecodeError::new("invalid tag value: 0"));
@@ -352,10 +625,10 @@ pub fn key_len(tag: u32) -> usize {
 #[inline]
 pub fn check_wire_type(expected: WireType, actual: WireType) -> Result<(), DecodeError> {
     if expected != actual {
-        return Err(DecodeError::new(format!(
-            "invalid wire type: {:?} (expected {:?})",
-            actual, expected
-        )));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidWireType { expected, actual },
+            format!("invalid wire type: {:?} (expected {:?})", actual, expected),
+        ));
     }
     Ok(())
 }
@@ -372,29 +645,77 @@ where
     M: FnMut(&mut T, &mut B, DecodeContext) -> Result<(), DecodeError>,
     B: Buf,
 {
-    let len = decode_varint(buf)?;
-    let remaining = buf.remaining();
-    if len > remaining as u64 {
-        return Err(DecodeError::new("buffer underflow"));
-    }
-
-    let limit = remaining - len as usize;
+    let len = ctx.decode_len(buf)?;
+    let limit = buf.remaining() - len;
     while buf.remaining() > limit {
         merge(value, buf, ctx.clone())?;
     }
 
+    check_limit(buf, limit)
+}
+
+/// Checks that decoding a length-delimited value stopped at its end, when `limit` bytes of the
+/// buffer remain, rather than overrunning it.
+#[inline]
+pub fn check_limit<B>(buf: &B, limit: usize) -> Result<(), DecodeError>
+where
+    B: Buf,
+{
     if buf.remaining() != limit {
-        return Err(DecodeError::new("delimited length exceeded"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
+            "delimited length exceeded",
+        ));
     }
     Ok(())
 }
 
+/// Returns `true` if the sorted tags `a` and `b` are the same, for the derived check that a
+/// flattened message has the tags its field declares.
+pub const fn same_tags(a: &[u32], b: &[u32]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 pub fn skip_field<B>(
     wire_type: WireType,
     tag: u32,
     buf: &mut B,
     ctx: DecodeContext,
 ) -> Result<(), DecodeError>
+where
+    B: Buf,
+{
+    #[cfg(feature = "tracing")]
+    tracing::trace!(tag, ?wire_type, "skipping unknown protobuf field");
+    if ctx.config.unknown_fields == UnknownFieldPolicy::Reject {
+        return Err(DecodeError::new(format!("unknown field: {}", tag)));
+    }
+    let hook = ctx.config.unknown_field_hook;
+    let remaining = buf.remaining();
+    skip_value(wire_type, tag, buf, ctx)?;
+    if let Some(hook) = hook {
+        hook(tag, key_len(tag) + remaining - buf.remaining());
+    }
+    Ok(())
+}
+
+/// Skips the value of a field whose key has already been decoded.
+fn skip_value<B>(
+    wire_type: WireType,
+    tag: u32,
+    buf: &mut B,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError>
 where
     B: Buf,
 {
@@ -403,24 +724,27 @@ where
         WireType::Varint => decode_varint(buf).map(|_| 0)?,
         WireType::ThirtyTwoBit => 4,
         WireType::SixtyFourBit => 8,
-        WireType::LengthDelimited => decode_varint(buf)?,
-        WireType::StartGroup => loop {
-            let (inner_tag, inner_wire_type) = decode_key(buf)?;
-            match inner_wire_type {
-                WireType::EndGroup => {
-                    if inner_tag != tag {
-                        return Err(DecodeError::new("unexpected end group tag"));
-                    }
-                    break 0;
-                }
-                _ => skip_field(inner_wire_type, inner_tag, buf, ctx.enter_recursion())?,
-            }
-        },
+        WireType::LengthDelimited => ctx.decode_len(buf)? as u64,
+        WireType::StartGroup => {
+            group::merge_fields(
+                tag,
+                wire_type,
+                buf,
+                ctx,
+                |inner_tag, inner_wire_type, buf, ctx| {
+                    skip_value(inner_wire_type, inner_tag, buf, ctx)
+                },
+            )?;
+            0
+        }
         WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
     };
 
     if len > buf.remaining() as u64 {
-        return Err(DecodeError::new("buffer underflow"));
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::Truncated,
+            "buffer underflow",
+        ));
     }
 
     buf.advance(len as usize);
@@ -637,7 +961,10 @@ macro_rules! fixed_width {
             {
                 check_wire_type($wire_type, wire_type)?;
                 if buf.remaining() < $width {
-                    return Err(DecodeError::new("buffer underflow"));
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::Truncated,
+                        "buffer underflow",
+                    ));
                 }
                 *value = buf.$get();
                 Ok(())
@@ -842,6 +1169,7 @@ pub mod string {
                 }
             }
 
+            let invalid_utf8 = ctx.config.invalid_utf8;
             let drop_guard = DropGuard(value.as_mut_vec());
             bytes::merge(wire_type, drop_guard.0, buf, ctx)?;
             match str::from_utf8(drop_guard.0) {
@@ -850,7 +1178,15 @@ pub mod string {
                     mem::forget(drop_guard);
                     Ok(())
                 }
-                Err(_) => Err(DecodeError::new(
+                Err(_) if invalid_utf8 == InvalidUtf8Policy::Replace => {
+                    // The replaced bytes are valid UTF-8, so they may be kept.
+                    let replaced = String::from_utf8_lossy(drop_guard.0).into_owned();
+                    *drop_guard.0 = replaced.into_bytes();
+                    mem::forget(drop_guard);
+                    Ok(())
+                }
+                Err(_) => Err(DecodeError::with_kind(
+                    DecodeErrorKind::InvalidUtf8,
                     "invalid string value: data is not UTF-8 encoded",
                 )),
             }
@@ -882,33 +1218,45 @@ pub mod string {
     }
 }
 
-pub trait BytesAdapter: sealed::BytesAdapter {}
-
-mod sealed {
-    use super::{Buf, BufMut};
-
-    pub trait BytesAdapter: Default + Sized + 'static {
-        fn len(&self) -> usize;
-
-        /// Replace contents of this buffer with the contents of another buffer.
-        fn replace_with<B>(&mut self, buf: B)
-        where
-            B: Buf;
+/// A type which can back a Protobuf `bytes` field.
+///
+/// `prost` implements `BytesAdapter` for [`Vec<u8>`] and [`Bytes`]. Other byte containers (for
+/// example `SmallVec<[u8; N]>`, `Arc<[u8]>`, or a memory-mapped region) can be used as the type of
+/// a `bytes` field by implementing this trait, and annotating the field with the path of the type
+/// in a `custom(...)` form:
+///
+/// ```rust,ignore
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Blob {
+///     #[prost(bytes = "custom(SharedBytes)", tag = "1")]
+///     data: SharedBytes,
+/// }
+/// ```
+///
+/// `prost-build` generates such fields with `Config::custom_bytes`. Besides `BytesAdapter`, the
+/// type must implement `AsRef<[u8]>`, along with the traits derived on the message (typically
+/// `Clone`, `PartialEq` and `Debug`).
+pub trait BytesAdapter: Default + Sized + 'static {
+    /// Returns the length of the buffer in bytes.
+    fn len(&self) -> usize;
+
+    /// Replace contents of this buffer with the contents of another buffer.
+    fn replace_with<B>(&mut self, buf: B)
+    where
+        B: Buf;
 
-        /// Appends this buffer to the (contents of) other buffer.
-        fn append_to<B>(&self, buf: &mut B)
-        where
-            B: BufMut;
+    /// Appends this buffer to the (contents of) other buffer.
+    fn append_to<B>(&self, buf: &mut B)
+    where
+        B: BufMut;
 
-        fn is_empty(&self) -> bool {
-            self.len() == 0
-        }
+    /// Returns `true` if the buffer is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
-impl BytesAdapter for Bytes {}
-
-impl sealed::BytesAdapter for Bytes {
+impl BytesAdapter for Bytes {
     fn len(&self) -> usize {
         Buf::remaining(self)
     }
@@ -928,9 +1276,7 @@ impl sealed::BytesAdapter for Bytes {
     }
 }
 
-impl BytesAdapter for Vec<u8> {}
-
-impl sealed::BytesAdapter for Vec<u8> {
+impl BytesAdapter for Vec<u8> {
     fn len(&self) -> usize {
         Vec::len(self)
     }
@@ -969,18 +1315,14 @@ pub mod bytes {
         wire_type: WireType,
         value: &mut A,
         buf: &mut B,
-        _ctx: DecodeContext,
+        ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
         A: BytesAdapter,
         B: Buf,
     {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
-        let len = decode_varint(buf)?;
-        if len > buf.remaining() as u64 {
-            return Err(DecodeError::new("buffer underflow"));
-        }
-        let len = len as usize;
+        let len = ctx.decode_len(buf)?;
 
         // Clear the existing value. This follows from the following rule in the encoding guide[1]:
         //
@@ -990,7 +1332,14 @@ pub mod bytes {
         // > last value it sees.
         //
         // [1]: https://developers.google.com/protocol-buffers/docs/encoding#optional
-        value.replace_with(buf.copy_to_bytes(len));
+        //
+        // The value reads the field through a `Take` rather than a copy of it, so that a `Vec<u8>`
+        // is filled from the chunks of the buffer directly, and a `Bytes` still shares the
+        // buffer if it is a `Bytes` itself.
+        let mut field = buf.take(len);
+        value.replace_with(&mut field);
+        let unread = field.remaining();
+        field.advance(unread);
         Ok(())
     }
 
@@ -1035,135 +1384,442 @@ pub mod bytes {
     }
 }
 
-pub mod message {
+/// A transformation applied to the value of a Protobuf `bytes` field on the wire.
+///
+/// A codec can encrypt, compress or otherwise transform a field transparently: the field holds
+/// the plain value, [`BytesCodec::encode`] is applied when the message is encoded, and
+/// [`BytesCodec::decode`] when it is decoded. The field keeps the `bytes` wire type.
+///
+/// A codec is attached to a field with the `codec` attribute, which `prost-build` generates with
+/// `Config::bytes_codec`:
+///
+/// ```rust,ignore
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Record {
+///     #[prost(bytes = "vec", codec = "crate::Encrypt", tag = "1")]
+///     secret: Vec<u8>,
+/// }
+/// ```
+///
+/// Codecs have no state, so keys or other configuration must be reachable from the codec type,
+/// for example through a static.
+///
+/// The length of a message is computed before it is encoded, to write the length-delimiters of
+/// nested messages, so the length of the wire representation must only depend on the plain
+/// value: `encode` must return exactly [`BytesCodec::encoded_len`] bytes, every time it is called
+/// with the same value. The bytes themselves may vary, as with a random nonce, but then the
+/// encoding of the message varies as well, including its deterministic encoding and its
+/// fingerprint.
+pub trait BytesCodec {
+    /// Transforms a plain field value into its wire representation.
+    fn encode(value: &[u8]) -> Vec<u8>;
+
+    /// Returns the length of the wire representation of a plain field value, which must be the
+    /// length of the output of `encode`.
+    ///
+    /// The default implementation encodes the value, so a message with a field using the codec
+    /// transforms the field twice when it is encoded; codecs which can compute the length without
+    /// transforming the value should override it.
+    fn encoded_len(value: &[u8]) -> usize {
+        Self::encode(value).len()
+    }
+
+    /// Transforms the wire representation of a field value back into the plain value.
+    fn decode(value: &[u8]) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Encoding of `bytes` fields transformed by a [`BytesCodec`].
+pub mod codec {
     use super::*;
 
-    pub fn encode<M, B>(tag: u32, msg: &M, buf: &mut B)
+    pub fn encode<C, A, B>(tag: u32, value: &A, buf: &mut B)
     where
-        M: Message,
+        C: BytesCodec,
+        A: BytesAdapter + AsRef<[u8]>,
         B: BufMut,
     {
-        encode_key(tag, WireType::LengthDelimited, buf);
-        encode_varint(msg.encoded_len() as u64, buf);
-        msg.encode_raw(buf);
+        let encoded = C::encode(value.as_ref());
+        debug_assert_eq!(
+            encoded.len(),
+            C::encoded_len(value.as_ref()),
+            "BytesCodec::encoded_len doesn't match the length of BytesCodec::encode"
+        );
+        bytes::encode(tag, &encoded, buf);
     }
 
-    pub fn merge<M, B>(
+    pub fn merge<C, A, B>(
         wire_type: WireType,
-        msg: &mut M,
+        value: &mut A,
         buf: &mut B,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
-        M: Message,
+        C: BytesCodec,
+        A: BytesAdapter,
         B: Buf,
     {
-        check_wire_type(WireType::LengthDelimited, wire_type)?;
-        ctx.limit_reached()?;
-        merge_loop(
-            msg,
-            buf,
-            ctx.enter_recursion(),
-            |msg: &mut M, buf: &mut B, ctx| {
-                let (tag, wire_type) = decode_key(buf)?;
-                msg.merge_field(tag, wire_type, buf, ctx)
-            },
-        )
+        let mut encoded = Vec::new();
+        bytes::merge(wire_type, &mut encoded, buf, ctx)?;
+        value.replace_with(&C::decode(&encoded)?[..]);
+        Ok(())
     }
 
-    pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
+    pub fn encode_repeated<C, A, B>(tag: u32, values: &[A], buf: &mut B)
     where
-        M: Message,
+        C: BytesCodec,
+        A: BytesAdapter + AsRef<[u8]>,
         B: BufMut,
     {
-        for msg in messages {
-            encode(tag, msg, buf);
+        for value in values {
+            encode::<C, A, B>(tag, value, buf);
         }
     }
 
-    pub fn merge_repeated<M, B>(
+    pub fn merge_repeated<C, A, B>(
         wire_type: WireType,
-        messages: &mut Vec<M>,
+        values: &mut Vec<A>,
         buf: &mut B,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
-        M: Message + Default,
+        C: BytesCodec,
+        A: BytesAdapter,
         B: Buf,
     {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
-        let mut msg = M::default();
-        merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
-        messages.push(msg);
+        let mut value = A::default();
+        merge::<C, A, B>(wire_type, &mut value, buf, ctx)?;
+        values.push(value);
         Ok(())
     }
 
     #[inline]
-    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    pub fn encoded_len<C, A>(tag: u32, value: &A) -> usize
     where
-        M: Message,
+        C: BytesCodec,
+        A: AsRef<[u8]>,
     {
-        let len = msg.encoded_len();
+        let len = C::encoded_len(value.as_ref());
         key_len(tag) + encoded_len_varint(len as u64) + len
     }
 
     #[inline]
-    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
+    pub fn encoded_len_repeated<C, A>(tag: u32, values: &[A]) -> usize
     where
-        M: Message,
+        C: BytesCodec,
+        A: AsRef<[u8]>,
     {
-        key_len(tag) * messages.len()
-            + messages
-                .iter()
-                .map(Message::encoded_len)
-                .map(|len| len + encoded_len_varint(len as u64))
-                .sum::<usize>()
+        values
+            .iter()
+            .map(|value| encoded_len::<C, A>(tag, value))
+            .sum()
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        /// Reverses the bytes of the value.
+        struct Reverse;
+
+        impl BytesCodec for Reverse {
+            fn encode(value: &[u8]) -> Vec<u8> {
+                value.iter().rev().copied().collect()
+            }
+
+            fn encoded_len(value: &[u8]) -> usize {
+                value.len()
+            }
+
+            fn decode(value: &[u8]) -> Result<Vec<u8>, DecodeError> {
+                Ok(Self::encode(value))
+            }
+        }
+
+        #[test]
+        fn roundtrip() {
+            let value = b"plain".to_vec();
+            let mut buf = Vec::new();
+            encode::<Reverse, _, _>(1, &value, &mut buf);
+            assert_eq!(buf.len(), encoded_len::<Reverse, _>(1, &value));
+            assert_eq!(&buf[2..], b"nialp");
+
+            let mut decoded = Bytes::new();
+            merge::<Reverse, _, _>(
+                WireType::LengthDelimited,
+                &mut decoded,
+                &mut &buf[1..],
+                DecodeContext::default(),
+            )
+            .unwrap();
+            assert_eq!(&decoded[..], b"plain");
+        }
     }
 }
 
-pub mod group {
+pub mod message {
     use super::*;
 
     pub fn encode<M, B>(tag: u32, msg: &M, buf: &mut B)
     where
-        M: Message,
+        M: EncodeMessage,
         B: BufMut,
     {
-        encode_key(tag, WireType::StartGroup, buf);
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(msg.encoded_len() as u64, buf);
         msg.encode_raw(buf);
-        encode_key(tag, WireType::EndGroup, buf);
+    }
+
+    /// Encodes a message field of a message which caches its encoded length, with the length of
+    /// `msg` cached while sizing the enclosing message.
+    pub fn encode_cached<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: EncodeMessage,
+        B: BufMut,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(msg.cached_encoded_len() as u64, buf);
+        msg.encode_raw(buf);
+    }
+
+    /// Encodes a message field deterministically, with
+    /// [`EncodeMessage::encode_raw_deterministic`].
+    pub fn encode_deterministic<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: EncodeMessage,
+        B: BufMut,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(msg.encoded_len() as u64, buf);
+        msg.encode_raw_deterministic(buf);
     }
 
     pub fn merge<M, B>(
-        tag: u32,
         wire_type: WireType,
         msg: &mut M,
         buf: &mut B,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
-        M: Message,
+        M: DecodeMessage,
         B: Buf,
     {
-        check_wire_type(WireType::StartGroup, wire_type)?;
-
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
         ctx.limit_reached()?;
-        loop {
-            let (field_tag, field_wire_type) = decode_key(buf)?;
-            if field_wire_type == WireType::EndGroup {
-                if field_tag != tag {
-                    return Err(DecodeError::new("unexpected end group tag"));
+        let mut seen = SeenFields::new(&ctx);
+        merge_loop(
+            msg,
+            buf,
+            ctx.enter_recursion(),
+            |msg: &mut M, buf: &mut B, ctx| {
+                let (tag, wire_type) = match ctx.decode_key(buf)? {
+                    Some(key) => key,
+                    None => return Ok(()),
+                };
+                seen.insert::<M>(tag)?;
+                msg.merge_field(tag, wire_type, buf, ctx)
+            },
+        )?;
+        seen.check_required::<M>()
+    }
+
+    pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
+    where
+        M: EncodeMessage,
+        B: BufMut,
+    {
+        for msg in messages {
+            encode(tag, msg, buf);
+        }
+    }
+
+    pub fn merge_repeated<M, B>(
+        wire_type: WireType,
+        messages: &mut Vec<M>,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: DecodeMessage + Default,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let mut msg = M::default();
+        merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
+        messages.push(msg);
+        Ok(())
+    }
+
+    /// Decodes the next element of a repeated message field into the element at index `*reused`,
+    /// which is reset first, or into a new element if there are no elements left to reuse.
+    pub fn replace_repeated<M, B>(
+        wire_type: WireType,
+        messages: &mut Vec<M>,
+        reused: &mut usize,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: DecodeMessage + Default,
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+        let len = ctx.decode_len(buf)?;
+        let limit = buf.remaining() - len;
+
+        if *reused == messages.len() {
+            messages.push(M::default());
+        }
+        messages[*reused].replace_from(buf, limit, ctx.enter_recursion())?;
+        *reused += 1;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
+    where
+        M: EncodeMessage,
+    {
+        let len = msg.encoded_len();
+        key_len(tag) + encoded_len_varint(len as u64) + len
+    }
+
+    #[inline]
+    pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
+    where
+        M: EncodeMessage,
+    {
+        key_len(tag) * messages.len()
+            + messages
+                .iter()
+                .map(EncodeMessage::encoded_len)
+                .map(|len| len + encoded_len_varint(len as u64))
+                .sum::<usize>()
+    }
+}
+
+/// Encoding of groups, the deprecated proto2 alternative to nested message fields.
+///
+/// A group is delimited by a start group key and an end group key with the same tag, rather than
+/// by a length prefix. These helpers are also meant for manual `Message` implementations which
+/// need to write or read group-delimited contents.
+pub mod group {
+    use super::*;
+
+    /// Encodes the start group key of a group field.
+    #[inline]
+    pub fn encode_start<B>(tag: u32, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        encode_key(tag, WireType::StartGroup, buf);
+    }
+
+    /// Encodes the end group key of a group field.
+    #[inline]
+    pub fn encode_end<B>(tag: u32, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        encode_key(tag, WireType::EndGroup, buf);
+    }
+
+    /// Encodes a message as a group field.
+    pub fn encode<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: EncodeMessage,
+        B: BufMut,
+    {
+        // Groups have no length prefix, so the enclosing message may have been written without
+        // sizing the group: refresh the cache used to write the nested messages of the group.
+        if M::has_cached_size() {
+            msg.encoded_len();
+        }
+        encode_start(tag, buf);
+        msg.encode_raw(buf);
+        encode_end(tag, buf);
+    }
+
+    /// Encodes a message as a group field deterministically, with
+    /// [`EncodeMessage::encode_raw_deterministic`].
+    pub fn encode_deterministic<M, B>(tag: u32, msg: &M, buf: &mut B)
+    where
+        M: EncodeMessage,
+        B: BufMut,
+    {
+        if M::has_cached_size() {
+            msg.encoded_len();
+        }
+        encode_start(tag, buf);
+        msg.encode_raw_deterministic(buf);
+        encode_end(tag, buf);
+    }
+
+    /// Decodes the contents of a group field, whose start group key has already been decoded,
+    /// calling `merge` with the tag and wire type of each field in the group.
+    ///
+    /// Returns once the matching end group key has been consumed. Nested groups are left to
+    /// `merge`, e.g. by calling this function recursively or [`skip_field`].
+    pub fn merge_fields<B, F>(
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+        mut merge: F,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        F: FnMut(u32, WireType, &mut B, DecodeContext) -> Result<(), DecodeError>,
+    {
+        check_wire_type(WireType::StartGroup, wire_type)?;
+
+        ctx.limit_reached()?;
+        loop {
+            let (field_tag, field_wire_type) = match ctx.decode_key(buf)? {
+                Some(key) => key,
+                None => continue,
+            };
+            if field_wire_type == WireType::EndGroup {
+                if field_tag != tag {
+                    return Err(DecodeError::new("unexpected end group tag"));
                 }
                 return Ok(());
             }
 
-            M::merge_field(msg, field_tag, field_wire_type, buf, ctx.enter_recursion())?;
+            merge(field_tag, field_wire_type, buf, ctx.enter_recursion())?;
         }
     }
 
+    /// Decodes a group field into a message.
+    pub fn merge<M, B>(
+        tag: u32,
+        wire_type: WireType,
+        msg: &mut M,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: DecodeMessage,
+        B: Buf,
+    {
+        let mut seen = SeenFields::new(&ctx);
+        merge_fields(
+            tag,
+            wire_type,
+            buf,
+            ctx,
+            |field_tag, field_wire_type, buf, ctx| {
+                seen.insert::<M>(field_tag)?;
+                M::merge_field(msg, field_tag, field_wire_type, buf, ctx)
+            },
+        )?;
+        seen.check_required::<M>()
+    }
+
     pub fn encode_repeated<M, B>(tag: u32, messages: &[M], buf: &mut B)
     where
-        M: Message,
+        M: EncodeMessage,
         B: BufMut,
     {
         for msg in messages {
@@ -1179,7 +1835,7 @@ pub mod group {
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
     where
-        M: Message + Default,
+        M: DecodeMessage + Default,
         B: Buf,
     {
         check_wire_type(WireType::StartGroup, wire_type)?;
@@ -1192,7 +1848,7 @@ pub mod group {
     #[inline]
     pub fn encoded_len<M>(tag: u32, msg: &M) -> usize
     where
-        M: Message,
+        M: EncodeMessage,
     {
         2 * key_len(tag) + msg.encoded_len()
     }
@@ -1200,14 +1856,615 @@ pub mod group {
     #[inline]
     pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
     where
-        M: Message,
+        M: EncodeMessage,
     {
-        2 * key_len(tag) * messages.len() + messages.iter().map(Message::encoded_len).sum::<usize>()
+        2 * key_len(tag) * messages.len()
+            + messages
+                .iter()
+                .map(EncodeMessage::encoded_len)
+                .sum::<usize>()
     }
 }
 
-/// Rust doesn't have a `Map` trait, so macros are currently the best way to be
-/// generic over `HashMap` and `BTreeMap`.
+/// Encoding of fields borrowing from the buffer they are decoded from.
+///
+/// `string` fields are decoded into `Cow<'a, str>` and `bytes` fields into `&'a [u8]`, pointing
+/// into the encoded message rather than copying it. Decoding is restricted to `&'a [u8]` buffers
+/// for this reason, and nested messages are merged through [`DecodeBorrowed`].
+pub mod borrowed {
+    use alloc::borrow::Cow;
+
+    use super::*;
+    use crate::DecodeBorrowed;
+
+    /// Splits the value of a length-delimited field off the front of the buffer.
+    fn split_value<'a>(
+        wire_type: WireType,
+        buf: &mut &'a [u8],
+        ctx: &DecodeContext,
+    ) -> Result<&'a [u8], DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = ctx.decode_len(buf)?;
+        let (value, rest) = buf.split_at(len);
+        *buf = rest;
+        Ok(value)
+    }
+
+    pub mod string {
+        use super::*;
+
+        pub fn encode<B>(tag: u32, value: &Cow<'_, str>, buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value.as_bytes());
+        }
+
+        /// Decodes a `string` field borrowing from the buffer.
+        ///
+        /// Invalid UTF-8 replaced under `InvalidUtf8Policy::Replace` is the only case where the
+        /// value is copied.
+        pub fn merge<'a>(
+            wire_type: WireType,
+            value: &mut Cow<'a, str>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            let bytes = split_value(wire_type, buf, &ctx)?;
+            match str::from_utf8(bytes) {
+                Ok(s) => *value = Cow::Borrowed(s),
+                Err(_) if ctx.config.invalid_utf8 == InvalidUtf8Policy::Replace => {
+                    *value = String::from_utf8_lossy(bytes);
+                }
+                Err(_) => {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::InvalidUtf8,
+                        "invalid string value: data is not UTF-8 encoded",
+                    ))
+                }
+            }
+            Ok(())
+        }
+
+        encode_repeated!(Cow<'_, str>);
+
+        pub fn merge_repeated<'a>(
+            wire_type: WireType,
+            values: &mut Vec<Cow<'a, str>>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            let mut value = Cow::Borrowed("");
+            merge(wire_type, &mut value, buf, ctx)?;
+            values.push(value);
+            Ok(())
+        }
+
+        #[inline]
+        pub fn encoded_len(tag: u32, value: &Cow<'_, str>) -> usize {
+            key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+        }
+
+        #[inline]
+        pub fn encoded_len_repeated(tag: u32, values: &[Cow<'_, str>]) -> usize {
+            key_len(tag) * values.len()
+                + values
+                    .iter()
+                    .map(|value| encoded_len_varint(value.len() as u64) + value.len())
+                    .sum::<usize>()
+        }
+    }
+
+    pub mod bytes {
+        use super::*;
+
+        pub fn encode<B>(tag: u32, value: &&[u8], buf: &mut B)
+        where
+            B: BufMut,
+        {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value);
+        }
+
+        /// Decodes a `bytes` field borrowing from the buffer.
+        pub fn merge<'a>(
+            wire_type: WireType,
+            value: &mut &'a [u8],
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            *value = split_value(wire_type, buf, &ctx)?;
+            Ok(())
+        }
+
+        encode_repeated!(&[u8]);
+
+        pub fn merge_repeated<'a>(
+            wire_type: WireType,
+            values: &mut Vec<&'a [u8]>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            let mut value: &[u8] = &[];
+            merge(wire_type, &mut value, buf, ctx)?;
+            values.push(value);
+            Ok(())
+        }
+
+        #[inline]
+        pub fn encoded_len(tag: u32, value: &&[u8]) -> usize {
+            key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+        }
+
+        #[inline]
+        pub fn encoded_len_repeated(tag: u32, values: &[&[u8]]) -> usize {
+            key_len(tag) * values.len()
+                + values
+                    .iter()
+                    .map(|value| encoded_len_varint(value.len() as u64) + value.len())
+                    .sum::<usize>()
+        }
+    }
+
+    pub mod message {
+        use super::*;
+
+        /// Decodes a message field whose message may borrow from the buffer.
+        pub fn merge<'a, M>(
+            wire_type: WireType,
+            msg: &mut M,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            M: DecodeBorrowed<'a>,
+        {
+            ctx.limit_reached()?;
+            let mut fields = split_value(wire_type, buf, &ctx)?;
+            msg.merge_fields_borrowed(&mut fields, ctx.enter_recursion())
+        }
+
+        pub fn merge_repeated<'a, M>(
+            wire_type: WireType,
+            messages: &mut Vec<M>,
+            buf: &mut &'a [u8],
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError>
+        where
+            M: DecodeBorrowed<'a> + Default,
+        {
+            let mut msg = M::default();
+            merge(wire_type, &mut msg, buf, ctx)?;
+            messages.push(msg);
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn string() {
+            let mut buf = Vec::new();
+            string::encode(1, &Cow::Borrowed("hello"), &mut buf);
+            assert_eq!(buf.len(), string::encoded_len(1, &Cow::Borrowed("hello")));
+
+            let mut input = &buf[1..];
+            let mut value = Cow::Borrowed("");
+            string::merge(
+                WireType::LengthDelimited,
+                &mut value,
+                &mut input,
+                DecodeContext::default(),
+            )
+            .unwrap();
+            assert!(input.is_empty());
+            match value {
+                Cow::Borrowed(s) => assert_eq!(s.as_ptr(), buf[2..].as_ptr()),
+                Cow::Owned(_) => panic!("string was copied"),
+            }
+        }
+
+        #[test]
+        fn invalid_utf8() {
+            let buf = [0x02, 0xff, 0x61];
+            let mut value = Cow::Borrowed("");
+            assert!(string::merge(
+                WireType::LengthDelimited,
+                &mut value,
+                &mut &buf[..],
+                DecodeContext::default(),
+            )
+            .is_err());
+
+            let config = DecodeConfig::new().invalid_utf8(InvalidUtf8Policy::Replace);
+            string::merge(
+                WireType::LengthDelimited,
+                &mut value,
+                &mut &buf[..],
+                DecodeContext::with_config(config),
+            )
+            .unwrap();
+            assert_eq!(value, "\u{FFFD}a");
+        }
+
+        #[test]
+        fn bytes() {
+            let mut buf = Vec::new();
+            bytes::encode_repeated(2, &[&b"ab"[..], &b""[..]], &mut buf);
+            assert_eq!(
+                buf.len(),
+                bytes::encoded_len_repeated(2, &[&b"ab"[..], &b""[..]])
+            );
+
+            let mut input = &buf[..];
+            let mut values = Vec::new();
+            while !input.is_empty() {
+                let (tag, wire_type) = decode_key(&mut input).unwrap();
+                assert_eq!(tag, 2);
+                bytes::merge_repeated(wire_type, &mut values, &mut input, DecodeContext::default())
+                    .unwrap();
+            }
+            assert_eq!(values, [&b"ab"[..], &b""[..]]);
+            assert_eq!(values[0].as_ptr(), buf[2..].as_ptr());
+        }
+
+        #[test]
+        fn truncated() {
+            let buf = [0x05, 0x61];
+            let mut value: &[u8] = &[];
+            assert!(bytes::merge(
+                WireType::LengthDelimited,
+                &mut value,
+                &mut &buf[..],
+                DecodeContext::default(),
+            )
+            .is_err());
+        }
+    }
+}
+
+/// A container for the entries of a Protobuf `map` field.
+///
+/// `HashMap` and `BTreeMap` are `MapContainer`s, and other map-like types, such as sorted vectors
+/// or maps with interned keys, can implement the trait to be used as `map` fields. Fields with a
+/// custom container are declared with the `map_container` attribute, which takes the key and value
+/// types like the `map` attribute:
+///
+/// ```rust,ignore
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// pub struct Labels {
+///     #[prost(map_container = "string, string", tag = "1")]
+///     pub labels: SortedVecMap<String, String>,
+/// }
+/// ```
+pub trait MapContainer: Default {
+    /// The type of the keys.
+    type Key;
+    /// The type of the values.
+    type Value;
+
+    /// Returns the number of entries in the container.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the container has no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts an entry, replacing the value of an existing entry with the same key.
+    fn insert(&mut self, key: Self::Key, value: Self::Value);
+
+    /// Calls `f` with each entry of the container.
+    ///
+    /// Entries are encoded in this order, by
+    /// [`encode_deterministic`](crate::EncodeMessage::encode_deterministic) as well, so a container
+    /// whose equal instances can iterate in different orders doesn't encode deterministically.
+    fn for_each<F>(&self, f: F)
+    where
+        F: FnMut(&Self::Key, &Self::Value);
+
+    /// Removes all entries of the container.
+    fn clear(&mut self);
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S> MapContainer for std::collections::HashMap<K, V, S>
+where
+    K: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher + Default,
+{
+    type Key = K;
+    type Value = V;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&K, &V),
+    {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
+impl<K, V> MapContainer for BTreeMap<K, V>
+where
+    K: Ord,
+{
+    type Key = K;
+    type Value = V;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&K, &V),
+    {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
+/// Encoding of `map` fields stored in any [`MapContainer`].
+pub mod map {
+    use super::*;
+
+    /// Generic protobuf map encode function.
+    pub fn encode<M, B, KE, KL, VE, VL>(
+        key_encode: KE,
+        key_encoded_len: KL,
+        val_encode: VE,
+        val_encoded_len: VL,
+        tag: u32,
+        values: &M,
+        buf: &mut B,
+    ) where
+        M: MapContainer,
+        M::Key: Default + PartialEq,
+        M::Value: Default + PartialEq,
+        B: BufMut,
+        KE: Fn(u32, &M::Key, &mut B),
+        KL: Fn(u32, &M::Key) -> usize,
+        VE: Fn(u32, &M::Value, &mut B),
+        VL: Fn(u32, &M::Value) -> usize,
+    {
+        encode_with_default(
+            key_encode,
+            key_encoded_len,
+            val_encode,
+            val_encoded_len,
+            &M::Value::default(),
+            tag,
+            values,
+            buf,
+        )
+    }
+
+    /// Generic protobuf map merge function.
+    pub fn merge<M, B, KM, VM>(
+        key_merge: KM,
+        val_merge: VM,
+        values: &mut M,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: MapContainer,
+        M::Key: Default,
+        M::Value: Default,
+        B: Buf,
+        KM: Fn(WireType, &mut M::Key, &mut B, DecodeContext) -> Result<(), DecodeError>,
+        VM: Fn(WireType, &mut M::Value, &mut B, DecodeContext) -> Result<(), DecodeError>,
+    {
+        merge_with_default(key_merge, val_merge, M::Value::default(), values, buf, ctx)
+    }
+
+    /// Generic protobuf map encode function.
+    pub fn encoded_len<M, KL, VL>(
+        key_encoded_len: KL,
+        val_encoded_len: VL,
+        tag: u32,
+        values: &M,
+    ) -> usize
+    where
+        M: MapContainer,
+        M::Key: Default + PartialEq,
+        M::Value: Default + PartialEq,
+        KL: Fn(u32, &M::Key) -> usize,
+        VL: Fn(u32, &M::Value) -> usize,
+    {
+        encoded_len_with_default(
+            key_encoded_len,
+            val_encoded_len,
+            &M::Value::default(),
+            tag,
+            values,
+        )
+    }
+
+    /// Generic protobuf map encode function with an overriden value default.
+    ///
+    /// This is necessary because enumeration values can have a default value other
+    /// than 0 in proto2.
+    pub fn encode_with_default<M, B, KE, KL, VE, VL>(
+        key_encode: KE,
+        key_encoded_len: KL,
+        val_encode: VE,
+        val_encoded_len: VL,
+        val_default: &M::Value,
+        tag: u32,
+        values: &M,
+        buf: &mut B,
+    ) where
+        M: MapContainer,
+        M::Key: Default + PartialEq,
+        M::Value: PartialEq,
+        B: BufMut,
+        KE: Fn(u32, &M::Key, &mut B),
+        KL: Fn(u32, &M::Key) -> usize,
+        VE: Fn(u32, &M::Value, &mut B),
+        VL: Fn(u32, &M::Value) -> usize,
+    {
+        let key_default = M::Key::default();
+        values.for_each(|key, val| {
+            encode_entry(
+                &key_encode,
+                &key_encoded_len,
+                &val_encode,
+                &val_encoded_len,
+                &key_default,
+                val_default,
+                tag,
+                key,
+                val,
+                buf,
+            )
+        });
+    }
+
+    /// Encodes a single map entry.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn encode_entry<K, V, B, KE, KL, VE, VL>(
+        key_encode: &KE,
+        key_encoded_len: &KL,
+        val_encode: &VE,
+        val_encoded_len: &VL,
+        key_default: &K,
+        val_default: &V,
+        tag: u32,
+        key: &K,
+        val: &V,
+        buf: &mut B,
+    ) where
+        K: PartialEq,
+        V: PartialEq,
+        B: BufMut,
+        KE: Fn(u32, &K, &mut B),
+        KL: Fn(u32, &K) -> usize,
+        VE: Fn(u32, &V, &mut B),
+        VL: Fn(u32, &V) -> usize,
+    {
+        let skip_key = key == key_default;
+        let skip_val = val == val_default;
+
+        let len = (if skip_key { 0 } else { key_encoded_len(1, key) })
+            + (if skip_val { 0 } else { val_encoded_len(2, val) });
+
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(len as u64, buf);
+        if !skip_key {
+            key_encode(1, key, buf);
+        }
+        if !skip_val {
+            val_encode(2, val, buf);
+        }
+    }
+
+    /// Generic protobuf map merge function with an overriden value default.
+    ///
+    /// This is necessary because enumeration values can have a default value other
+    /// than 0 in proto2.
+    pub fn merge_with_default<M, B, KM, VM>(
+        key_merge: KM,
+        val_merge: VM,
+        val_default: M::Value,
+        values: &mut M,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        M: MapContainer,
+        M::Key: Default,
+        B: Buf,
+        KM: Fn(WireType, &mut M::Key, &mut B, DecodeContext) -> Result<(), DecodeError>,
+        VM: Fn(WireType, &mut M::Value, &mut B, DecodeContext) -> Result<(), DecodeError>,
+    {
+        let mut key = Default::default();
+        let mut val = val_default;
+        ctx.limit_reached()?;
+        merge_loop(
+            &mut (&mut key, &mut val),
+            buf,
+            ctx.enter_recursion(),
+            |&mut (ref mut key, ref mut val), buf, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                match tag {
+                    1 => key_merge(wire_type, key, buf, ctx),
+                    2 => val_merge(wire_type, val, buf, ctx),
+                    _ => skip_field(wire_type, tag, buf, ctx),
+                }
+            },
+        )?;
+        values.insert(key, val);
+
+        Ok(())
+    }
+
+    /// Generic protobuf map encode function with an overriden value default.
+    ///
+    /// This is necessary because enumeration values can have a default value other
+    /// than 0 in proto2.
+    pub fn encoded_len_with_default<M, KL, VL>(
+        key_encoded_len: KL,
+        val_encoded_len: VL,
+        val_default: &M::Value,
+        tag: u32,
+        values: &M,
+    ) -> usize
+    where
+        M: MapContainer,
+        M::Key: Default + PartialEq,
+        M::Value: PartialEq,
+        KL: Fn(u32, &M::Key) -> usize,
+        VL: Fn(u32, &M::Value) -> usize,
+    {
+        let key_default = M::Key::default();
+        let mut len = key_len(tag) * values.len();
+        values.for_each(|key, val| {
+            let entry_len = (if key == &key_default {
+                0
+            } else {
+                key_encoded_len(1, key)
+            }) + (if val == val_default {
+                0
+            } else {
+                val_encoded_len(2, val)
+            });
+            len += encoded_len_varint(entry_len as u64) + entry_len;
+        });
+        len
+    }
+}
+
+/// Defines the functions of the `hash_map` and `btree_map` modules, which generated code calls for
+/// `map` fields, in terms of the generic `MapContainer` functions of the `map` module.
 macro_rules! map {
     ($map_ty:ident) => {
         use crate::encoding::*;
@@ -1258,7 +2515,7 @@ macro_rules! map {
             KM: Fn(WireType, &mut K, &mut B, DecodeContext) -> Result<(), DecodeError>,
             VM: Fn(WireType, &mut V, &mut B, DecodeContext) -> Result<(), DecodeError>,
         {
-            merge_with_default(key_merge, val_merge, V::default(), values, buf, ctx)
+            map::merge(key_merge, val_merge, values, buf, ctx)
         }
 
         /// Generic protobuf map encode function.
@@ -1274,7 +2531,7 @@ macro_rules! map {
             KL: Fn(u32, &K) -> usize,
             VL: Fn(u32, &V) -> usize,
         {
-            encoded_len_with_default(key_encoded_len, val_encoded_len, &V::default(), tag, values)
+            map::encoded_len(key_encoded_len, val_encoded_len, tag, values)
         }
 
         /// Generic protobuf map encode function with an overriden value default.
@@ -1299,21 +2556,96 @@ macro_rules! map {
             VE: Fn(u32, &V, &mut B),
             VL: Fn(u32, &V) -> usize,
         {
-            for (key, val) in values.iter() {
-                let skip_key = key == &K::default();
-                let skip_val = val == val_default;
+            map::encode_with_default(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                val_default,
+                tag,
+                values,
+                buf,
+            )
+        }
 
-                let len = (if skip_key { 0 } else { key_encoded_len(1, key) })
-                    + (if skip_val { 0 } else { val_encoded_len(2, val) });
+        /// Encodes a map field with its entries in key order, for deterministic encoding.
+        pub fn encode_sorted<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: Default + PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            encode_sorted_with_default(
+                key_encode,
+                key_encoded_len,
+                val_encode,
+                val_encoded_len,
+                &V::default(),
+                tag,
+                values,
+                buf,
+            )
+        }
 
-                encode_key(tag, WireType::LengthDelimited, buf);
-                encode_varint(len as u64, buf);
-                if !skip_key {
-                    key_encode(1, key, buf);
-                }
-                if !skip_val {
-                    val_encode(2, val, buf);
-                }
+        /// Encodes a map field with its entries in key order, for deterministic encoding, with an
+        /// overriden value default.
+        pub fn encode_sorted_with_default<K, V, B, KE, KL, VE, VL>(
+            key_encode: KE,
+            key_encoded_len: KL,
+            val_encode: VE,
+            val_encoded_len: VL,
+            val_default: &V,
+            tag: u32,
+            values: &$map_ty<K, V>,
+            buf: &mut B,
+        ) where
+            K: Default + Eq + Hash + Ord,
+            V: PartialEq,
+            B: BufMut,
+            KE: Fn(u32, &K, &mut B),
+            KL: Fn(u32, &K) -> usize,
+            VE: Fn(u32, &V, &mut B),
+            VL: Fn(u32, &V) -> usize,
+        {
+            if SORTED {
+                return encode_with_default(
+                    key_encode,
+                    key_encoded_len,
+                    val_encode,
+                    val_encoded_len,
+                    val_default,
+                    tag,
+                    values,
+                    buf,
+                );
+            }
+            let mut entries = values.iter().collect::<Vec<_>>();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            let key_default = K::default();
+            for (key, val) in entries {
+                map::encode_entry(
+                    &key_encode,
+                    &key_encoded_len,
+                    &val_encode,
+                    &val_encoded_len,
+                    &key_default,
+                    val_default,
+                    tag,
+                    key,
+                    val,
+                    buf,
+                );
             }
         }
 
@@ -1335,25 +2667,7 @@ macro_rules! map {
             KM: Fn(WireType, &mut K, &mut B, DecodeContext) -> Result<(), DecodeError>,
             VM: Fn(WireType, &mut V, &mut B, DecodeContext) -> Result<(), DecodeError>,
         {
-            let mut key = Default::default();
-            let mut val = val_default;
-            ctx.limit_reached()?;
-            merge_loop(
-                &mut (&mut key, &mut val),
-                buf,
-                ctx.enter_recursion(),
-                |&mut (ref mut key, ref mut val), buf, ctx| {
-                    let (tag, wire_type) = decode_key(buf)?;
-                    match tag {
-                        1 => key_merge(wire_type, key, buf, ctx),
-                        2 => val_merge(wire_type, val, buf, ctx),
-                        _ => skip_field(wire_type, tag, buf, ctx),
-                    }
-                },
-            )?;
-            values.insert(key, val);
-
-            Ok(())
+            map::merge_with_default(key_merge, val_merge, val_default, values, buf, ctx)
         }
 
         /// Generic protobuf map encode function with an overriden value default.
@@ -1373,22 +2687,13 @@ macro_rules! map {
             KL: Fn(u32, &K) -> usize,
             VL: Fn(u32, &V) -> usize,
         {
-            key_len(tag) * values.len()
-                + values
-                    .iter()
-                    .map(|(key, val)| {
-                        let len = (if key == &K::default() {
-                            0
-                        } else {
-                            key_encoded_len(1, key)
-                        }) + (if val == val_default {
-                            0
-                        } else {
-                            val_encoded_len(2, val)
-                        });
-                        encoded_len_varint(len as u64) + len
-                    })
-                    .sum::<usize>()
+            map::encoded_len_with_default(
+                key_encoded_len,
+                val_encoded_len,
+                val_default,
+                tag,
+                values,
+            )
         }
     };
 }
@@ -1396,16 +2701,21 @@ macro_rules! map {
 #[cfg(feature = "std")]
 pub mod hash_map {
     use std::collections::HashMap;
+    /// Whether the map iterates its entries in key order.
+    const SORTED: bool = false;
     map!(HashMap);
 }
 
 pub mod btree_map {
+    /// Whether the map iterates its entries in key order.
+    const SORTED: bool = true;
     map!(BTreeMap);
 }
 
 #[cfg(test)]
 mod test {
     use alloc::string::ToString;
+    use alloc::vec;
     use core::borrow::Borrow;
     use core::fmt::Debug;
     use core::u64;
@@ -1583,6 +2893,216 @@ mod test {
         assert!(s.is_empty());
     }
 
+    #[test]
+    fn wire_type() {
+        for value in 0..6 {
+            let wire_type = WireType::try_from(value).unwrap();
+            assert_eq!(wire_type as u64, value);
+            assert_eq!(wire_type.is_length_delimited(), value == 2);
+        }
+        assert!(WireType::try_from(6).is_err());
+        assert!(WireType::try_from(7).is_err());
+        assert_eq!(WireType::LengthDelimited.to_string(), "length-delimited");
+        assert_eq!(format!("{}", WireType::SixtyFourBit), "64-bit");
+    }
+
+    #[test]
+    fn group_fields() {
+        let mut buf = Vec::new();
+        group::encode_start(1, &mut buf);
+        uint32::encode(2, &7, &mut buf);
+        group::encode_start(3, &mut buf);
+        uint32::encode(4, &8, &mut buf);
+        group::encode_end(3, &mut buf);
+        group::encode_end(1, &mut buf);
+        uint32::encode(5, &9, &mut buf);
+
+        let mut buf = &buf[..];
+        let (tag, wire_type) = decode_key(&mut buf).unwrap();
+        let mut fields = Vec::new();
+        group::merge_fields(
+            tag,
+            wire_type,
+            &mut buf,
+            DecodeContext::default(),
+            |tag, wire_type, buf, ctx| {
+                fields.push((tag, wire_type));
+                skip_field(wire_type, tag, buf, ctx)
+            },
+        )
+        .unwrap();
+        assert_eq!(fields, [(2, WireType::Varint), (3, WireType::StartGroup)]);
+        assert_eq!(buf, [0x28, 0x09]);
+
+        let mut buf = Vec::new();
+        group::encode_start(1, &mut buf);
+        group::encode_end(2, &mut buf);
+        let mut buf = &buf[1..];
+        assert!(group::merge_fields(
+            1,
+            WireType::StartGroup,
+            &mut buf,
+            DecodeContext::default(),
+            |tag, wire_type, buf, ctx| skip_field(wire_type, tag, buf, ctx),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unknown_field_hook() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static FIELDS: AtomicUsize = AtomicUsize::new(0);
+        static BYTES: AtomicUsize = AtomicUsize::new(0);
+        fn hook(_tag: u32, len: usize) {
+            FIELDS.fetch_add(1, Ordering::Relaxed);
+            BYTES.fetch_add(len, Ordering::Relaxed);
+        }
+
+        let mut buf = Vec::new();
+        group::encode_start(20, &mut buf);
+        uint32::encode(2, &7, &mut buf);
+        group::encode_end(20, &mut buf);
+        uint32::encode(1, &9, &mut buf);
+        string::encode(3, &String::from("skipped"), &mut buf);
+
+        let config = DecodeConfig::new().on_unknown_field(hook);
+        assert_eq!(u32::decode_with_config(&buf[..], config).unwrap(), 9);
+        // The field in the group isn't counted separately.
+        assert_eq!(FIELDS.load(Ordering::Relaxed), 2);
+        assert_eq!(BYTES.load(Ordering::Relaxed), buf.len() - 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn deterministic_hash_map() {
+        let hash_map = (0..100u32)
+            .map(|key| (key, key.to_string()))
+            .collect::<std::collections::HashMap<_, _>>();
+        let btree_map = hash_map.clone().into_iter().collect::<BTreeMap<_, _>>();
+
+        let mut expected = Vec::new();
+        crate::encoding::btree_map::encode(
+            uint32::encode,
+            uint32::encoded_len,
+            string::encode,
+            string::encoded_len,
+            1,
+            &btree_map,
+            &mut expected,
+        );
+
+        let mut buf = Vec::new();
+        crate::encoding::hash_map::encode_sorted(
+            uint32::encode,
+            uint32::encoded_len,
+            string::encode,
+            string::encoded_len,
+            1,
+            &hash_map,
+            &mut buf,
+        );
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn decode_limits_and_policies() {
+        let mut buf = Vec::new();
+        string::encode(1, &String::from("hello"), &mut buf);
+
+        let config = DecodeConfig::new().max_message_size(buf.len());
+        assert_eq!(
+            String::decode_with_config(&buf[..], config).unwrap(),
+            "hello"
+        );
+        let config = DecodeConfig::new().max_message_size(buf.len() - 1);
+        assert!(String::decode_with_config(&buf[..], config).is_err());
+
+        let config = DecodeConfig::new().max_field_size(5);
+        assert_eq!(
+            String::decode_with_config(&buf[..], config).unwrap(),
+            "hello"
+        );
+        let config = DecodeConfig::new().max_field_size(4);
+        assert!(String::decode_with_config(&buf[..], config).is_err());
+
+        // Unknown fields are rejected, but not retained.
+        uint32::encode(2, &7, &mut buf);
+        let config = DecodeConfig::new().unknown_fields(UnknownFieldPolicy::Reject);
+        assert!(String::decode(&buf[..]).is_ok());
+        assert!(String::decode_with_config(&buf[..], config).is_err());
+        let fields = crate::UnknownFieldSet::decode_with_config(&buf[..], config).unwrap();
+        assert_eq!(fields.len(), 2);
+
+        let mut buf = Vec::new();
+        bytes::encode(1, &vec![b'a', 0xff, b'b'], &mut buf);
+        assert!(String::decode(&buf[..]).is_err());
+        let config = DecodeConfig::new().invalid_utf8(InvalidUtf8Policy::Replace);
+        assert_eq!(
+            String::decode_with_config(&buf[..], config).unwrap(),
+            "a\u{fffd}b"
+        );
+    }
+
+    #[test]
+    fn length_delimited_chained() {
+        let value = (0..100u8).collect::<Vec<u8>>();
+        let mut buf = Vec::new();
+        bytes::encode(1, &value, &mut buf);
+        uint32::encode(2, &7, &mut buf);
+
+        for split in 0..=buf.len() {
+            let (first, second) = buf.split_at(split);
+            let ctx = DecodeContext::default();
+
+            let mut chain = first.chain(second);
+            let (_, wire_type) = decode_key(&mut chain).unwrap();
+            let mut decoded = Vec::new();
+            bytes::merge(wire_type, &mut decoded, &mut chain, ctx.clone()).unwrap();
+            assert_eq!(decoded, value);
+            assert_eq!(decode_key(&mut chain).unwrap(), (2, WireType::Varint));
+
+            let mut chain = Bytes::copy_from_slice(first).chain(Bytes::copy_from_slice(second));
+            let (_, wire_type) = decode_key(&mut chain).unwrap();
+            let mut decoded = Bytes::new();
+            bytes::merge(wire_type, &mut decoded, &mut chain, ctx).unwrap();
+            assert_eq!(decoded, value);
+            assert_eq!(decode_key(&mut chain).unwrap(), (2, WireType::Varint));
+        }
+    }
+
+    /// A `Buf` which returns an empty chunk after each byte is read, before its end.
+    struct Stuttering<'a> {
+        buf: &'a [u8],
+        stalled: bool,
+    }
+
+    impl Buf for Stuttering<'_> {
+        fn remaining(&self) -> usize {
+            self.buf.len()
+        }
+
+        fn chunk(&self) -> &[u8] {
+            if self.stalled {
+                &[]
+            } else {
+                &self.buf[..min(1, self.buf.len())]
+            }
+        }
+
+        fn advance(&mut self, cnt: usize) {
+            self.buf = &self.buf[cnt..];
+            self.stalled = cnt > 0;
+        }
+
+        fn get_u8(&mut self) -> u8 {
+            let byte = self.buf[0];
+            self.buf = &self.buf[1..];
+            self.stalled = false;
+            byte
+        }
+    }
+
     #[test]
     fn varint() {
         fn check(value: u64, mut encoded: &[u8]) {
@@ -1604,8 +3124,22 @@ mod test {
             let roundtrip_value = decode_varint(&mut encoded.clone()).expect("decoding failed");
             assert_eq!(value, roundtrip_value);
 
+            // Varints split across the chunks of a non-contiguous buffer.
+            for split in 0..=encoded.len() {
+                let (first, second) = encoded.split_at(split);
+                let mut chain = first.chain(second);
+                assert_eq!(decode_varint(&mut chain).unwrap(), value);
+                assert!(!chain.has_remaining());
+            }
             let roundtrip_value = decode_varint_slow(&mut encoded).expect("slow decoding failed");
             assert_eq!(value, roundtrip_value);
+
+            let mut stuttering = Stuttering {
+                buf: &buf,
+                stalled: false,
+            };
+            assert_eq!(decode_varint(&mut stuttering).unwrap(), value);
+            assert!(!stuttering.has_remaining());
         }
 
         check(2u64.pow(0) - 1, &[0x00]);
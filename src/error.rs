@@ -2,10 +2,13 @@
 
 use alloc::borrow::Cow;
 use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::vec::Vec;
 
 use core::fmt;
 
+use crate::encoding::WireType;
+
 /// A Protobuf message decoding error.
 ///
 /// `DecodeError` indicates that the input buffer does not caontain a valid
@@ -18,12 +21,23 @@ pub struct DecodeError {
 
 #[derive(Clone, PartialEq, Eq)]
 struct Inner {
+    /// The kind of error.
+    kind: DecodeErrorKind,
     /// A 'best effort' root cause description.
     description: Cow<'static, str>,
-    /// A stack of (message, field) name pairs, which identify the specific
-    /// message type and field where decoding failed. The stack contains an
-    /// entry per level of nesting.
-    stack: Vec<(&'static str, &'static str)>,
+    /// A stack of locations, which identify the specific message type and
+    /// field where decoding failed. The stack contains an entry per level of
+    /// nesting, innermost first.
+    stack: Vec<Location>,
+}
+
+/// A field of a message in which decoding failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Location {
+    message: &'static str,
+    field: &'static str,
+    /// The index of the element, for repeated fields.
+    index: Option<usize>,
 }
 
 impl DecodeError {
@@ -33,26 +47,92 @@ impl DecodeError {
     #[doc(hidden)]
     #[cold]
     pub fn new(description: impl Into<Cow<'static, str>>) -> DecodeError {
+        DecodeError::with_kind(DecodeErrorKind::Other, description)
+    }
+
+    /// Creates a new `DecodeError` of a specific kind.
+    #[cold]
+    pub(crate) fn with_kind(
+        kind: DecodeErrorKind,
+        description: impl Into<Cow<'static, str>>,
+    ) -> DecodeError {
         DecodeError {
             inner: Box::new(Inner {
+                kind,
                 description: description.into(),
                 stack: Vec::new(),
             }),
         }
     }
 
+    /// Emits a `tracing` event for the error, as it leaves a public decode entry point.
+    pub(crate) fn traced(self) -> DecodeError {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(kind = ?self.inner.kind, error = %self, "protobuf decode error");
+        self
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.inner.kind
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
     #[doc(hidden)]
     pub fn push(&mut self, message: &'static str, field: &'static str) {
-        self.inner.stack.push((message, field));
+        self.inner.stack.push(Location {
+            message,
+            field,
+            index: None,
+        });
+    }
+
+    /// Pushes the location of an element of a repeated field on to the location stack.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    pub fn push_element(&mut self, message: &'static str, field: &'static str, index: usize) {
+        self.inner.stack.push(Location {
+            message,
+            field,
+            index: Some(index),
+        });
+    }
+
+    /// Returns the path of the field in which decoding failed, from the outermost message, such as
+    /// `address.lines[3].city`, or `None` if the error isn't specific to a field.
+    ///
+    /// The path is also part of the `Display` output of the error, after the name of the
+    /// outermost message.
+    pub fn field_path(&self) -> Option<String> {
+        if self.inner.stack.is_empty() {
+            return None;
+        }
+        let mut path = String::new();
+        self.write_field_path(&mut path).unwrap();
+        Some(path)
+    }
+
+    fn write_field_path(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        for (i, location) in self.inner.stack.iter().rev().enumerate() {
+            if i > 0 {
+                w.write_char('.')?;
+            }
+            w.write_str(location.field)?;
+            if let Some(index) = location.index {
+                write!(w, "[{}]", index)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl fmt::Debug for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DecodeError")
+            .field("kind", &self.inner.kind)
             .field("description", &self.inner.description)
             .field("stack", &self.inner.stack)
             .finish()
@@ -62,8 +142,10 @@ impl fmt::Debug for DecodeError {
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("failed to decode Protobuf message: ")?;
-        for &(message, field) in &self.inner.stack {
-            write!(f, "{}.{}: ", message, field)?;
+        if let Some(outermost) = self.inner.stack.last() {
+            write!(f, "{}.", outermost.message)?;
+            self.write_field_path(f)?;
+            f.write_str(": ")?;
         }
         f.write_str(&self.inner.description)
     }
@@ -72,6 +154,61 @@ impl fmt::Display for DecodeError {
 #[cfg(feature = "std")]
 impl std::error::Error for DecodeError {}
 
+/// The kind of a [`DecodeError`], for the errors which callers may want to handle specifically.
+///
+/// The kind classifies the cause of the error; the error message describes it, and is unchanged
+/// by the classification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeErrorKind {
+    /// The input ends in the middle of a value.
+    ///
+    /// When the input is read incrementally, this may mean that more of it is needed.
+    Truncated,
+    /// A varint is longer than 10 bytes, or overflows a `u64`.
+    InvalidVarint,
+    /// A field has a different wire type than its type requires.
+    InvalidWireType {
+        /// The wire type of the field type.
+        expected: WireType,
+        /// The wire type of the field key.
+        actual: WireType,
+    },
+    /// A `string` value isn't valid UTF-8.
+    InvalidUtf8,
+    /// Messages or groups are nested more deeply than the
+    /// [recursion limit](crate::DecodeConfig::recursion_limit).
+    RecursionLimit,
+    /// A length exceeds the `usize` range, the enclosing message, or a
+    /// [size limit](crate::DecodeConfig::max_field_size).
+    LengthOverflow,
+    /// A field key has one of the reserved wire types 6 and 7.
+    ///
+    /// The length of a value with a reserved wire type can't be determined, so the rest of the
+    /// message can't be decoded reliably. This usually means that the input is corrupted, or is
+    /// not a Protobuf message at all. See
+    /// [`ReservedWireTypePolicy`](crate::ReservedWireTypePolicy) for decoding such input anyway.
+    ReservedWireType {
+        /// The field tag of the key.
+        tag: u32,
+        /// The wire type of the key, 6 or 7.
+        wire_type: u8,
+    },
+    /// A message lacks some of its proto2 `required` fields, which the error message lists.
+    ///
+    /// Only reported with [`RequiredFieldPolicy::Reject`](crate::RequiredFieldPolicy::Reject).
+    MissingRequiredFields,
+    /// A non-repeated field, or another member of the same oneof, occurs more than once.
+    ///
+    /// Only reported with [`DuplicateFieldPolicy::Reject`](crate::DuplicateFieldPolicy::Reject).
+    DuplicateField {
+        /// The field tag of the duplicate.
+        tag: u32,
+    },
+    /// Any other error, described by the error message.
+    Other,
+}
+
 #[cfg(feature = "std")]
 impl From<DecodeError> for std::io::Error {
     fn from(error: DecodeError) -> std::io::Error {
@@ -81,13 +218,17 @@ impl From<DecodeError> for std::io::Error {
 
 /// A Protobuf message encoding error.
 ///
-/// `EncodeError` always indicates that a message failed to encode because the
-/// provided buffer had insufficient capacity. Message encoding is otherwise
-/// infallible.
+/// `EncodeError` indicates that a message failed to encode because the
+/// provided buffer had insufficient capacity, or that a length delimiter
+/// exceeded the limit given to [`encode_length_delimiter_with_limit`].
+/// Message encoding is otherwise infallible.
+///
+/// [`encode_length_delimiter_with_limit`]: crate::encode_length_delimiter_with_limit
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct EncodeError {
     required: usize,
     remaining: usize,
+    limit: Option<usize>,
 }
 
 impl EncodeError {
@@ -96,6 +237,17 @@ impl EncodeError {
         EncodeError {
             required,
             remaining,
+            limit: None,
+        }
+    }
+
+    /// Creates an `EncodeError` for a length which exceeds `limit`, with `remaining` the
+    /// remaining length in the buffer.
+    pub(crate) fn length_limit(length: usize, limit: usize, remaining: usize) -> EncodeError {
+        EncodeError {
+            required: length,
+            remaining,
+            limit: Some(limit),
         }
     }
 
@@ -108,15 +260,31 @@ impl EncodeError {
     pub fn remaining(&self) -> usize {
         self.remaining
     }
+
+    /// Returns the limit which the encoded length exceeded, or `None` if the buffer had
+    /// insufficient capacity.
+    ///
+    /// For such errors, [`required_capacity`](EncodeError::required_capacity) is the encoded
+    /// length, which need not exceed [`remaining`](EncodeError::remaining).
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
 }
 
 impl fmt::Display for EncodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "failed to encode Protobuf messsage; insufficient buffer capacity (required: {}, remaining: {})",
-            self.required, self.remaining
-        )
+        match self.limit {
+            Some(limit) => write!(
+                f,
+                "failed to encode Protobuf messsage; length exceeds limit (length: {}, limit: {})",
+                self.required, limit
+            ),
+            None => write!(
+                f,
+                "failed to encode Protobuf messsage; insufficient buffer capacity (required: {}, remaining: {})",
+                self.required, self.remaining
+            ),
+        }
     }
 }
 
@@ -9,23 +9,64 @@ pub extern crate alloc;
 #[doc(hidden)]
 pub use bytes;
 
+mod blob;
+mod borrowed;
+mod cached_size;
+#[cfg(any(feature = "std-types", feature = "uuid"))]
+mod common_types;
+mod decode_config;
+mod dyn_message;
 mod error;
+mod lossy_string;
 mod message;
+mod open_enum;
+mod partial_decoder;
+mod sink;
+mod stream_decoder;
+#[cfg(feature = "tokio")]
+mod tokio_codec;
 mod types;
+mod unknown_field_set;
 
 #[doc(hidden)]
 pub mod encoding;
+pub mod reflect;
 
-pub use crate::error::{DecodeError, EncodeError};
-pub use crate::message::Message;
+pub use crate::blob::Blob;
+pub use crate::borrowed::DecodeBorrowed;
+pub use crate::cached_size::CachedSize;
+#[cfg(feature = "std-types")]
+pub use crate::common_types::{IpAddress, SocketAddress};
+pub use crate::decode_config::{
+    DecodeConfig, DuplicateFieldPolicy, InvalidUtf8Policy, RequiredFieldPolicy,
+    ReservedWireTypePolicy, UnknownFieldPolicy,
+};
+pub use crate::dyn_message::DynMessage;
+pub use crate::encoding::{BytesAdapter, BytesCodec, MapContainer, WireType};
+pub use crate::error::{DecodeError, DecodeErrorKind, EncodeError};
+pub use crate::lossy_string::LossyString;
+pub use crate::message::{DecodeMessage, EncodeMessage, Message};
+pub use crate::open_enum::OpenEnum;
+pub use crate::partial_decoder::{DecodeProgress, PartialDecoder};
+pub use crate::reflect::{ReflectMessage, ReflectOneof};
+pub use crate::sink::{EncodeSink, HasherSink};
+pub use crate::stream_decoder::MessageStreamDecoder;
+#[cfg(feature = "std")]
+pub use crate::stream_decoder::MessageStreamReader;
+#[cfg(feature = "tokio")]
+pub use crate::tokio_codec::{AsyncMessageExt, MessageCodec};
+pub use crate::unknown_field_set::{UnknownField, UnknownFieldData, UnknownFieldSet};
+
+use alloc::format;
 
 use bytes::{Buf, BufMut};
 
-use crate::encoding::{decode_varint, encode_varint, encoded_len_varint};
+use crate::encoding::{decode_varint, encode_varint};
+
+pub use crate::encoding::encoded_len_varint;
 
-// See `encoding::DecodeContext` for more info.
+// See `DecodeConfig::recursion_limit` for more info.
 // 100 is the default recursion limit in the C++ implementation.
-#[cfg(not(feature = "no-recursion-limit"))]
 const RECURSION_LIMIT: u32 = 100;
 
 /// Encodes a length delimiter to the buffer.
@@ -48,10 +89,51 @@ where
     Ok(())
 }
 
+/// Encodes a length delimiter to the buffer, failing if the length exceeds `limit`.
+///
+/// The encode-side counterpart of [`decode_length_delimiter_with_limit`]: a frame which the
+/// receiver would reject is refused before anything is written. The errors are otherwise the
+/// same as those of [`encode_length_delimiter`].
+///
+/// # Example
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// let error = prost::encode_length_delimiter_with_limit(5 << 20, 4 << 20, &mut buf).unwrap_err();
+/// assert_eq!(error.limit(), Some(4 << 20));
+/// assert_eq!(error.required_capacity(), 5 << 20);
+/// assert!(buf.is_empty());
+///
+/// prost::encode_length_delimiter_with_limit(5 << 20, 8 << 20, &mut buf).unwrap();
+/// assert_eq!(prost::decode_length_delimiter(&buf[..]).unwrap(), 5 << 20);
+/// ```
+pub fn encode_length_delimiter_with_limit<B>(
+    length: usize,
+    limit: usize,
+    buf: &mut B,
+) -> Result<(), EncodeError>
+where
+    B: BufMut,
+{
+    if length > limit {
+        return Err(EncodeError::length_limit(
+            length,
+            limit,
+            buf.remaining_mut(),
+        ));
+    }
+    encode_length_delimiter(length, buf)
+}
+
 /// Returns the encoded length of a length delimiter.
 ///
 /// Applications may use this method to ensure sufficient buffer capacity before calling
 /// `encode_length_delimiter`. The returned size will be between 1 and 10, inclusive.
+///
+/// The size of a length-delimited message, as written by
+/// [`EncodeMessage::encode_length_delimited`], is `length_delimiter_len(len) + len`, where `len` is
+/// the [`EncodeMessage::encoded_len`] of the message, so that it can be checked against a frame
+/// budget before the message is encoded.
 pub fn length_delimiter_len(length: usize) -> usize {
     encoded_len_varint(length as u64)
 }
@@ -73,13 +155,43 @@ where
 {
     let length = decode_varint(&mut buf)?;
     if length > usize::max_value() as u64 {
-        return Err(DecodeError::new(
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
             "length delimiter exceeds maximum usize value",
         ));
     }
     Ok(length as usize)
 }
 
+/// Decodes a length delimiter from the buffer, failing if the length exceeds `limit`.
+///
+/// Applications enforcing a frame budget, such as the 4 MiB default maximum message size of gRPC,
+/// can reject an oversized message from its delimiter alone, before buffering the message. The
+/// errors are otherwise the same as those of [`decode_length_delimiter`].
+///
+/// # Example
+///
+/// ```rust
+/// let mut buf = Vec::new();
+/// prost::encode_length_delimiter(5 << 20, &mut buf).unwrap();
+///
+/// assert!(prost::decode_length_delimiter_with_limit(&buf[..], 4 << 20).is_err());
+/// assert_eq!(prost::decode_length_delimiter_with_limit(&buf[..], 8 << 20).unwrap(), 5 << 20);
+/// ```
+pub fn decode_length_delimiter_with_limit<B>(buf: B, limit: usize) -> Result<usize, DecodeError>
+where
+    B: Buf,
+{
+    let length = decode_length_delimiter(buf)?;
+    if length > limit {
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
+            format!("length delimiter exceeds limit: {} > {}", length, limit),
+        ));
+    }
+    Ok(length)
+}
+
 // Re-export #[derive(Message, Enumeration, Oneof)].
 // Based on serde's equivalent re-export [1], but enabled by default.
 //
@@ -0,0 +1,180 @@
+//! A Protobuf `string` which tolerates invalid UTF-8.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+
+use ::bytes::{Buf, BufMut};
+
+use crate::encoding::BytesAdapter;
+
+/// The value of a Protobuf `string` field, stored as raw bytes.
+///
+/// Decoding a `String` field fails the whole message if the field isn't valid UTF-8.
+/// `LossyString` accepts any bytes instead, and re-encodes them unchanged, so messages from
+/// sources which routinely produce invalid UTF-8 can still be decoded and passed through. The
+/// text is available with [`LossyString::to_string_lossy`], or with [`LossyString::to_str`] when
+/// it must be valid.
+///
+/// `prost-build` generates `LossyString` fields with `Config::lossy_string`.
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LossyString {
+    bytes: Vec<u8>,
+}
+
+impl LossyString {
+    /// Creates an empty `LossyString`.
+    pub const fn new() -> LossyString {
+        LossyString { bytes: Vec::new() }
+    }
+
+    /// Returns the raw bytes of the string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Converts the string into its raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the string if it is valid UTF-8.
+    pub fn to_str(&self) -> Result<&str, str::Utf8Error> {
+        str::from_utf8(&self.bytes)
+    }
+
+    /// Returns the string, replacing invalid UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+
+    /// Returns `true` if the string is valid UTF-8.
+    pub fn is_utf8(&self) -> bool {
+        self.to_str().is_ok()
+    }
+
+    /// Returns the length of the string in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns `true` if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl fmt::Debug for LossyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl AsRef<[u8]> for LossyString {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<String> for LossyString {
+    fn from(value: String) -> LossyString {
+        LossyString {
+            bytes: value.into_bytes(),
+        }
+    }
+}
+
+impl From<&str> for LossyString {
+    fn from(value: &str) -> LossyString {
+        LossyString {
+            bytes: value.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for LossyString {
+    fn from(bytes: Vec<u8>) -> LossyString {
+        LossyString { bytes }
+    }
+}
+
+impl From<&[u8]> for LossyString {
+    fn from(bytes: &[u8]) -> LossyString {
+        LossyString {
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+impl BytesAdapter for LossyString {
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn replace_with<B>(&mut self, buf: B)
+    where
+        B: Buf,
+    {
+        self.bytes.replace_with(buf)
+    }
+
+    fn append_to<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        self.bytes.append_to(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::format;
+
+    use super::*;
+
+    use crate::encoding::{bytes, string, DecodeContext, WireType};
+
+    #[test]
+    fn invalid_utf8_roundtrip() {
+        let raw = b"caf\xe9".to_vec();
+        let mut buf = Vec::new();
+        bytes::encode(1, &raw, &mut buf);
+
+        // Decoding as a `String` fails.
+        let mut value = String::new();
+        assert!(string::merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut &buf[1..],
+            DecodeContext::default()
+        )
+        .is_err());
+
+        // Decoding as a `LossyString` succeeds, and re-encodes the original bytes.
+        let mut value = LossyString::new();
+        bytes::merge(
+            WireType::LengthDelimited,
+            &mut value,
+            &mut &buf[1..],
+            DecodeContext::default(),
+        )
+        .unwrap();
+        assert_eq!(value.as_bytes(), &raw[..]);
+        assert_eq!(value.to_string_lossy(), "caf\u{FFFD}");
+        assert!(value.to_str().is_err());
+
+        let mut reencoded = Vec::new();
+        bytes::encode(1, &value, &mut reencoded);
+        assert_eq!(reencoded, buf);
+    }
+
+    #[test]
+    fn valid_utf8() {
+        let value = LossyString::from("hello");
+        assert_eq!(value.to_str(), Ok("hello"));
+        assert!(value.is_utf8());
+        assert_eq!(format!("{:?}", value), "\"hello\"");
+    }
+}
@@ -1,17 +1,52 @@
 use alloc::boxed::Box;
 use core::fmt::Debug;
+use core::hash::Hasher;
 use core::usize;
 
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::encoding::{
-    decode_key, encode_varint, encoded_len_varint, message, DecodeContext, WireType,
+    self, encode_varint, encoded_len_varint, message, DecodeContext, SeenFields, WireType,
 };
+use crate::sink::SinkBuf;
+#[cfg(feature = "std")]
+use crate::sink::WriterSink;
+#[cfg(feature = "std")]
+use crate::stream_decoder::LengthDelimiter;
+use crate::DecodeConfig;
 use crate::DecodeError;
 use crate::EncodeError;
+use crate::EncodeSink;
+use crate::HasherSink;
 
 /// A Protocol Buffers message.
-pub trait Message: Debug + Send + Sync {
+///
+/// `Message` combines [`EncodeMessage`] and [`DecodeMessage`], the encoding and decoding halves
+/// of a message, which declare its methods, and is implemented for every type implementing both.
+/// Types which are only ever encoded, such as write-only telemetry records, or only ever decoded
+/// can derive `EncodeMessage` or `DecodeMessage` instead of `Message`.
+///
+/// Calling the methods of a concrete message requires the half declaring them to be in scope, so
+/// code which imported only `Message` imports the halves as well:
+///
+/// ```rust
+/// use prost::{DecodeMessage, EncodeMessage};
+///
+/// let mut buf = Vec::new();
+/// String::from("hello").encode(&mut buf).unwrap();
+/// assert_eq!(String::decode(&buf[..]).unwrap(), "hello");
+/// ```
+///
+/// Generic code bounded by `Message` can call the methods of both halves.
+pub trait Message: EncodeMessage + DecodeMessage {}
+
+impl<M> Message for M where M: EncodeMessage + DecodeMessage {}
+
+/// The encoding half of a Protocol Buffers [`Message`].
+///
+/// Implemented by `#[derive(Message)]`, and by `#[derive(EncodeMessage)]` for types which are
+/// only ever encoded.
+pub trait EncodeMessage: Debug + Send + Sync {
     /// Encodes the message to a buffer.
     ///
     /// This method will panic if the buffer has insufficient capacity.
@@ -23,24 +58,49 @@ pub trait Message: Debug + Send + Sync {
         B: BufMut,
         Self: Sized;
 
-    /// Decodes a field from a buffer, and merges it into `self`.
+    /// Encodes the message to a buffer deterministically, as described by
+    /// [`encode_deterministic`](EncodeMessage::encode_deterministic).
+    ///
+    /// The default implementation calls `encode_raw`, which is deterministic for messages without
+    /// `HashMap` fields. `#[derive(Message)]` implements it in terms of the deterministic encoding
+    /// of the fields.
+    ///
+    /// This method will panic if the buffer has insufficient capacity.
     ///
     /// Meant to be used only by `Message` implementations.
     #[doc(hidden)]
-    fn merge_field<B>(
-        &mut self,
-        tag: u32,
-        wire_type: WireType,
-        buf: &mut B,
-        ctx: DecodeContext,
-    ) -> Result<(), DecodeError>
+    fn encode_raw_deterministic<B>(&self, buf: &mut B)
     where
-        B: Buf,
-        Self: Sized;
+        B: BufMut,
+        Self: Sized,
+    {
+        self.encode_raw(buf)
+    }
 
     /// Returns the encoded length of the message without a length delimiter.
     fn encoded_len(&self) -> usize;
 
+    /// Returns the encoded length cached by the last call to `encoded_len`, or computes it if the
+    /// message doesn't cache it.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn cached_encoded_len(&self) -> usize {
+        self.encoded_len()
+    }
+
+    /// Returns `true` if the message caches its encoded length in a
+    /// [`CachedSize`](crate::CachedSize) field.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn has_cached_size() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
     /// Encodes the message to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
@@ -59,6 +119,39 @@ pub trait Message: Debug + Send + Sync {
         Ok(())
     }
 
+    /// Encodes the message to a buffer deterministically: the same message is always encoded to
+    /// the same bytes, for use in signatures, cache keys and content-addressed storage.
+    ///
+    /// The entries of map fields are encoded in key order, including those of `HashMap` fields,
+    /// which [`encode`](EncodeMessage::encode) encodes in their unspecified iteration order; custom
+    /// [`MapContainer`](crate::MapContainer)s are encoded in their iteration order, so the
+    /// encoding is only deterministic if that order is. Varints are always encoded minimally,
+    /// and unknown fields kept in an [`UnknownFieldSet`](crate::UnknownFieldSet) in their stored
+    /// order.
+    ///
+    /// Hand-written messages encode their fields deterministically by implementing
+    /// `encode_raw_deterministic`, e.g. with the `encode_sorted` functions of
+    /// `prost::encoding::hash_map`; otherwise they are encoded as by `encode`.
+    ///
+    /// The encoding is deterministic for a given build of the message types only: it isn't
+    /// canonical across languages or schema versions.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_deterministic<B>(&self, buf: &mut B) -> Result<(), EncodeError>
+    where
+        B: BufMut,
+        Self: Sized,
+    {
+        let required = self.encoded_len();
+        let remaining = buf.remaining_mut();
+        if required > remaining {
+            return Err(EncodeError::new(required, remaining));
+        }
+
+        self.encode_raw_deterministic(buf);
+        Ok(())
+    }
+
     #[cfg(feature = "std")]
     /// Encodes the message to a newly allocated buffer.
     fn encode_to_vec(&self) -> Vec<u8>
@@ -71,6 +164,107 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    /// Encodes the message to a newly allocated `Bytes`, for APIs taking `Bytes`, such as those of
+    /// `hyper` and `tonic`, without copying the encoding.
+    fn encode_to_bytes(&self) -> Bytes
+    where
+        Self: Sized,
+    {
+        let mut buf = BytesMut::with_capacity(self.encoded_len());
+
+        self.encode_raw(&mut buf);
+        buf.freeze()
+    }
+
+    #[cfg(feature = "std")]
+    /// Encodes the message to a newly allocated buffer with the given capacity.
+    ///
+    /// [`encode_to_vec`](EncodeMessage::encode_to_vec) computes the encoded length of the message
+    /// to allocate exactly once; callers which already know the length, or an upper bound of it,
+    /// can skip that traversal. A capacity which is too small only costs reallocations. Messages
+    /// with a [`CachedSize`](crate::CachedSize) are still sized, to refresh the cache.
+    fn encode_to_vec_with_capacity(&self, capacity: usize) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::with_capacity(capacity);
+
+        if Self::has_cached_size() {
+            self.encoded_len();
+        }
+        self.encode_raw(&mut buf);
+        buf
+    }
+
+    /// Encodes the message into a sink, such as a hasher, without materializing the encoding.
+    ///
+    /// The sink sees the same bytes as [`encode`](EncodeMessage::encode) would write to a buffer,
+    /// split into chunks of unspecified size.
+    fn encode_to_sink<S>(&self, sink: S)
+    where
+        S: EncodeSink,
+        Self: Sized,
+    {
+        if Self::has_cached_size() {
+            self.encoded_len();
+        }
+        let mut buf = SinkBuf::new(sink);
+        self.encode_raw(&mut buf);
+        buf.flush();
+    }
+
+    /// Feeds the deterministic encoding of the message to a hasher, without materializing it, to
+    /// compute a fingerprint for deduplication or cache keys.
+    ///
+    /// Messages which are equal, as encoded by
+    /// [`encode_deterministic`](EncodeMessage::encode_deterministic), feed the same bytes to the
+    /// hasher in the same chunks, so their fingerprints are equal for any hasher. The fingerprint
+    /// of a hasher which doesn't depend on how its input is split into writes, such as
+    /// `DefaultHasher` or a cryptographic digest, is that of the encoding hashed at once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::Hasher;
+    ///
+    /// use prost::EncodeMessage;
+    ///
+    /// let mut hasher = DefaultHasher::new();
+    /// String::from("hello").fingerprint(&mut hasher);
+    /// let fingerprint = hasher.finish();
+    /// # let _ = fingerprint;
+    /// ```
+    fn fingerprint<H>(&self, hasher: &mut H)
+    where
+        H: Hasher,
+        Self: Sized,
+    {
+        if Self::has_cached_size() {
+            self.encoded_len();
+        }
+        let mut buf = SinkBuf::new(HasherSink(hasher));
+        self.encode_raw_deterministic(&mut buf);
+        buf.flush();
+    }
+
+    #[cfg(feature = "std")]
+    /// Encodes the message to a writer, such as a file or a socket.
+    ///
+    /// The encoding is staged in a small buffer and written in chunks, rather than materialized
+    /// in full, so there is no need to wrap the writer in a `BufWriter`.
+    fn encode_to_writer<W>(&self, writer: W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+        Self: Sized,
+    {
+        let mut sink = WriterSink::new(writer, self.encoded_len());
+        let mut buf = SinkBuf::new(&mut sink);
+        self.encode_raw(&mut buf);
+        buf.flush();
+        sink.finish()
+    }
+
     /// Encodes the message with a length-delimiter to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
@@ -104,6 +298,107 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    #[cfg(feature = "std")]
+    /// Encodes the message with a length-delimiter to a writer, such as a file or a socket.
+    ///
+    /// The encoding is written in chunks, as by
+    /// [`encode_to_writer`](EncodeMessage::encode_to_writer).
+    fn encode_length_delimited_to_writer<W>(&self, writer: W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+        Self: Sized,
+    {
+        let len = self.encoded_len();
+        let mut sink = WriterSink::new(writer, len + encoded_len_varint(len as u64));
+        let mut buf = SinkBuf::new(&mut sink);
+        encode_varint(len as u64, &mut buf);
+        self.encode_raw(&mut buf);
+        buf.flush();
+        sink.finish()
+    }
+}
+
+/// The decoding half of a Protocol Buffers [`Message`].
+///
+/// Implemented by `#[derive(Message)]`, and by `#[derive(DecodeMessage)]` for types which are
+/// only ever decoded.
+pub trait DecodeMessage: Debug + Send + Sync {
+    /// Decodes a field from a buffer, and merges it into `self`.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized;
+
+    /// Replaces the message with the fields decoded from a buffer, up to `limit` remaining bytes.
+    ///
+    /// The elements of repeated message fields are cleared and reused instead of being dropped.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn replace_from<B>(
+        &mut self,
+        buf: &mut B,
+        limit: usize,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        self.clear();
+        while buf.remaining() > limit {
+            if let Some((tag, wire_type)) = ctx.decode_key(buf)? {
+                self.merge_field(tag, wire_type, buf, ctx.clone())?;
+            }
+        }
+        encoding::check_limit(buf, limit)
+    }
+
+    /// Returns `true` if `tag` is the tag of a non-repeated field of the message, including the
+    /// members of a `oneof`.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn is_singular_field(_tag: u32) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
+    /// Returns the identifier of the non-repeated field with tag `tag` when checking for
+    /// duplicate fields: the tag itself, except for the members of a `oneof`, which share an
+    /// identifier which isn't a valid tag, so that any two of them are duplicates.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn singular_field_id(tag: u32) -> u32
+    where
+        Self: Sized,
+    {
+        tag
+    }
+
+    /// Calls `f` with the tag and name of each proto2 `required` field of the message, including
+    /// those of flattened messages.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn for_each_required_field(_f: &mut dyn FnMut(u32, &'static str))
+    where
+        Self: Sized,
+    {
+    }
+
     /// Decodes an instance of the message from a buffer.
     ///
     /// The entire buffer will be consumed.
@@ -116,6 +411,36 @@ pub trait Message: Debug + Send + Sync {
         Self::merge(&mut message, &mut buf).map(|_| message)
     }
 
+    /// Decodes an instance of the message from a buffer, with the given decode options.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_config<B>(mut buf: B, config: DecodeConfig) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+        Self: Default,
+    {
+        let mut message = Self::default();
+        Self::merge_with_config(&mut message, &mut buf, config).map(|_| message)
+    }
+
+    /// Decodes an instance of the message from a buffer into `self`, replacing its contents.
+    ///
+    /// The result is the same as decoding a new message, but the allocations of `self` are reused
+    /// where possible: in particular, the elements of repeated message fields are cleared and
+    /// decoded into, rather than dropped and allocated again. This makes decoding a stream of
+    /// similar messages with many repeated elements cheaper.
+    ///
+    /// The entire buffer will be consumed. If decoding fails, the contents of `self` are
+    /// unspecified.
+    fn decode_into<B>(&mut self, mut buf: B) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        self.replace_from(&mut buf, 0, DecodeContext::default())
+            .map_err(DecodeError::traced)
+    }
+
     /// Decodes a length-delimited instance of the message from the buffer.
     fn decode_length_delimited<B>(buf: B) -> Result<Self, DecodeError>
     where
@@ -127,20 +452,133 @@ pub trait Message: Debug + Send + Sync {
         Ok(message)
     }
 
+    /// Decodes a length-delimited instance of the message from the front of the buffer, and returns
+    /// it with the number of bytes consumed, including the length-delimiter.
+    ///
+    /// Unlike [`decode_length_delimited`](DecodeMessage::decode_length_delimited), the buffer may
+    /// hold further bytes after the message, such as the next record of a concatenated stream,
+    /// which start at the returned offset. Since an encoded message doesn't record its own length,
+    /// the message must be length-delimited, as written by
+    /// [`encode_length_delimited`](EncodeMessage::encode_length_delimited).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use prost::{DecodeMessage, EncodeMessage};
+    ///
+    /// let mut records = Vec::new();
+    /// for value in &["first", "second"] {
+    ///     value.to_string().encode_length_delimited(&mut records).unwrap();
+    /// }
+    ///
+    /// let (first, len) = String::decode_partial(&records[..]).unwrap();
+    /// assert_eq!((first.as_str(), len), ("first", 8));
+    /// let (second, _) = String::decode_partial(&records[len..]).unwrap();
+    /// assert_eq!(second, "second");
+    /// ```
+    fn decode_partial<B>(mut buf: B) -> Result<(Self, usize), DecodeError>
+    where
+        B: Buf,
+        Self: Default,
+    {
+        let remaining = buf.remaining();
+        let mut message = Self::default();
+        message.merge_length_delimited(&mut buf)?;
+        Ok((message, remaining - buf.remaining()))
+    }
+
+    #[cfg(feature = "std")]
+    /// Decodes an instance of the message from a reader, such as a file or a socket.
+    ///
+    /// The reader is read to its end.
+    fn decode_from_reader<R>(mut reader: R) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+        Self: Default,
+    {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(Self::decode(&*buf)?)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decodes a single length-delimited instance of the message from a reader.
+    ///
+    /// Exactly the bytes of the message and its length-delimiter are read, so further messages can
+    /// be read from the same reader. Returns `None` if the reader is at its end, and an error of
+    /// kind `UnexpectedEof` if it ends in the middle of the message.
+    ///
+    /// The length of the message isn't limited: to read from an untrusted source, use
+    /// `decode_delimited_from_reader_with_config` with a `max_message_size`.
+    fn decode_delimited_from_reader<R>(reader: R) -> std::io::Result<Option<Self>>
+    where
+        R: std::io::Read,
+        Self: Default,
+    {
+        Self::decode_delimited_from_reader_with_config(reader, DecodeConfig::default())
+    }
+
+    #[cfg(feature = "std")]
+    /// Decodes a single length-delimited instance of the message from a reader, with the given
+    /// decode options.
+    ///
+    /// A message longer than the `max_message_size` of the options is rejected with an error of
+    /// kind `InvalidData` as soon as its length-delimiter is read, before any of its bytes are.
+    fn decode_delimited_from_reader_with_config<R>(
+        mut reader: R,
+        config: DecodeConfig,
+    ) -> std::io::Result<Option<Self>>
+    where
+        R: std::io::Read,
+        Self: Default,
+    {
+        use std::io::{ErrorKind, Read};
+
+        let mut delimiter = LengthDelimiter::default();
+        let len = loop {
+            let mut byte = [0u8];
+            if let Err(error) = reader.read_exact(&mut byte) {
+                if delimiter.is_empty() && error.kind() == ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                }
+                return Err(error);
+            }
+            if let Some(len) = delimiter
+                .push(byte[0], &config)
+                .map_err(DecodeError::traced)?
+            {
+                break len;
+            }
+        };
+        let mut buf = Vec::new();
+        reader.by_ref().take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(Some(Self::decode_with_config(&*buf, config)?))
+    }
+
     /// Decodes an instance of the message from a buffer, and merges it into `self`.
     ///
     /// The entire buffer will be consumed.
-    fn merge<B>(&mut self, mut buf: B) -> Result<(), DecodeError>
+    fn merge<B>(&mut self, buf: B) -> Result<(), DecodeError>
     where
         B: Buf,
         Self: Sized,
     {
-        let ctx = DecodeContext::default();
-        while buf.has_remaining() {
-            let (tag, wire_type) = decode_key(&mut buf)?;
-            self.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
-        }
-        Ok(())
+        self.merge_with_config(buf, DecodeConfig::default())
+    }
+
+    /// Decodes an instance of the message from a buffer with the given decode options, and merges
+    /// it into `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_with_config<B>(&mut self, buf: B, config: DecodeConfig) -> Result<(), DecodeError>
+    where
+        B: Buf,
+        Self: Sized,
+    {
+        merge_fields(self, buf, config).map_err(DecodeError::traced)
     }
 
     /// Decodes a length-delimited instance of the message from buffer, and
@@ -156,15 +594,34 @@ pub trait Message: Debug + Send + Sync {
             &mut buf,
             DecodeContext::default(),
         )
+        .map_err(DecodeError::traced)
     }
 
     /// Clears the message, resetting all fields to their default.
     fn clear(&mut self);
 }
 
-impl<M> Message for Box<M>
+/// Decodes the fields of a message from a buffer, and merges them into `message`.
+fn merge_fields<M, B>(message: &mut M, mut buf: B, config: DecodeConfig) -> Result<(), DecodeError>
 where
-    M: Message,
+    M: DecodeMessage,
+    B: Buf,
+{
+    config.check_message_size(buf.remaining() as u64)?;
+    let ctx = DecodeContext::with_config(config);
+    let mut seen = SeenFields::new(&ctx);
+    while buf.has_remaining() {
+        if let Some((tag, wire_type)) = ctx.decode_key(&mut buf)? {
+            seen.insert::<M>(tag)?;
+            message.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
+        }
+    }
+    seen.check_required::<M>()
+}
+
+impl<M> EncodeMessage for Box<M>
+where
+    M: EncodeMessage,
 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
@@ -172,6 +629,28 @@ where
     {
         (**self).encode_raw(buf)
     }
+
+    fn encode_raw_deterministic<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        (**self).encode_raw_deterministic(buf)
+    }
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+    fn cached_encoded_len(&self) -> usize {
+        (**self).cached_encoded_len()
+    }
+    fn has_cached_size() -> bool {
+        M::has_cached_size()
+    }
+}
+
+impl<M> DecodeMessage for Box<M>
+where
+    M: DecodeMessage,
+{
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -184,8 +663,25 @@ where
     {
         (**self).merge_field(tag, wire_type, buf, ctx)
     }
-    fn encoded_len(&self) -> usize {
-        (**self).encoded_len()
+    fn replace_from<B>(
+        &mut self,
+        buf: &mut B,
+        limit: usize,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        (**self).replace_from(buf, limit, ctx)
+    }
+    fn is_singular_field(tag: u32) -> bool {
+        M::is_singular_field(tag)
+    }
+    fn singular_field_id(tag: u32) -> u32 {
+        M::singular_field_id(tag)
+    }
+    fn for_each_required_field(f: &mut dyn FnMut(u32, &'static str)) {
+        M::for_each_required_field(f)
     }
     fn clear(&mut self) {
         (**self).clear()
@@ -194,7 +690,96 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use bytes::Bytes;
+
+    use super::{DecodeMessage, EncodeMessage, Message};
 
     const _MESSAGE_IS_OBJECT_SAFE: Option<&dyn Message> = None;
+    const _ENCODE_MESSAGE_IS_OBJECT_SAFE: Option<&dyn EncodeMessage> = None;
+    const _DECODE_MESSAGE_IS_OBJECT_SAFE: Option<&dyn DecodeMessage> = None;
+
+    #[test]
+    fn encode_to_bytes() {
+        let value = "a".repeat(200);
+        let mut buf = Vec::new();
+        value.encode(&mut buf).unwrap();
+        assert_eq!(value.encode_to_bytes(), buf);
+        assert_eq!(().encode_to_bytes(), Bytes::new());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn encode_to_vec_with_capacity() {
+        let value = "a".repeat(200);
+        let len = value.encoded_len();
+        assert_eq!(
+            value.encode_to_vec_with_capacity(len),
+            value.encode_to_vec()
+        );
+        assert_eq!(value.encode_to_vec_with_capacity(len).capacity(), len);
+        assert_eq!(value.encode_to_vec_with_capacity(0), value.encode_to_vec());
+    }
+
+    #[test]
+    fn decode_partial() {
+        let mut buf = Vec::new();
+        String::from("a").encode_length_delimited(&mut buf).unwrap();
+        "b".repeat(200).encode_length_delimited(&mut buf).unwrap();
+
+        let (first, len) = String::decode_partial(&buf[..]).unwrap();
+        assert_eq!((first.as_str(), len), ("a", 4));
+        let (second, len) = String::decode_partial(&buf[4..]).unwrap();
+        assert_eq!((second, len), ("b".repeat(200), buf.len() - 4));
+
+        assert!(String::decode_partial(&buf[..3]).is_err());
+        assert!(String::decode_partial(&[][..]).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn decode_from_reader() {
+        use std::io::ErrorKind;
+
+        use crate::DecodeConfig;
+
+        let values = [String::from("a"), String::new(), "b".repeat(200)];
+        let mut stream = Vec::new();
+        for value in &values {
+            value
+                .encode_length_delimited_to_writer(&mut stream)
+                .unwrap();
+        }
+        let mut reader = &stream[..];
+        for value in &values {
+            let decoded = String::decode_delimited_from_reader(&mut reader).unwrap();
+            assert_eq!(decoded.as_ref(), Some(value));
+        }
+        assert_eq!(
+            String::decode_delimited_from_reader(&mut reader).unwrap(),
+            None
+        );
+
+        let err = String::decode_delimited_from_reader(&stream[..2]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = String::decode_delimited_from_reader(&[0xff; 11][..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // The limit is checked before the message is read.
+        let config = DecodeConfig::new().max_message_size(200);
+        let mut reader = &stream[..];
+        let decoded = String::decode_delimited_from_reader_with_config(&mut reader, config);
+        assert_eq!(decoded.unwrap().as_deref(), Some("a"));
+        let decoded = String::decode_delimited_from_reader_with_config(&mut reader, config);
+        assert_eq!(decoded.unwrap().as_deref(), Some(""));
+        let err =
+            String::decode_delimited_from_reader_with_config(&mut reader, config).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(reader.len(), 203);
+
+        let decoded = String::decode_from_reader(&stream[1..4]).unwrap();
+        assert_eq!(decoded, "a");
+    }
 }
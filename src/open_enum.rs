@@ -0,0 +1,97 @@
+//! The value of an open enum field.
+
+/// The value of a Protobuf enum field, which may not be one of the values of the enum.
+///
+/// Proto3 enums are open: a field may hold a value added to the enum by a newer schema, and the
+/// value must survive decoding and re-encoding. Generated messages store enum fields as `i32`, so
+/// the value is kept, but the accessor methods of an enum field fall back to the default value of
+/// the enum when the value is unknown. With `prost_build::Config::open_enums`, or the `open`
+/// field attribute, the accessors use `OpenEnum` instead, so unknown values can be told apart
+/// from the default, and set:
+///
+/// ```rust
+/// # #[cfg(feature = "prost-derive")]
+/// # fn main() {
+/// use prost::{Enumeration, Message, OpenEnum};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+/// enum Color {
+///     Red = 0,
+///     Blue = 1,
+/// }
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// struct Pixel {
+///     #[prost(enumeration = "Color", open, tag = "1")]
+///     color: i32,
+/// }
+///
+/// let mut pixel = Pixel::default();
+/// assert_eq!(pixel.color(), OpenEnum::Known(Color::Red));
+/// pixel.set_color(OpenEnum::Unknown(7));
+/// assert_eq!(pixel.color(), OpenEnum::Unknown(7));
+/// pixel.set_color(Color::Blue);
+/// assert_eq!(pixel.color, 1);
+/// # }
+/// # #[cfg(not(feature = "prost-derive"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OpenEnum<E> {
+    /// A value of the enum.
+    Known(E),
+    /// A value which isn't a value of the enum.
+    Unknown(i32),
+}
+
+impl<E> OpenEnum<E> {
+    /// Returns the enum value, or `None` if the value is unknown.
+    pub fn known(self) -> Option<E> {
+        match self {
+            OpenEnum::Known(value) => Some(value),
+            OpenEnum::Unknown(_) => None,
+        }
+    }
+
+    /// Returns `true` if the value is a value of the enum.
+    pub fn is_known(&self) -> bool {
+        matches!(*self, OpenEnum::Known(_))
+    }
+}
+
+impl<E> OpenEnum<E>
+where
+    E: Into<i32>,
+{
+    /// Returns the value as stored in the field.
+    pub fn to_i32(self) -> i32 {
+        match self {
+            OpenEnum::Known(value) => value.into(),
+            OpenEnum::Unknown(value) => value,
+        }
+    }
+}
+
+impl<E> Default for OpenEnum<E>
+where
+    E: Default,
+{
+    fn default() -> OpenEnum<E> {
+        OpenEnum::Known(E::default())
+    }
+}
+
+impl<E> From<E> for OpenEnum<E> {
+    fn from(value: E) -> OpenEnum<E> {
+        OpenEnum::Known(value)
+    }
+}
+
+impl<E> From<OpenEnum<E>> for i32
+where
+    E: Into<i32>,
+{
+    fn from(value: OpenEnum<E>) -> i32 {
+        value.to_i32()
+    }
+}
@@ -0,0 +1,354 @@
+//! Decoding of messages received in several chunks.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::encoding::{decode_varint, DecodeContext, SeenFields, WireType};
+use crate::{DecodeConfig, DecodeError, DecodeErrorKind, DecodeMessage};
+
+/// The state of a [`PartialDecoder`] after a chunk has been pushed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeProgress {
+    /// The bytes pushed so far consist of complete fields, which have all been decoded. The
+    /// message may be complete, or more fields may follow.
+    Ready,
+    /// The last field is incomplete, and needs at least this many more bytes.
+    ///
+    /// The count is a lower bound: the length of some fields, such as varints and groups, is only
+    /// known once their last byte has been received.
+    NeedMoreData(usize),
+}
+
+/// A decoder for a message received in several chunks, such as successive socket reads.
+///
+/// Each complete field is decoded as soon as its last byte is pushed, and only the bytes of the
+/// last, incomplete field are buffered, so the encoding of the message never has to be
+/// assembled in memory. A group is only decoded once its end has been pushed, so all of its bytes
+/// are buffered until then. Since an encoded message doesn't record its own length, the caller
+/// decides when the message ends, and calls [`finish`](PartialDecoder::finish).
+///
+/// The size limits of the decode options are checked as the bytes are pushed: a length-delimited
+/// value longer than `max_field_size` is rejected as soon as its length is known, and the message
+/// once the bytes pushed, or the known length of its last field, exceed `max_message_size`.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::{DecodeProgress, EncodeMessage, PartialDecoder};
+///
+/// let mut buf = Vec::new();
+/// String::from("hello").encode(&mut buf).unwrap();
+///
+/// let mut decoder = PartialDecoder::<String>::new();
+/// assert_eq!(decoder.push(&buf[..3]).unwrap(), DecodeProgress::NeedMoreData(4));
+/// assert_eq!(decoder.push(&buf[3..]).unwrap(), DecodeProgress::Ready);
+/// assert_eq!(decoder.finish().unwrap(), "hello");
+/// ```
+#[derive(Debug)]
+pub struct PartialDecoder<M> {
+    message: M,
+    /// The bytes of the last, incomplete field.
+    pending: Vec<u8>,
+    /// The length of the complete elements at the start of `pending`, which are the key of a group
+    /// and the fields within it.
+    scanned: usize,
+    /// The tags of the open groups, with the context of their fields.
+    groups: Vec<(u32, DecodeContext)>,
+    /// The number of bytes of the message which have been decoded.
+    decoded: usize,
+    /// The number of bytes the last field needs at least to be complete.
+    needed: usize,
+    config: DecodeConfig,
+    seen: SeenFields,
+}
+
+impl<M> PartialDecoder<M>
+where
+    M: DecodeMessage + Default,
+{
+    /// Creates a decoder with the default decode configuration.
+    pub fn new() -> PartialDecoder<M> {
+        PartialDecoder::with_config(DecodeConfig::default())
+    }
+
+    /// Creates a decoder with the given decode options.
+    pub fn with_config(config: DecodeConfig) -> PartialDecoder<M> {
+        PartialDecoder {
+            message: M::default(),
+            pending: Vec::new(),
+            scanned: 0,
+            groups: Vec::new(),
+            decoded: 0,
+            needed: 0,
+            config,
+            seen: SeenFields::new(&DecodeContext::with_config(config)),
+        }
+    }
+
+    /// Decodes the fields completed by the next chunk of the message.
+    ///
+    /// After an error, the state of the decoder is unspecified, and it should be dropped.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<DecodeProgress, DecodeError> {
+        self.push_fields(chunk).map_err(DecodeError::traced)
+    }
+
+    fn push_fields(&mut self, chunk: &[u8]) -> Result<DecodeProgress, DecodeError> {
+        self.pending.extend_from_slice(chunk);
+        let size = self.decoded as u64 + self.pending.len() as u64;
+        self.config.check_message_size(size)?;
+
+        let ctx = DecodeContext::with_config(self.config);
+        let mut offset = 0;
+        self.needed = loop {
+            if self.scanned == self.pending.len() {
+                break if self.groups.is_empty() { 0 } else { 1 };
+            }
+            let inner = self.groups.last().map_or(&ctx, |(_, ctx)| ctx);
+            let (len, key) = match scan_element(&self.pending[self.scanned..], inner)? {
+                Scan::Complete(len, key) => (len, key),
+                Scan::Incomplete(needed) => {
+                    self.config.check_message_size(size + needed as u64)?;
+                    break needed;
+                }
+            };
+            self.scanned += len;
+
+            match key {
+                Some((tag, WireType::StartGroup)) => {
+                    inner.limit_reached()?;
+                    let inner = inner.enter_recursion();
+                    self.groups.push((tag, inner));
+                }
+                Some((tag, WireType::EndGroup)) => match self.groups.pop() {
+                    Some((group_tag, _)) if group_tag != tag => {
+                        return Err(DecodeError::new("unexpected end group tag"));
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+            if !self.groups.is_empty() {
+                continue;
+            }
+
+            let mut field = &self.pending[offset..self.scanned];
+            if let Some((tag, wire_type)) = ctx.decode_key(&mut field)? {
+                self.seen.insert::<M>(tag)?;
+                self.message
+                    .merge_field(tag, wire_type, &mut field, ctx.clone())?;
+            }
+            offset = self.scanned;
+        };
+
+        self.pending.drain(..offset);
+        self.scanned -= offset;
+        self.decoded += offset;
+        if self.needed == 0 {
+            Ok(DecodeProgress::Ready)
+        } else {
+            Ok(DecodeProgress::NeedMoreData(self.needed))
+        }
+    }
+
+    /// Returns the message decoded from the complete fields pushed so far.
+    pub fn message(&self) -> &M {
+        &self.message
+    }
+
+    /// Returns the decoded message, once its last chunk has been pushed.
+    ///
+    /// An error is returned if the last field is incomplete, or if required fields are missing and
+    /// the decode configuration rejects such messages.
+    pub fn finish(self) -> Result<M, DecodeError> {
+        if self.needed > 0 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::Truncated,
+                format!(
+                    "truncated message: at least {} more bytes needed",
+                    self.needed
+                ),
+            )
+            .traced());
+        }
+        self.seen
+            .check_required::<M>()
+            .map_err(DecodeError::traced)?;
+        Ok(self.message)
+    }
+}
+
+impl<M> Default for PartialDecoder<M>
+where
+    M: DecodeMessage + Default,
+{
+    fn default() -> PartialDecoder<M> {
+        PartialDecoder::new()
+    }
+}
+
+/// The length of the element at the start of a buffer.
+enum Scan {
+    /// The element is complete, and has this length and key.
+    Complete(usize, Option<(u32, WireType)>),
+    /// The element is incomplete, and needs at least this many more bytes.
+    Incomplete(usize),
+}
+
+/// Finds the length of the element at the start of a non-empty buffer, without decoding it.
+///
+/// An element is a field, except for groups, whose start and end keys are elements of their own.
+fn scan_element(buf: &[u8], ctx: &DecodeContext) -> Result<Scan, DecodeError> {
+    let key_len = match varint_len(buf)? {
+        Some(len) => len,
+        None => return Ok(Scan::Incomplete(1)),
+    };
+    let (tag, wire_type) = match ctx.decode_key(&mut &buf[..key_len])? {
+        Some(key) => key,
+        None => return Ok(Scan::Complete(key_len, None)),
+    };
+
+    let value = &buf[key_len..];
+    let value_len = match wire_type {
+        WireType::Varint => match varint_len(value)? {
+            Some(len) => len,
+            None => return Ok(Scan::Incomplete(1)),
+        },
+        WireType::SixtyFourBit => 8,
+        WireType::ThirtyTwoBit => 4,
+        WireType::LengthDelimited => match varint_len(value)? {
+            Some(len) => {
+                let data_len = decode_varint(&mut &value[..len])?;
+                ctx.config().check_field_size(data_len)?;
+                if data_len > (usize::MAX - key_len - len) as u64 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::LengthOverflow,
+                        "length delimiter exceeds maximum usize value",
+                    ));
+                }
+                len + data_len as usize
+            }
+            None => return Ok(Scan::Incomplete(1)),
+        },
+        WireType::StartGroup | WireType::EndGroup => 0,
+    };
+
+    if value.len() < value_len {
+        Ok(Scan::Incomplete(value_len - value.len()))
+    } else {
+        Ok(Scan::Complete(key_len + value_len, Some((tag, wire_type))))
+    }
+}
+
+/// Returns the length of the varint at the start of a buffer, or `None` if it's incomplete.
+pub(crate) fn varint_len(buf: &[u8]) -> Result<Option<usize>, DecodeError> {
+    match buf.iter().take(10).position(|&b| b < 0x80) {
+        Some(position) => Ok(Some(position + 1)),
+        None if buf.len() >= 10 => Err(DecodeError::with_kind(
+            DecodeErrorKind::InvalidVarint,
+            "invalid varint",
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+    use alloc::vec;
+
+    use super::*;
+    use crate::encoding::{encode_key, group, uint32};
+    use crate::EncodeMessage;
+
+    #[test]
+    fn byte_by_byte() {
+        let value = String::from("partial");
+        let mut buf = Vec::new();
+        value.encode(&mut buf).unwrap();
+
+        let mut decoder = PartialDecoder::<String>::new();
+        assert_eq!(
+            decoder.push(&buf[..1]).unwrap(),
+            DecodeProgress::NeedMoreData(1)
+        );
+        for i in 1..buf.len() - 1 {
+            assert_eq!(
+                decoder.push(&buf[i..i + 1]).unwrap(),
+                DecodeProgress::NeedMoreData(buf.len() - i - 1)
+            );
+            assert_eq!(decoder.message(), "");
+        }
+        assert_eq!(
+            decoder.push(&buf[buf.len() - 1..]).unwrap(),
+            DecodeProgress::Ready
+        );
+        assert_eq!(decoder.finish().unwrap(), value);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut decoder = PartialDecoder::<u32>::new();
+        assert_eq!(
+            decoder.push(&[0x08, 0x96]).unwrap(),
+            DecodeProgress::NeedMoreData(1)
+        );
+        assert!(decoder.finish().is_err());
+
+        let mut decoder = PartialDecoder::<u32>::new();
+        assert!(decoder
+            .push(&[0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])
+            .is_err());
+    }
+
+    #[test]
+    fn groups() {
+        let mut buf = Vec::new();
+        group::encode_start(2, &mut buf);
+        uint32::encode(1, &5, &mut buf);
+        group::encode_start(3, &mut buf);
+        group::encode_end(3, &mut buf);
+        group::encode_end(2, &mut buf);
+        uint32::encode(1, &7, &mut buf);
+
+        // The group is an unknown field of the message, skipped once complete.
+        let mut decoder = PartialDecoder::<u32>::new();
+        for (i, byte) in buf.iter().enumerate() {
+            let progress = decoder.push(&[*byte]).unwrap();
+            assert_eq!(progress == DecodeProgress::Ready, i == 5 || i == 7);
+        }
+        assert_eq!(decoder.finish().unwrap(), 7);
+
+        let mut buf = vec![];
+        group::encode_start(2, &mut buf);
+        encode_key(4, WireType::EndGroup, &mut buf);
+        assert!(PartialDecoder::<u32>::new().push(&buf).is_err());
+
+        let mut decoder = PartialDecoder::<u32>::new();
+        assert_eq!(
+            decoder.push(&buf[..1]).unwrap(),
+            DecodeProgress::NeedMoreData(1)
+        );
+        assert!(decoder.finish().is_err());
+    }
+
+    #[test]
+    fn size_limits() {
+        // Only the length of a huge field is needed to reject it.
+        let config = DecodeConfig::new().max_field_size(16);
+        let mut decoder = PartialDecoder::<String>::with_config(config);
+        let error = decoder.push(&[0x0a, 0x80, 0x80, 0x80, 0x08]).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::LengthOverflow);
+
+        let config = DecodeConfig::new().max_message_size(16);
+        let mut decoder = PartialDecoder::<String>::with_config(config);
+        let error = decoder.push(&[0x0a, 0x0f]).unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::LengthOverflow);
+
+        // Decoded fields count towards the size of the message.
+        let mut decoder = PartialDecoder::<String>::with_config(config);
+        for _ in 0..5 {
+            assert_eq!(decoder.push(&[0x0a, 0x01, b'a']), Ok(DecodeProgress::Ready));
+        }
+        assert!(decoder.push(&[0x0a, 0x00]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Runtime reflection on messages.
+//!
+//! Messages deriving [`ReflectMessage`] describe their fields, and return the value of a field by
+//! name, so generic code such as formatters, diffing or redaction can walk any message without
+//! per-type code. The derive is enabled for generated messages with
+//! `prost_build::Config::reflect`.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "prost-derive")]
+//! # fn main() {
+//! use prost::reflect::{FieldKind, Value};
+//! use prost::{Message, ReflectMessage};
+//!
+//! #[derive(Clone, PartialEq, Message, ReflectMessage)]
+//! struct Person {
+//!     #[prost(string, tag = "1")]
+//!     name: String,
+//!     #[prost(uint32, repeated, tag = "2")]
+//!     scores: Vec<u32>,
+//! }
+//!
+//! // Lists the fields of any message.
+//! fn field_names(message: &dyn ReflectMessage) -> Vec<&'static str> {
+//!     message.fields().iter().map(|field| field.name).collect()
+//! }
+//!
+//! let person = Person { name: "Ada".to_string(), scores: vec![3, 5] };
+//! assert_eq!(field_names(&person), ["name", "scores"]);
+//! assert_eq!(person.fields()[1].kind, FieldKind::Uint32);
+//! match person.get("name") {
+//!     Some(Value::String(name)) => assert_eq!(name, "Ada"),
+//!     _ => unreachable!(),
+//! }
+//! # }
+//! # #[cfg(not(feature = "prost-derive"))]
+//! # fn main() {}
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::Message;
+
+/// A message describing its fields at runtime.
+///
+/// The trait is object safe, so generic code can take a `&dyn ReflectMessage`. It is implemented
+/// with `#[derive(ReflectMessage)]`, next to `#[derive(Message)]`, which requires the types of
+/// nested message fields to implement `ReflectMessage` as well, and the enums of `oneof` fields
+/// to derive [`ReflectOneof`].
+pub trait ReflectMessage: Message {
+    /// Returns the name of the Rust type of the message.
+    fn message_name(&self) -> &'static str;
+
+    /// Returns the descriptions of the fields of the message, in declaration order.
+    fn fields(&self) -> &'static [FieldInfo];
+
+    /// Returns the value of the field with the given name, or `None` if the message has no such
+    /// field, or if the field has no value: an optional field or a message field which isn't
+    /// set, or a `oneof` without a variant.
+    ///
+    /// The fields of proto3 messages without presence always have a value, which may be the
+    /// default.
+    fn get(&self, name: &str) -> Option<Value<'_>>;
+}
+
+impl<M> ReflectMessage for Box<M>
+where
+    M: ReflectMessage,
+{
+    fn message_name(&self) -> &'static str {
+        (**self).message_name()
+    }
+
+    fn fields(&self) -> &'static [FieldInfo] {
+        (**self).fields()
+    }
+
+    fn get(&self, name: &str) -> Option<Value<'_>> {
+        (**self).get(name)
+    }
+}
+
+/// The enum of a `oneof` field returning its value at runtime, implemented with
+/// `#[derive(ReflectOneof)]`.
+pub trait ReflectOneof {
+    /// Returns the tag of the variant, and its value.
+    fn reflect(&self) -> (u32, Value<'_>);
+}
+
+/// The description of a message field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// The name of the field in the Rust struct.
+    pub name: &'static str,
+    /// The tag of the field, or the tags of the variants of a `oneof`.
+    pub tags: &'static [u32],
+    /// The type of the field.
+    pub kind: FieldKind,
+    /// The label of the field.
+    pub label: FieldLabel,
+}
+
+/// The type of a message field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FieldKind {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    /// An enumeration field, whose value is an `i32`.
+    Enum,
+    /// A nested message, including a flattened message.
+    Message,
+    /// A proto2 group.
+    Group,
+    /// A map, whose value is a [`Value::Map`].
+    Map,
+    /// A `oneof`, whose value is a [`Value::Oneof`].
+    Oneof,
+}
+
+/// The label of a message field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldLabel {
+    /// A field without presence, whose value is always set, possibly to the default.
+    Singular,
+    /// A field with presence: an `optional` scalar, a message or a `oneof`.
+    Optional,
+    /// A proto2 `required` field.
+    Required,
+    /// A repeated field or a map.
+    Repeated,
+}
+
+/// The value of a message field.
+///
+/// Values borrow from the message. Integer values keep the width of the field type, so the
+/// variant of a value is determined by the [`FieldKind`] of its field.
+#[derive(Clone, Debug)]
+pub enum Value<'a> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    /// The value of an enumeration field, which may not be a value of the enum.
+    Enum(i32),
+    Message(&'a dyn ReflectMessage),
+    /// The values of a repeated field.
+    List(Vec<Value<'a>>),
+    /// The entries of a map field, in the iteration order of the map.
+    Map(Vec<(Value<'a>, Value<'a>)>),
+    /// The tag and value of the variant of a `oneof` field.
+    Oneof(u32, Box<Value<'a>>),
+}
@@ -0,0 +1,237 @@
+//! Encoding into sinks which consume bytes without storing them.
+
+use core::hash::Hasher;
+
+use ::bytes::buf::UninitSlice;
+use ::bytes::BufMut;
+
+/// A consumer of encoded bytes, such as a hasher, a checksum or a byte counter.
+///
+/// Messages are encoded into a sink with
+/// [`EncodeMessage::encode_to_sink`](crate::EncodeMessage::encode_to_sink), which passes the
+/// encoding to the sink in chunks instead of materializing it, so a digest of a message can be
+/// computed in a single traversal without allocating.
+pub trait EncodeSink {
+    /// Consumes the next chunk of the encoding.
+    fn write(&mut self, bytes: &[u8]);
+}
+
+impl<S> EncodeSink for &mut S
+where
+    S: EncodeSink + ?Sized,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        (**self).write(bytes)
+    }
+}
+
+/// An [`EncodeSink`] feeding the encoding to a [`Hasher`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+///
+/// use prost::{EncodeMessage, HasherSink};
+///
+/// let mut sink = HasherSink(DefaultHasher::new());
+/// String::from("hello").encode_to_sink(&mut sink);
+/// let hash = sink.0.finish();
+/// # let _ = hash;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HasherSink<H>(pub H);
+
+impl<H> EncodeSink for HasherSink<H>
+where
+    H: Hasher,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+}
+
+/// The size of the buffer collecting small writes before they are passed to the sink.
+const CHUNK_LEN: usize = 64;
+
+/// A `BufMut` passing the bytes put into it to an `EncodeSink` in chunks.
+pub(crate) struct SinkBuf<S> {
+    sink: S,
+    chunk: [u8; CHUNK_LEN],
+    len: usize,
+}
+
+impl<S> SinkBuf<S>
+where
+    S: EncodeSink,
+{
+    pub(crate) fn new(sink: S) -> SinkBuf<S> {
+        SinkBuf {
+            sink,
+            chunk: [0; CHUNK_LEN],
+            len: 0,
+        }
+    }
+
+    /// Passes the buffered bytes to the sink.
+    pub(crate) fn flush(&mut self) {
+        if self.len > 0 {
+            self.sink.write(&self.chunk[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+unsafe impl<S> BufMut for SinkBuf<S>
+where
+    S: EncodeSink,
+{
+    fn remaining_mut(&self) -> usize {
+        usize::MAX
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(cnt <= CHUNK_LEN - self.len, "advance out of bounds");
+        self.len += cnt;
+        if self.len == CHUNK_LEN {
+            self.flush();
+        }
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        let chunk = &mut self.chunk[self.len..];
+        // Safety: the chunk is initialized memory, and is never full since it's flushed when it
+        // fills up.
+        unsafe { UninitSlice::from_raw_parts_mut(chunk.as_mut_ptr(), chunk.len()) }
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        if src.len() > CHUNK_LEN - self.len {
+            self.flush();
+            self.sink.write(src);
+        } else {
+            self.chunk[self.len..self.len + src.len()].copy_from_slice(src);
+            self.len += src.len();
+            if self.len == CHUNK_LEN {
+                self.flush();
+            }
+        }
+    }
+}
+
+/// The size of the buffer staging writes to an `io::Write`.
+#[cfg(feature = "std")]
+const STAGE_LEN: usize = 8 * 1024;
+
+/// An `EncodeSink` writing to an `io::Write`, through a staging buffer so that the writer sees
+/// few, large writes.
+///
+/// The first error is kept, and the rest of the encoding is dropped.
+#[cfg(feature = "std")]
+pub(crate) struct WriterSink<W> {
+    writer: W,
+    stage: Vec<u8>,
+    result: std::io::Result<()>,
+}
+
+#[cfg(feature = "std")]
+impl<W> WriterSink<W>
+where
+    W: std::io::Write,
+{
+    /// Creates a sink for an encoding of `len` bytes.
+    pub(crate) fn new(writer: W, len: usize) -> WriterSink<W> {
+        WriterSink {
+            writer,
+            stage: Vec::with_capacity(core::cmp::min(len, STAGE_LEN)),
+            result: Ok(()),
+        }
+    }
+
+    fn write_stage(&mut self) {
+        if self.result.is_ok() && !self.stage.is_empty() {
+            self.result = self.writer.write_all(&self.stage);
+        }
+        self.stage.clear();
+    }
+
+    /// Writes the staged bytes, and returns the first error.
+    pub(crate) fn finish(mut self) -> std::io::Result<()> {
+        self.write_stage();
+        self.result
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W> EncodeSink for WriterSink<W>
+where
+    W: std::io::Write,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        if self.result.is_err() {
+            return;
+        }
+        if bytes.len() > STAGE_LEN - self.stage.len() {
+            self.write_stage();
+        }
+        if bytes.len() >= STAGE_LEN {
+            if self.result.is_ok() {
+                self.result = self.writer.write_all(bytes);
+            }
+        } else {
+            self.stage.extend_from_slice(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::EncodeMessage;
+
+    /// A sink recording the chunks it is passed.
+    #[derive(Default)]
+    struct Chunks(Vec<Vec<u8>>);
+
+    impl EncodeSink for Chunks {
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.push(bytes.to_vec());
+        }
+    }
+
+    #[test]
+    fn encode_to_sink() {
+        for len in &[0, 1, CHUNK_LEN - 3, CHUNK_LEN - 2, CHUNK_LEN, 1000] {
+            let msg = (0..*len).map(|b| b as u8).collect::<Vec<u8>>();
+            let mut sink = Chunks::default();
+            msg.encode_to_sink(&mut sink);
+            assert!(sink.0.iter().all(|chunk| !chunk.is_empty()));
+            let mut expected = Vec::new();
+            msg.encode(&mut expected).unwrap();
+            assert_eq!(sink.0.concat(), expected);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn encode_to_writer() {
+        for len in &[0, 1, STAGE_LEN - 3, STAGE_LEN, 3 * STAGE_LEN] {
+            let msg = (0..*len).map(|b| b as u8).collect::<Vec<u8>>();
+            let mut written = Vec::new();
+            msg.encode_to_writer(&mut written).unwrap();
+            assert_eq!(written, msg.encode_to_vec());
+            written.clear();
+            msg.encode_length_delimited_to_writer(&mut written).unwrap();
+            assert_eq!(written, msg.encode_length_delimited_to_vec());
+        }
+
+        let mut full = [0u8; 4];
+        let err = String::from("hello")
+            .encode_to_writer(&mut full[..])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    }
+}
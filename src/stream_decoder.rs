@@ -0,0 +1,394 @@
+//! Decoding of streams of length-delimited messages.
+
+use alloc::format;
+use core::marker::PhantomData;
+
+use ::bytes::{Buf, BytesMut};
+
+use crate::encoding::decode_varint;
+use crate::partial_decoder::varint_len;
+use crate::{DecodeConfig, DecodeError, DecodeErrorKind, DecodeMessage};
+
+/// A decoder for a stream of length-delimited messages, such as a record log or a socket carrying
+/// messages written with
+/// [`EncodeMessage::encode_length_delimited`](crate::EncodeMessage::encode_length_delimited).
+///
+/// Bytes are pushed in chunks of any size, as they are read, and each message is decoded once
+/// all of its bytes have been pushed. The decoder is an iterator over the complete messages
+/// pushed so far: `None` means that more bytes are needed, not that the stream has ended, so the
+/// iterator can be resumed after pushing more bytes. Message bytes are decoded from the shared
+/// buffer of the decoder, so `Bytes` and [`Blob`](crate::Blob) fields don't copy their values.
+///
+/// A message longer than the `max_message_size` of the decode options is rejected as soon as its
+/// length-delimiter has been pushed, before its bytes are buffered. To read a stream from an
+/// [`std::io::Read`], use [`MessageStreamReader`] instead.
+///
+/// After an error the state of the decoder is unspecified, and it should be dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::{EncodeMessage, MessageStreamDecoder};
+///
+/// let mut stream = Vec::new();
+/// for value in &["first", "second"] {
+///     value.to_string().encode_length_delimited(&mut stream).unwrap();
+/// }
+///
+/// let mut decoder = MessageStreamDecoder::<String>::new();
+/// decoder.push(&stream[..4]);
+/// assert!(decoder.next().is_none());
+/// decoder.push(&stream[4..]);
+/// let messages: Result<Vec<_>, _> = decoder.by_ref().collect();
+/// assert_eq!(messages.unwrap(), ["first", "second"]);
+/// assert!(decoder.finish().is_ok());
+/// ```
+#[derive(Debug)]
+pub struct MessageStreamDecoder<M> {
+    buf: BytesMut,
+    config: DecodeConfig,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M> MessageStreamDecoder<M>
+where
+    M: DecodeMessage + Default,
+{
+    /// Creates a decoder with the default decode configuration.
+    pub fn new() -> MessageStreamDecoder<M> {
+        MessageStreamDecoder::with_config(DecodeConfig::default())
+    }
+
+    /// Creates a decoder with the given decode options, which apply to each message.
+    pub fn with_config(config: DecodeConfig) -> MessageStreamDecoder<M> {
+        MessageStreamDecoder {
+            buf: BytesMut::new(),
+            config,
+            message: PhantomData,
+        }
+    }
+
+    /// Appends the next chunk of the stream.
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Decodes the next message, or returns `None` if it hasn't been pushed completely yet.
+    pub fn decode_next(&mut self) -> Result<Option<M>, DecodeError> {
+        decode_frame(&mut self.buf, self.config)
+    }
+
+    /// Returns the number of bytes pushed which don't belong to a decoded message yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Checks that the stream didn't end in the middle of a message.
+    pub fn finish(self) -> Result<(), DecodeError> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::with_kind(
+                DecodeErrorKind::Truncated,
+                format!(
+                    "truncated stream: {} bytes of an incomplete message",
+                    self.buf.len()
+                ),
+            )
+            .traced())
+        }
+    }
+}
+
+/// Splits the next length-delimited message off the front of `buf` and decodes it, or returns
+/// `None` if `buf` doesn't hold the complete message yet.
+pub(crate) fn decode_frame<M>(
+    buf: &mut BytesMut,
+    config: DecodeConfig,
+) -> Result<Option<M>, DecodeError>
+where
+    M: DecodeMessage + Default,
+{
+    let (prefix_len, len) = match frame_len(buf, config).map_err(DecodeError::traced)? {
+        Some(frame_len) => frame_len,
+        None => return Ok(None),
+    };
+    if buf.len() - prefix_len < len {
+        return Ok(None);
+    }
+
+    buf.advance(prefix_len);
+    let message = buf.split_to(len).freeze();
+    M::decode_with_config(message, config).map(Some)
+}
+
+/// Returns the lengths of the length-delimiter and of the message at the front of `buf`, or
+/// `None` if `buf` doesn't hold the complete length-delimiter yet.
+fn frame_len(buf: &BytesMut, config: DecodeConfig) -> Result<Option<(usize, usize)>, DecodeError> {
+    let prefix_len = match varint_len(buf)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    let len = decode_varint(&mut &buf[..prefix_len])?;
+    if len > (usize::MAX - prefix_len) as u64 {
+        return Err(DecodeError::with_kind(
+            DecodeErrorKind::LengthOverflow,
+            "length delimiter exceeds maximum usize value",
+        ));
+    }
+    config.check_message_size(len)?;
+    Ok(Some((prefix_len, len as usize)))
+}
+
+/// A length-delimiter read a byte at a time, for readers which must not read past the end of a
+/// message.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub(crate) struct LengthDelimiter {
+    len: u64,
+    read: u32,
+}
+
+#[cfg(feature = "std")]
+impl LengthDelimiter {
+    /// Returns `true` if no byte of the length-delimiter has been read yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.read == 0
+    }
+
+    /// Adds the next byte of the length-delimiter, and returns the length of the message once the
+    /// length-delimiter is complete.
+    ///
+    /// The length is checked against the `max_message_size` of the decode options, so that no
+    /// byte of an oversized message is read.
+    pub(crate) fn push(
+        &mut self,
+        byte: u8,
+        config: &DecodeConfig,
+    ) -> Result<Option<usize>, DecodeError> {
+        if self.read == 9 && byte > 1 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::InvalidVarint,
+                "invalid varint",
+            ));
+        }
+        self.len |= u64::from(byte & 0x7F) << (self.read * 7);
+        self.read += 1;
+        if byte >= 0x80 {
+            return Ok(None);
+        }
+        if self.len > usize::MAX as u64 {
+            return Err(DecodeError::with_kind(
+                DecodeErrorKind::LengthOverflow,
+                "length delimiter exceeds maximum usize value",
+            ));
+        }
+        config.check_message_size(self.len)?;
+        Ok(Some(self.len as usize))
+    }
+}
+
+impl<M> Default for MessageStreamDecoder<M>
+where
+    M: DecodeMessage + Default,
+{
+    fn default() -> MessageStreamDecoder<M> {
+        MessageStreamDecoder::new()
+    }
+}
+
+/// Iterates over the messages pushed so far.
+///
+/// The iterator isn't fused: after returning `None` because the next message is incomplete, it
+/// returns that message once the rest of its bytes have been pushed.
+impl<M> Iterator for MessageStreamDecoder<M>
+where
+    M: DecodeMessage + Default,
+{
+    type Item = Result<M, DecodeError>;
+
+    fn next(&mut self) -> Option<Result<M, DecodeError>> {
+        self.decode_next().transpose()
+    }
+}
+
+/// An iterator over a stream of length-delimited messages read from an [`std::io::Read`].
+///
+/// The reader is read in chunks, which are decoded as by [`MessageStreamDecoder`], so it doesn't
+/// need to be buffered. The iterator ends when the reader is at its end, returning an error of
+/// kind `UnexpectedEof` first if it ends in the middle of a message, and after the first error.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::{EncodeMessage, MessageStreamReader};
+///
+/// let mut stream = Vec::new();
+/// for value in &["first", "second"] {
+///     value.to_string().encode_length_delimited(&mut stream).unwrap();
+/// }
+///
+/// let messages: Result<Vec<String>, _> = MessageStreamReader::new(&stream[..]).collect();
+/// assert_eq!(messages.unwrap(), ["first", "second"]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MessageStreamReader<R, M> {
+    reader: R,
+    decoder: MessageStreamDecoder<M>,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R, M> MessageStreamReader<R, M>
+where
+    R: std::io::Read,
+    M: DecodeMessage + Default,
+{
+    /// Creates an iterator over the messages read from `reader`, with the default decode
+    /// configuration.
+    pub fn new(reader: R) -> MessageStreamReader<R, M> {
+        MessageStreamReader::with_config(reader, DecodeConfig::default())
+    }
+
+    /// Creates an iterator over the messages read from `reader`, with the given decode options,
+    /// which apply to each message.
+    pub fn with_config(reader: R, config: DecodeConfig) -> MessageStreamReader<R, M> {
+        MessageStreamReader {
+            reader,
+            decoder: MessageStreamDecoder::with_config(config),
+            done: false,
+        }
+    }
+
+    /// Returns the reader. Bytes read from it which don't belong to a decoded message are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, M> Iterator for MessageStreamReader<R, M>
+where
+    R: std::io::Read,
+    M: DecodeMessage + Default,
+{
+    type Item = std::io::Result<M>;
+
+    fn next(&mut self) -> Option<std::io::Result<M>> {
+        use std::io::ErrorKind;
+
+        let mut chunk = [0u8; 8 * 1024];
+        while !self.done {
+            match self.decoder.decode_next() {
+                Ok(Some(message)) => return Some(Ok(message)),
+                Ok(None) => (),
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error.into()));
+                }
+            }
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.done = true;
+                    if self.decoder.buffered() > 0 {
+                        return Some(Err(ErrorKind::UnexpectedEof.into()));
+                    }
+                }
+                Ok(len) => self.decoder.push(&chunk[..len]),
+                Err(error) if error.kind() == ErrorKind::Interrupted => (),
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::EncodeMessage;
+
+    #[test]
+    fn byte_by_byte() {
+        let values = [String::from("a"), String::new(), "b".repeat(200)];
+        let mut stream = Vec::new();
+        for value in &values {
+            value.encode_length_delimited(&mut stream).unwrap();
+        }
+
+        let mut decoder = MessageStreamDecoder::<String>::new();
+        let mut decoded = Vec::new();
+        for byte in &stream {
+            decoder.push(&[*byte]);
+            for message in decoder.by_ref() {
+                decoded.push(message.unwrap());
+            }
+        }
+        assert_eq!(decoded, values);
+        assert_eq!(decoder.buffered(), 0);
+        assert!(decoder.finish().is_ok());
+    }
+
+    #[test]
+    fn errors() {
+        let mut decoder = MessageStreamDecoder::<String>::new();
+        decoder.push(&[0x03, 0x0a]);
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.buffered(), 2);
+        assert!(decoder.finish().is_err());
+
+        // A message with an invalid field.
+        let mut decoder = MessageStreamDecoder::<String>::new();
+        decoder.push(&[0x02, 0x0a, 0x05]);
+        assert!(decoder.next().unwrap().is_err());
+
+        let mut decoder = MessageStreamDecoder::<String>::new();
+        decoder.push(&[0xff; 11]);
+        assert!(decoder.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn message_size_limit() {
+        // Only the length-delimiter of a huge message is needed to reject it.
+        let config = DecodeConfig::new().max_message_size(16);
+        let mut decoder = MessageStreamDecoder::<String>::with_config(config);
+        decoder.push(&[0x80, 0x80, 0x80, 0x80, 0x08]);
+        let error = decoder.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), DecodeErrorKind::LengthOverflow);
+
+        let mut decoder = MessageStreamDecoder::<String>::with_config(config);
+        decoder.push(&[0x11]);
+        assert!(decoder.next().unwrap().is_err());
+
+        let mut decoder = MessageStreamDecoder::<String>::with_config(config);
+        decoder.push(&[0x10]);
+        assert!(decoder.next().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader() {
+        let values = [String::from("a"), String::new(), "b".repeat(20_000)];
+        let mut stream = Vec::new();
+        for value in &values {
+            value.encode_length_delimited(&mut stream).unwrap();
+        }
+
+        let decoded: std::io::Result<Vec<String>> = MessageStreamReader::new(&stream[..]).collect();
+        assert_eq!(decoded.unwrap(), values);
+
+        let mut reader = MessageStreamReader::<_, String>::new(&stream[..stream.len() - 1]);
+        assert_eq!(reader.next().unwrap().unwrap(), "a");
+        assert_eq!(reader.next().unwrap().unwrap(), "");
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+    }
+}
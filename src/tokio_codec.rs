@@ -0,0 +1,284 @@
+//! Integration with the `tokio` asynchronous I/O traits and `tokio-util` codecs.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use std::io;
+
+use ::bytes::BytesMut;
+use tokio_dep::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util_dep::codec::{Decoder, Encoder};
+
+use crate::stream_decoder::{decode_frame, LengthDelimiter};
+use crate::{DecodeConfig, DecodeError, DecodeMessage, EncodeMessage, Message};
+
+/// Asynchronous reading and writing of length-delimited messages.
+///
+/// The trait is implemented for all messages. Its methods return boxed futures, so the readers
+/// and writers must be `Send`.
+///
+/// # Example
+///
+/// ```rust
+/// # async fn example() -> std::io::Result<()> {
+/// use prost::AsyncMessageExt;
+///
+/// let mut stream = Vec::new();
+/// String::from("hello").encode_delimited(&mut stream).await?;
+/// let decoded = String::decode_delimited(&mut &stream[..]).await?;
+/// assert_eq!(decoded.as_deref(), Some("hello"));
+/// # Ok(())
+/// # }
+/// ```
+pub trait AsyncMessageExt: Message + Sized {
+    /// Writes the message with a length-delimiter to a writer.
+    fn encode_delimited<'a, W>(
+        &'a self,
+        writer: &'a mut W,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'a>>
+    where
+        W: AsyncWrite + Unpin + Send + ?Sized,
+    {
+        Box::pin(async move {
+            let buf = self.encode_length_delimited_to_vec();
+            writer.write_all(&buf).await
+        })
+    }
+
+    /// Reads a single length-delimited instance of the message from a reader.
+    ///
+    /// Exactly the bytes of the message and its length-delimiter are read, so further messages can
+    /// be read from the same reader; since the length-delimiter is read a byte at a time, the
+    /// reader should be buffered. Returns `None` if the reader is at its end, and an error of kind
+    /// `UnexpectedEof` if it ends in the middle of the message.
+    ///
+    /// Messages are limited to 8 MiB, as the frames of [`MessageCodec::new`] are; a longer
+    /// message is rejected with an error of kind `InvalidData` before it is read.
+    fn decode_delimited<'a, R>(
+        reader: &'a mut R,
+    ) -> Pin<Box<dyn Future<Output = io::Result<Option<Self>>> + Send + 'a>>
+    where
+        R: AsyncRead + Unpin + Send + ?Sized,
+        Self: Default + 'a,
+    {
+        let config = DecodeConfig::new().max_message_size(DEFAULT_MAX_FRAME_SIZE);
+        Self::decode_delimited_with_config(reader, config)
+    }
+
+    /// Reads a single length-delimited instance of the message from a reader, with the given
+    /// decode options.
+    ///
+    /// Messages are limited to the `max_message_size` of the options, which is unlimited by
+    /// default; a longer message is rejected with an error of kind `InvalidData` before it is
+    /// read.
+    fn decode_delimited_with_config<'a, R>(
+        reader: &'a mut R,
+        config: DecodeConfig,
+    ) -> Pin<Box<dyn Future<Output = io::Result<Option<Self>>> + Send + 'a>>
+    where
+        R: AsyncRead + Unpin + Send + ?Sized,
+        Self: Default + 'a,
+    {
+        Box::pin(async move {
+            let mut delimiter = LengthDelimiter::default();
+            let len = loop {
+                let byte = match reader.read_u8().await {
+                    Ok(byte) => byte,
+                    Err(error)
+                        if delimiter.is_empty() && error.kind() == io::ErrorKind::UnexpectedEof =>
+                    {
+                        return Ok(None);
+                    }
+                    Err(error) => return Err(error),
+                };
+                if let Some(len) = delimiter.push(byte, &config).map_err(DecodeError::traced)? {
+                    break len;
+                }
+            };
+            let mut buf = Vec::new();
+            (&mut *reader)
+                .take(len as u64)
+                .read_to_end(&mut buf)
+                .await?;
+            if buf.len() < len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(Some(Self::decode_with_config(&*buf, config)?))
+        })
+    }
+}
+
+impl<M> AsyncMessageExt for M where M: Message {}
+
+/// A `tokio-util` codec for length-delimited message frames, for use with `Framed`,
+/// `FramedRead` and `FramedWrite`.
+///
+/// Frames are decoded as by [`MessageStreamDecoder`](crate::MessageStreamDecoder), and encoded
+/// with [`EncodeMessage::encode_length_delimited`]. A frame longer than the `max_message_size` of
+/// the decode options is rejected as soon as its length-delimiter is read.
+#[derive(Debug)]
+pub struct MessageCodec<M> {
+    config: DecodeConfig,
+    message: PhantomData<fn() -> M>,
+}
+
+/// The default maximum size of a decoded frame, as for `LengthDelimitedCodec`.
+const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+
+impl<M> MessageCodec<M> {
+    /// Creates a codec with the default decode configuration, except that frames are limited to
+    /// 8 MiB.
+    pub fn new() -> MessageCodec<M> {
+        MessageCodec::with_config(DecodeConfig::new().max_message_size(DEFAULT_MAX_FRAME_SIZE))
+    }
+
+    /// Creates a codec with the given decode options, which apply to each decoded message.
+    ///
+    /// Frames are limited to the `max_message_size` of the options, which is unlimited by
+    /// default.
+    pub fn with_config(config: DecodeConfig) -> MessageCodec<M> {
+        MessageCodec {
+            config,
+            message: PhantomData,
+        }
+    }
+}
+
+impl<M> Clone for MessageCodec<M> {
+    fn clone(&self) -> MessageCodec<M> {
+        MessageCodec::with_config(self.config)
+    }
+}
+
+impl<M> Default for MessageCodec<M> {
+    fn default() -> MessageCodec<M> {
+        MessageCodec::new()
+    }
+}
+
+impl<M> Decoder for MessageCodec<M>
+where
+    M: DecodeMessage + Default,
+{
+    type Item = M;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<M>> {
+        Ok(decode_frame(src, self.config)?)
+    }
+}
+
+impl<M> Encoder<M> for MessageCodec<M>
+where
+    M: EncodeMessage,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: M, dst: &mut BytesMut) -> io::Result<()> {
+        <Self as Encoder<&M>>::encode(self, &item, dst)
+    }
+}
+
+impl<'a, M> Encoder<&'a M> for MessageCodec<M>
+where
+    M: EncodeMessage,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &'a M, dst: &mut BytesMut) -> io::Result<()> {
+        let len = item.encoded_len();
+        dst.reserve(len + crate::encoded_len_varint(len as u64));
+        item.encode_length_delimited(dst)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::String;
+
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio_dep::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn async_delimited() {
+        let values = [String::from("a"), String::new(), "b".repeat(200)];
+        let mut stream = Vec::new();
+        for value in &values {
+            block_on(value.encode_delimited(&mut stream)).unwrap();
+        }
+        assert_eq!(stream[..3], [0x03, 0x0a, 0x01]);
+
+        let mut reader = &stream[..];
+        for value in &values {
+            let decoded = block_on(String::decode_delimited(&mut reader)).unwrap();
+            assert_eq!(decoded.as_ref(), Some(value));
+        }
+        assert_eq!(
+            block_on(String::decode_delimited(&mut reader)).unwrap(),
+            None
+        );
+
+        let err = block_on(String::decode_delimited(&mut &stream[..2])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // The limit is checked before the message is read.
+        let config = DecodeConfig::new().max_message_size(200);
+        let mut reader = &stream[4..];
+        let decoded = block_on(String::decode_delimited_with_config(&mut reader, config));
+        assert_eq!(decoded.unwrap().as_deref(), Some(""));
+        let decoded = block_on(String::decode_delimited_with_config(&mut reader, config));
+        assert_eq!(decoded.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.len(), 203);
+
+        let oversized = [0x80, 0x80, 0x80, 0x08];
+        let err = block_on(String::decode_delimited(&mut &oversized[..])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn codec() {
+        let mut codec = MessageCodec::<String>::new();
+        let mut buf = BytesMut::new();
+        codec.encode(String::from("a"), &mut buf).unwrap();
+        codec.encode(&"b".repeat(200), &mut buf).unwrap();
+
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for byte in buf.iter() {
+            src.extend_from_slice(&[*byte]);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                decoded.push(message);
+            }
+        }
+        assert_eq!(decoded, [String::from("a"), "b".repeat(200)]);
+        assert!(codec.decode_eof(&mut src).unwrap().is_none());
+
+        src.extend_from_slice(&[0x02, 0x0a]);
+        assert!(codec.decode_eof(&mut src).is_err());
+    }
+
+    #[test]
+    fn codec_frame_limit() {
+        // A frame of 8 MiB and a byte is rejected from its length-delimiter alone.
+        let mut codec = MessageCodec::<String>::new();
+        let mut src = BytesMut::from(&[0x81, 0x80, 0x80, 0x04][..]);
+        let error = codec.decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut src = BytesMut::from(&[0x80, 0x80, 0x80, 0x04][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+
+        let mut codec = MessageCodec::<String>::with_config(DecodeConfig::new());
+        let mut src = BytesMut::from(&[0x81, 0x80, 0x80, 0x04][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+    }
+}
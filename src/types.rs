@@ -15,11 +15,11 @@ use crate::{
         bool, bytes, double, float, int32, int64, skip_field, string, uint32, uint64,
         DecodeContext, WireType,
     },
-    DecodeError, Message,
+    DecodeError, DecodeMessage, EncodeMessage,
 };
 
 /// `google.protobuf.BoolValue`
-impl Message for bool {
+impl EncodeMessage for bool {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -28,6 +28,16 @@ impl Message for bool {
             bool::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self {
+            2
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for bool {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -44,12 +54,8 @@ impl Message for bool {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self {
-            2
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = false;
@@ -57,7 +63,7 @@ impl Message for bool {
 }
 
 /// `google.protobuf.UInt32Value`
-impl Message for u32 {
+impl EncodeMessage for u32 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -66,6 +72,16 @@ impl Message for u32 {
             uint32::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0 {
+            uint32::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for u32 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -82,12 +98,8 @@ impl Message for u32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0 {
-            uint32::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0;
@@ -95,7 +107,7 @@ impl Message for u32 {
 }
 
 /// `google.protobuf.UInt64Value`
-impl Message for u64 {
+impl EncodeMessage for u64 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -104,6 +116,16 @@ impl Message for u64 {
             uint64::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0 {
+            uint64::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for u64 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -120,12 +142,8 @@ impl Message for u64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0 {
-            uint64::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0;
@@ -133,7 +151,7 @@ impl Message for u64 {
 }
 
 /// `google.protobuf.Int32Value`
-impl Message for i32 {
+impl EncodeMessage for i32 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -142,6 +160,16 @@ impl Message for i32 {
             int32::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0 {
+            int32::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for i32 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -158,12 +186,8 @@ impl Message for i32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0 {
-            int32::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0;
@@ -171,7 +195,7 @@ impl Message for i32 {
 }
 
 /// `google.protobuf.Int64Value`
-impl Message for i64 {
+impl EncodeMessage for i64 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -180,6 +204,16 @@ impl Message for i64 {
             int64::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0 {
+            int64::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for i64 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -196,12 +230,8 @@ impl Message for i64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0 {
-            int64::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0;
@@ -209,7 +239,7 @@ impl Message for i64 {
 }
 
 /// `google.protobuf.FloatValue`
-impl Message for f32 {
+impl EncodeMessage for f32 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -218,6 +248,16 @@ impl Message for f32 {
             float::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0.0 {
+            float::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for f32 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -234,12 +274,8 @@ impl Message for f32 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0.0 {
-            float::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0.0;
@@ -247,7 +283,7 @@ impl Message for f32 {
 }
 
 /// `google.protobuf.DoubleValue`
-impl Message for f64 {
+impl EncodeMessage for f64 {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -256,6 +292,16 @@ impl Message for f64 {
             double::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if *self != 0.0 {
+            double::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for f64 {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -272,12 +318,8 @@ impl Message for f64 {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if *self != 0.0 {
-            double::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         *self = 0.0;
@@ -285,7 +327,7 @@ impl Message for f64 {
 }
 
 /// `google.protobuf.StringValue`
-impl Message for String {
+impl EncodeMessage for String {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -294,6 +336,16 @@ impl Message for String {
             string::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if !self.is_empty() {
+            string::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for String {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -310,12 +362,8 @@ impl Message for String {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if !self.is_empty() {
-            string::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         self.clear();
@@ -323,7 +371,7 @@ impl Message for String {
 }
 
 /// `google.protobuf.BytesValue`
-impl Message for Vec<u8> {
+impl EncodeMessage for Vec<u8> {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -332,6 +380,16 @@ impl Message for Vec<u8> {
             bytes::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if !self.is_empty() {
+            bytes::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for Vec<u8> {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -348,12 +406,8 @@ impl Message for Vec<u8> {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if !self.is_empty() {
-            bytes::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         self.clear();
@@ -361,7 +415,7 @@ impl Message for Vec<u8> {
 }
 
 /// `google.protobuf.BytesValue`
-impl Message for Bytes {
+impl EncodeMessage for Bytes {
     fn encode_raw<B>(&self, buf: &mut B)
     where
         B: BufMut,
@@ -370,6 +424,16 @@ impl Message for Bytes {
             bytes::encode(1, self, buf)
         }
     }
+    fn encoded_len(&self) -> usize {
+        if !self.is_empty() {
+            bytes::encoded_len(1, self)
+        } else {
+            0
+        }
+    }
+}
+
+impl DecodeMessage for Bytes {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -386,12 +450,8 @@ impl Message for Bytes {
             skip_field(wire_type, tag, buf, ctx)
         }
     }
-    fn encoded_len(&self) -> usize {
-        if !self.is_empty() {
-            bytes::encoded_len(1, self)
-        } else {
-            0
-        }
+    fn is_singular_field(tag: u32) -> bool {
+        tag == 1
     }
     fn clear(&mut self) {
         self.clear();
@@ -399,12 +459,18 @@ impl Message for Bytes {
 }
 
 /// `google.protobuf.Empty`
-impl Message for () {
+impl EncodeMessage for () {
     fn encode_raw<B>(&self, _buf: &mut B)
     where
         B: BufMut,
     {
     }
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
+
+impl DecodeMessage for () {
     fn merge_field<B>(
         &mut self,
         tag: u32,
@@ -417,8 +483,5 @@ impl Message for () {
     {
         skip_field(wire_type, tag, buf, ctx)
     }
-    fn encoded_len(&self) -> usize {
-        0
-    }
     fn clear(&mut self) {}
 }
@@ -0,0 +1,771 @@
+//! Schema-less decoding of messages into their raw fields.
+
+use alloc::collections::{btree_set, BTreeSet};
+use alloc::format;
+use alloc::vec::Vec;
+use core::{fmt, mem, slice, str};
+
+use ::bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::encoding::{
+    decode_varint, encode_key, encode_varint, encoded_len_varint, group, key_len, DecodeContext,
+    WireType, MAX_TAG, MIN_TAG,
+};
+use crate::{DecodeError, DecodeErrorKind, DecodeMessage, EncodeMessage};
+
+/// The fields of an encoded message, decoded without its message type.
+///
+/// Each field is kept with its tag and its raw value, in the order of the encoding, including
+/// repeated occurrences of a tag. The set implements [`Message`], so any message payload can be
+/// decoded into it with [`DecodeMessage::decode`], which makes it possible to inspect or route
+/// messages whose schema isn't known. Encoding the set reproduces the fields in the same order.
+///
+/// Length-delimited values decoded from a [`Bytes`] buffer share the buffer rather than copying
+/// it.
+///
+/// # Example
+///
+/// ```rust
+/// use prost::{DecodeMessage, EncodeMessage, UnknownFieldData, UnknownFieldSet};
+///
+/// let mut buf = Vec::new();
+/// String::from("hello").encode(&mut buf).unwrap();
+///
+/// let fields = UnknownFieldSet::decode(&buf[..]).unwrap();
+/// assert_eq!(fields.len(), 1);
+/// let field = fields.iter().next().unwrap();
+/// assert_eq!(field.tag(), 1);
+/// assert_eq!(*field.data(), UnknownFieldData::LengthDelimited("hello".into()));
+///
+/// let mut encoded = Vec::new();
+/// fields.encode(&mut encoded).unwrap();
+/// assert_eq!(encoded, buf);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UnknownFieldSet {
+    // A `Vec` doesn't allocate while the set is empty, as it is for most messages, and holds any
+    // number of fields in a single allocation. Keeping a few fields inline would need
+    // `UnknownFieldData::Group` to box its set, so that the set has a finite size, which costs an
+    // allocation per group instead; the `unknown_fields` benchmark measures the decode path.
+    fields: Vec<UnknownField>,
+}
+
+/// A field of an [`UnknownFieldSet`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UnknownField {
+    tag: u32,
+    data: UnknownFieldData,
+}
+
+/// The raw value of an [`UnknownField`], by wire type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnknownFieldData {
+    /// A varint, of any integer, `bool` or enum type.
+    Varint(u64),
+    /// A 64-bit value, of type `fixed64`, `sfixed64` or `double`.
+    SixtyFourBit(u64),
+    /// A length-delimited value: a `string`, `bytes`, message or packed repeated field.
+    LengthDelimited(Bytes),
+    /// The fields of a group.
+    Group(UnknownFieldSet),
+    /// A 32-bit value, of type `fixed32`, `sfixed32` or `float`.
+    ThirtyTwoBit(u32),
+}
+
+impl UnknownFieldSet {
+    /// Creates an empty field set.
+    pub fn new() -> UnknownFieldSet {
+        UnknownFieldSet::default()
+    }
+
+    /// Returns an iterator over the fields, in encoding order.
+    pub fn iter(&self) -> slice::Iter<'_, UnknownField> {
+        self.fields.iter()
+    }
+
+    /// Returns the number of fields, counting each occurrence of a repeated tag.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if the set has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns the last occurrence of the field with the given tag, or `None` if there is none.
+    ///
+    /// The last occurrence is the value of a non-repeated scalar field. The elements of a repeated
+    /// field, or the parts of a message field to merge, are all the occurrences of its tag, which
+    /// `iter` returns.
+    pub fn get(&self, tag: u32) -> Option<&UnknownField> {
+        self.fields.iter().rev().find(|field| field.tag == tag)
+    }
+
+    /// Removes all the occurrences of the field with the given tag, and returns the last one, as
+    /// returned by `get`, or `None` if there were none.
+    pub fn remove(&mut self, tag: u32) -> Option<UnknownField> {
+        let index = self.fields.iter().rposition(|field| field.tag == tag)?;
+        let field = self.fields.remove(index);
+        self.fields.retain(|field| field.tag != tag);
+        Some(field)
+    }
+
+    /// Merges the fields of another set into this one, as decoding the encodings of both sets one
+    /// after the other does.
+    ///
+    /// The fields of `other` are appended after those of `self`, so the occurrences of each tag
+    /// are concatenated: the elements of repeated fields accumulate, and the later value of a
+    /// non-repeated scalar field wins when the fields are decoded as a message. To merge an
+    /// encoded message into the set, call [`DecodeMessage::merge`] instead.
+    pub fn merge(&mut self, other: &UnknownFieldSet) {
+        self.fields.extend_from_slice(&other.fields);
+    }
+
+    /// Takes the fields out of the set, leaving it empty.
+    pub fn take(&mut self) -> UnknownFieldSet {
+        mem::take(self)
+    }
+
+    /// Returns the distinct tags of the fields, in ascending order.
+    pub fn tags(&self) -> btree_set::IntoIter<u32> {
+        self.fields
+            .iter()
+            .map(|field| field.tag)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Puts the fields in a canonical order, so that sets holding the same fields encode to the
+    /// same bytes.
+    ///
+    /// Fields are sorted by tag, recursively within groups. The sort is stable, since the order of
+    /// the occurrences of a tag is significant: they may be the elements of a repeated field, or
+    /// values of which the last wins. For the same reason no occurrences are coalesced, as that
+    /// requires knowing the type of the field. Keys and varints are always encoded in their
+    /// shortest form, whatever their form in the decoded input.
+    ///
+    /// Length-delimited values are left as they are, since they can't reliably be told apart
+    /// from nested messages.
+    pub fn canonicalize(&mut self) {
+        self.fields.sort_by_key(|field| field.tag);
+        for field in &mut self.fields {
+            if let UnknownFieldData::Group(ref mut fields) = field.data {
+                fields.canonicalize();
+            }
+        }
+    }
+
+    /// Returns the encoded length, keys included, of the fields with the given tag.
+    ///
+    /// Summed over all tags, this is the [`encoded_len`](EncodeMessage::encoded_len) of the set.
+    pub fn tag_encoded_len(&self, tag: u32) -> usize {
+        self.fields
+            .iter()
+            .filter(|field| field.tag == tag)
+            .map(UnknownField::encoded_len)
+            .sum()
+    }
+
+    /// Encodes the fields with the given tag, keys included, to a newly allocated `Bytes`, or
+    /// returns `None` if there are none.
+    ///
+    /// Occurrences of a repeated tag are concatenated in encoding order. The fields are encoded
+    /// from their decoded values, not copied from the decoded input: keys and varints are written
+    /// in their shortest form, so the result differs from the input where the input used longer
+    /// forms.
+    pub fn encode_tag_to_bytes(&self, tag: u32) -> Option<Bytes> {
+        let mut fields = self
+            .fields
+            .iter()
+            .filter(|field| field.tag == tag)
+            .peekable();
+        fields.peek()?;
+        let mut buf = BytesMut::new();
+        for field in fields {
+            buf.reserve(field.encoded_len());
+            field.encode_raw(&mut buf);
+        }
+        Some(buf.freeze())
+    }
+
+    /// Appends a field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tag of the field isn't a valid field number.
+    pub fn push(&mut self, field: UnknownField) -> &mut Self {
+        assert!(
+            (MIN_TAG..=MAX_TAG).contains(&field.tag),
+            "invalid field number: {}",
+            field.tag
+        );
+        self.fields.push(field);
+        self
+    }
+
+    /// Appends a varint field, such as an integer, `bool` or enum value.
+    ///
+    /// Signed values are encoded as `int64` by converting them with `as u64`; `sint32` and
+    /// `sint64` values need to be zigzag-encoded first.
+    pub fn push_varint(&mut self, tag: u32, value: u64) -> &mut Self {
+        self.push(UnknownField::new(tag, UnknownFieldData::Varint(value)))
+    }
+
+    /// Appends a 32-bit field, such as a `fixed32` value, or a `float` converted with
+    /// `f32::to_bits`.
+    pub fn push_fixed32(&mut self, tag: u32, value: u32) -> &mut Self {
+        self.push(UnknownField::new(
+            tag,
+            UnknownFieldData::ThirtyTwoBit(value),
+        ))
+    }
+
+    /// Appends a 64-bit field, such as a `fixed64` value, or a `double` converted with
+    /// `f64::to_bits`.
+    pub fn push_fixed64(&mut self, tag: u32, value: u64) -> &mut Self {
+        self.push(UnknownField::new(
+            tag,
+            UnknownFieldData::SixtyFourBit(value),
+        ))
+    }
+
+    /// Appends a length-delimited field, such as a `string` or `bytes` value.
+    pub fn push_bytes<T>(&mut self, tag: u32, value: T) -> &mut Self
+    where
+        T: Into<Bytes>,
+    {
+        self.push(UnknownField::new(
+            tag,
+            UnknownFieldData::LengthDelimited(value.into()),
+        ))
+    }
+
+    /// Appends a message field, holding the encoding of the message.
+    pub fn push_message<M>(&mut self, tag: u32, message: &M) -> &mut Self
+    where
+        M: EncodeMessage,
+    {
+        let mut buf = BytesMut::with_capacity(message.encoded_len());
+        message.encode_raw(&mut buf);
+        self.push_bytes(tag, buf.freeze())
+    }
+
+    /// Appends a group field, holding the given fields.
+    pub fn push_group(&mut self, tag: u32, fields: UnknownFieldSet) -> &mut Self {
+        self.push(UnknownField::new(tag, UnknownFieldData::Group(fields)))
+    }
+}
+
+impl<'a> IntoIterator for &'a UnknownFieldSet {
+    type Item = &'a UnknownField;
+    type IntoIter = slice::Iter<'a, UnknownField>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl UnknownField {
+    /// Creates a field.
+    pub fn new(tag: u32, data: UnknownFieldData) -> UnknownField {
+        UnknownField { tag, data }
+    }
+
+    /// Returns the tag of the field.
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Returns the value of the field.
+    pub fn data(&self) -> &UnknownFieldData {
+        &self.data
+    }
+
+    /// Returns the wire type of the field.
+    pub fn wire_type(&self) -> WireType {
+        match self.data {
+            UnknownFieldData::Varint(_) => WireType::Varint,
+            UnknownFieldData::SixtyFourBit(_) => WireType::SixtyFourBit,
+            UnknownFieldData::LengthDelimited(_) => WireType::LengthDelimited,
+            UnknownFieldData::Group(_) => WireType::StartGroup,
+            UnknownFieldData::ThirtyTwoBit(_) => WireType::ThirtyTwoBit,
+        }
+    }
+
+    /// Decodes the value of the field as a nested message of unknown type.
+    ///
+    /// Length-delimited values are decoded, and the fields of a group are returned as they are.
+    /// An error is returned if the field has another wire type, or if the value isn't a valid
+    /// message. Since `string` and `bytes` values share the wire type of messages, and may happen
+    /// to be valid message encodings, success doesn't prove that the field holds a message.
+    pub fn as_message(&self) -> Result<UnknownFieldSet, DecodeError> {
+        match self.data {
+            UnknownFieldData::LengthDelimited(ref value) => UnknownFieldSet::decode(value.clone()),
+            UnknownFieldData::Group(ref fields) => Ok(fields.clone()),
+            _ => Err(DecodeError::new(format!(
+                "field {} is not a message: wire type {:?}",
+                self.tag,
+                self.wire_type()
+            ))),
+        }
+    }
+
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        encode_key(self.tag, self.wire_type(), buf);
+        match self.data {
+            UnknownFieldData::Varint(value) => encode_varint(value, buf),
+            UnknownFieldData::SixtyFourBit(value) => buf.put_u64_le(value),
+            UnknownFieldData::LengthDelimited(ref value) => {
+                encode_varint(value.len() as u64, buf);
+                buf.put_slice(value);
+            }
+            UnknownFieldData::Group(ref fields) => {
+                fields.encode_raw(buf);
+                encode_key(self.tag, WireType::EndGroup, buf);
+            }
+            UnknownFieldData::ThirtyTwoBit(value) => buf.put_u32_le(value),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        key_len(self.tag)
+            + match self.data {
+                UnknownFieldData::Varint(value) => encoded_len_varint(value),
+                UnknownFieldData::SixtyFourBit(_) => 8,
+                UnknownFieldData::LengthDelimited(ref value) => {
+                    encoded_len_varint(value.len() as u64) + value.len()
+                }
+                UnknownFieldData::Group(ref fields) => fields.encoded_len() + key_len(self.tag),
+                UnknownFieldData::ThirtyTwoBit(_) => 4,
+            }
+    }
+}
+
+/// The maximum nesting depth of the sub-messages and groups which are rendered as such by
+/// `Display`; deeper ones are rendered as bytes.
+const DISPLAY_DEPTH_LIMIT: usize = 64;
+
+/// Renders the fields in the text format of [protoscope], one field per line.
+///
+/// Varints are rendered as decimal numbers, 32-bit and 64-bit values as signed numbers with an
+/// `i32` or `i64` suffix, and groups as `!{ ... }`. Length-delimited values are guessed at:
+/// printable UTF-8 is rendered as a quoted string, a valid encoding of a message as the fields of
+/// a nested message, and anything else as hexadecimal bytes.
+///
+/// ```rust
+/// use prost::UnknownFieldSet;
+///
+/// let mut fields = UnknownFieldSet::new();
+/// fields
+///     .push_varint(1, 150)
+///     .push_bytes(2, "hello")
+///     .push_message(3, &150u32);
+/// assert_eq!(fields.to_string(), "1: 150\n2: {\"hello\"}\n3: {\n  1: 150\n}\n");
+/// ```
+///
+/// [protoscope]: https://github.com/protocolbuffers/protoscope
+impl fmt::Display for UnknownFieldSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fields(self, f, 0)
+    }
+}
+
+fn fmt_fields(fields: &UnknownFieldSet, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+    for field in fields {
+        write!(f, "{:indent$}{}: ", "", field.tag, indent = depth * 2)?;
+        match field.data {
+            UnknownFieldData::Varint(value) => writeln!(f, "{}", value)?,
+            UnknownFieldData::SixtyFourBit(value) => writeln!(f, "{}i64", value as i64)?,
+            UnknownFieldData::ThirtyTwoBit(value) => writeln!(f, "{}i32", value as i32)?,
+            UnknownFieldData::Group(ref group) => {
+                if depth < DISPLAY_DEPTH_LIMIT {
+                    writeln!(f, "!{{")?;
+                    fmt_fields(group, f, depth + 1)?;
+                    writeln!(f, "{:indent$}}}", "", indent = depth * 2)?;
+                } else {
+                    let mut buf = Vec::with_capacity(group.encoded_len());
+                    group.encode_raw(&mut buf);
+                    write!(f, "!{{")?;
+                    fmt_bytes(&buf, f)?;
+                    writeln!(f, "}}")?;
+                }
+            }
+            UnknownFieldData::LengthDelimited(ref value) => {
+                if value.is_empty() {
+                    writeln!(f, "{{}}")?;
+                    continue;
+                }
+                if let Ok(text) = str::from_utf8(value) {
+                    if text
+                        .chars()
+                        .all(|c| !c.is_control() || c == '\n' || c == '\t')
+                    {
+                        writeln!(f, "{{{:?}}}", text)?;
+                        continue;
+                    }
+                }
+                if depth < DISPLAY_DEPTH_LIMIT {
+                    if let Ok(nested) = field.as_message() {
+                        writeln!(f, "{{")?;
+                        fmt_fields(&nested, f, depth + 1)?;
+                        writeln!(f, "{:indent$}}}", "", indent = depth * 2)?;
+                        continue;
+                    }
+                }
+                write!(f, "{{")?;
+                fmt_bytes(value, f)?;
+                writeln!(f, "}}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders bytes as a protoscope hexadecimal literal.
+fn fmt_bytes(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("`")?;
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    f.write_str("`")
+}
+
+impl EncodeMessage for UnknownFieldSet {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for field in &self.fields {
+            field.encode_raw(buf);
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.fields.iter().map(UnknownField::encoded_len).sum()
+    }
+}
+
+impl DecodeMessage for UnknownFieldSet {
+    fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(tag, ?wire_type, "retaining unknown protobuf field");
+        let data = match wire_type {
+            WireType::Varint => UnknownFieldData::Varint(decode_varint(buf)?),
+            WireType::SixtyFourBit => {
+                if buf.remaining() < 8 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::Truncated,
+                        "buffer underflow",
+                    ));
+                }
+                UnknownFieldData::SixtyFourBit(buf.get_u64_le())
+            }
+            WireType::LengthDelimited => {
+                let len = ctx.decode_len(buf)?;
+                UnknownFieldData::LengthDelimited(buf.copy_to_bytes(len))
+            }
+            WireType::StartGroup => {
+                let mut fields = UnknownFieldSet::new();
+                group::merge_fields(tag, wire_type, buf, ctx, |tag, wire_type, buf, ctx| {
+                    fields.merge_field(tag, wire_type, buf, ctx)
+                })?;
+                UnknownFieldData::Group(fields)
+            }
+            WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+            WireType::ThirtyTwoBit => {
+                if buf.remaining() < 4 {
+                    return Err(DecodeError::with_kind(
+                        DecodeErrorKind::Truncated,
+                        "buffer underflow",
+                    ));
+                }
+                UnknownFieldData::ThirtyTwoBit(buf.get_u32_le())
+            }
+        };
+        self.fields.push(UnknownField { tag, data });
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.fields.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    use super::*;
+    use crate::encoding::{double, fixed32, float, int32, int64, message, string};
+
+    fn to_vec<M: EncodeMessage>(message: &M) -> Vec<u8> {
+        let mut buf = Vec::new();
+        message.encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buf = Vec::new();
+        string::encode(3, &String::from("first"), &mut buf);
+        int64::encode(1, &-1, &mut buf);
+        group::encode_start(7, &mut buf);
+        fixed32::encode(2, &0xdead_beef, &mut buf);
+        group::encode_end(7, &mut buf);
+        string::encode(3, &String::from("second"), &mut buf);
+        buf.extend_from_slice(&[0x11, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let input = Bytes::from(buf.clone());
+        let fields = UnknownFieldSet::decode(input.clone()).unwrap();
+        let tags: Vec<u32> = fields.iter().map(UnknownField::tag).collect();
+        assert_eq!(tags, vec![3, 1, 7, 3, 2]);
+
+        let data: Vec<&UnknownFieldData> = fields.iter().map(UnknownField::data).collect();
+        assert_eq!(*data[0], UnknownFieldData::LengthDelimited("first".into()));
+        assert_eq!(*data[1], UnknownFieldData::Varint(u64::MAX));
+        assert_eq!(
+            *data[2],
+            UnknownFieldData::Group(UnknownFieldSet {
+                fields: vec![UnknownField::new(
+                    2,
+                    UnknownFieldData::ThirtyTwoBit(0xdead_beef)
+                )],
+            })
+        );
+        assert_eq!(
+            *data[4],
+            UnknownFieldData::SixtyFourBit(0x0807_0605_0403_0201)
+        );
+
+        // Length-delimited values share the input buffer.
+        if let UnknownFieldData::LengthDelimited(ref value) = *data[0] {
+            assert_eq!(value.as_ptr(), input[2..].as_ptr());
+        }
+
+        assert_eq!(fields.encoded_len(), buf.len());
+        assert_eq!(to_vec(&fields), buf);
+    }
+
+    #[test]
+    fn nested_messages() {
+        let inner = to_vec(&String::from("inner"));
+        let mut buf = Vec::new();
+        crate::encoding::bytes::encode(4, &inner, &mut buf);
+        group::encode_start(5, &mut buf);
+        int64::encode(1, &9, &mut buf);
+        group::encode_end(5, &mut buf);
+        int64::encode(6, &9, &mut buf);
+        string::encode(7, &String::from("\u{ff}"), &mut buf);
+
+        let fields = UnknownFieldSet::decode(&buf[..]).unwrap();
+        let fields: Vec<&UnknownField> = fields.iter().collect();
+
+        let nested = fields[0].as_message().unwrap();
+        assert_eq!(nested, UnknownFieldSet::decode(&inner[..]).unwrap());
+        assert_eq!(to_vec(&nested), inner);
+
+        let group = fields[1].as_message().unwrap();
+        assert_eq!(group.iter().next().unwrap().tag(), 1);
+
+        assert!(fields[2].as_message().is_err());
+        // The two bytes of "\u{ff}" aren't a valid key and value.
+        assert!(fields[3].as_message().is_err());
+    }
+
+    #[test]
+    fn builder() {
+        let mut group = UnknownFieldSet::new();
+        group.push_bytes(1, "group");
+        let mut fields = UnknownFieldSet::new();
+        fields
+            .push_varint(1, -7i64 as u64)
+            .push_fixed32(2, 0.5f32.to_bits())
+            .push_fixed64(3, 1.5f64.to_bits())
+            .push_bytes(4, "name")
+            .push_message(5, &String::from("nested"))
+            .push_group(6, group);
+
+        let mut buf = Vec::new();
+        int32::encode(1, &-7, &mut buf);
+        float::encode(2, &0.5, &mut buf);
+        double::encode(3, &1.5, &mut buf);
+        string::encode(4, &String::from("name"), &mut buf);
+        message::encode(5, &String::from("nested"), &mut buf);
+        group::encode(6, &String::from("group"), &mut buf);
+
+        assert_eq!(to_vec(&fields), buf);
+        assert_eq!(UnknownFieldSet::decode(&buf[..]).unwrap(), fields);
+    }
+
+    #[test]
+    fn get_and_tags() {
+        let mut fields = UnknownFieldSet::new();
+        fields
+            .push_varint(3, 1)
+            .push_bytes(1, "name")
+            .push_varint(3, 2);
+        assert_eq!(
+            fields.get(3),
+            Some(&UnknownField::new(3, UnknownFieldData::Varint(2)))
+        );
+        assert_eq!(fields.get(1).unwrap().tag(), 1);
+        assert_eq!(fields.get(2), None);
+        assert_eq!(fields.tags().collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(UnknownFieldSet::new().tags().next(), None);
+    }
+
+    #[test]
+    fn remove_and_take() {
+        let mut fields = UnknownFieldSet::new();
+        fields
+            .push_varint(3, 1)
+            .push_bytes(1, "name")
+            .push_varint(3, 2);
+        assert_eq!(
+            fields.remove(3),
+            Some(UnknownField::new(3, UnknownFieldData::Varint(2)))
+        );
+        assert_eq!(fields.remove(3), None);
+        assert_eq!(fields.tags().collect::<Vec<_>>(), [1]);
+
+        let taken = fields.take();
+        assert!(fields.is_empty());
+        assert_eq!(taken.get(1).unwrap().tag(), 1);
+    }
+
+    #[test]
+    fn merge() {
+        let mut first = UnknownFieldSet::new();
+        first.push_varint(1, 1).push_bytes(2, "a");
+        let mut second = UnknownFieldSet::new();
+        second.push_varint(1, 2).push_bytes(2, "b");
+
+        let mut buf = to_vec(&first);
+        buf.extend_from_slice(&to_vec(&second));
+        first.merge(&second);
+        assert_eq!(first, UnknownFieldSet::decode(&buf[..]).unwrap());
+        assert_eq!(
+            first.get(1),
+            Some(&UnknownField::new(1, UnknownFieldData::Varint(2)))
+        );
+        assert_eq!(first.len(), 4);
+    }
+
+    #[test]
+    fn encode_tag_to_bytes() {
+        let mut buf = Vec::new();
+        int64::encode(1, &5, &mut buf);
+        let first = buf.len();
+        string::encode(2, &String::from("skipped"), &mut buf);
+        let second = buf.len();
+        int64::encode(1, &300, &mut buf);
+
+        let fields = UnknownFieldSet::decode(&buf[..]).unwrap();
+        let mut expected = buf[..first].to_vec();
+        expected.extend_from_slice(&buf[second..]);
+        assert_eq!(fields.encode_tag_to_bytes(1).unwrap(), expected);
+        assert_eq!(fields.encode_tag_to_bytes(2).unwrap(), buf[first..second]);
+        assert_eq!(fields.encode_tag_to_bytes(3), None);
+        assert_eq!(fields.tag_encoded_len(1), expected.len());
+        assert_eq!(fields.tag_encoded_len(3), 0);
+
+        // A non-minimal varint is written in its shortest form.
+        let fields = UnknownFieldSet::decode(&[0x08, 0x85, 0x00][..]).unwrap();
+        assert_eq!(fields.encode_tag_to_bytes(1).unwrap(), [0x08, 0x05][..]);
+    }
+
+    #[test]
+    fn display() {
+        let mut group = UnknownFieldSet::new();
+        group.push_fixed32(1, -2i32 as u32);
+        let mut fields = UnknownFieldSet::new();
+        fields
+            .push_fixed64(1, 5)
+            .push_group(2, group)
+            .push_bytes(3, "")
+            .push_bytes(4, &b"\xff\x00"[..])
+            .push_bytes(5, "tab\t\"quote\"");
+        assert_eq!(
+            fields.to_string(),
+            "1: 5i64\n2: !{\n  1: -2i32\n}\n3: {}\n4: {`ff00`}\n5: {\"tab\\t\\\"quote\\\"\"}\n"
+        );
+
+        // Deeply nested messages are eventually rendered as bytes.
+        let mut nested = UnknownFieldSet::new();
+        nested.push_varint(1, 1);
+        for _ in 0..DISPLAY_DEPTH_LIMIT + 1 {
+            let mut outer = UnknownFieldSet::new();
+            outer.push_message(1, &nested);
+            nested = outer;
+        }
+        assert!(nested.to_string().contains('`'));
+
+        // So are deeply nested groups.
+        let mut nested = UnknownFieldSet::new();
+        nested.push_varint(1, 1);
+        for _ in 0..DISPLAY_DEPTH_LIMIT + 1 {
+            let mut outer = UnknownFieldSet::new();
+            outer.push_group(1, nested);
+            nested = outer;
+        }
+        let text = nested.to_string();
+        assert_eq!(text.matches("!{\n").count(), DISPLAY_DEPTH_LIMIT);
+        assert!(text.contains("!{`0801`}"));
+    }
+
+    #[test]
+    fn canonicalize() {
+        // A non-minimal varint key and value.
+        let mut buf = vec![0x98, 0x00, 0x81, 0x80, 0x00];
+        let mut group = UnknownFieldSet::new();
+        group.push_varint(2, 1).push_varint(1, 1);
+        let mut fields = UnknownFieldSet::new();
+        fields
+            .push_bytes(2, "b")
+            .push_group(1, group)
+            .push_bytes(2, "a");
+        buf.extend_from_slice(&to_vec(&fields));
+
+        let mut fields = UnknownFieldSet::decode(&buf[..]).unwrap();
+        fields.canonicalize();
+
+        let mut group = UnknownFieldSet::new();
+        group.push_varint(1, 1).push_varint(2, 1);
+        let mut expected = UnknownFieldSet::new();
+        expected
+            .push_group(1, group)
+            .push_bytes(2, "b")
+            .push_bytes(2, "a")
+            .push_varint(3, 1);
+        assert_eq!(fields, expected);
+        assert_eq!(to_vec(&fields), to_vec(&expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid field number: 0")]
+    fn builder_invalid_tag() {
+        UnknownFieldSet::new().push_varint(0, 1);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(UnknownFieldSet::decode(&[0x0c][..]).is_err());
+        assert!(UnknownFieldSet::decode(&[0x0b, 0x08, 0x01][..]).is_err());
+        assert!(UnknownFieldSet::decode(&[0x0d, 0x01, 0x02][..]).is_err());
+        assert!(UnknownFieldSet::decode(&[0x0a, 0x05, 0x01][..]).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use alloc::vec;
+
+use prost::bytes::Bytes;
+use prost::{Blob, DecodeMessage, EncodeMessage};
+
+mod blob {
+    include!(concat!(env!("OUT_DIR"), "/blob.rs"));
+}
+
+use self::blob::Attachment;
+
+#[test]
+fn test_blob_shares_decode_buffer() {
+    let message = Attachment {
+        name: "report.pdf".into(),
+        content: Blob::from(vec![7; 1024]),
+        chunks: vec![Blob::from(&b"one"[..]), Blob::from(&b"two"[..])],
+    };
+    let encoded = Bytes::from(message.encode_to_vec());
+
+    let mut decoded = Attachment::decode(encoded.clone()).unwrap();
+    assert_eq!(decoded, message);
+    assert!(decoded.content.is_shared());
+    assert!(decoded.chunks.iter().all(Blob::is_shared));
+    let offset = decoded.content.as_bytes().as_ptr() as usize - encoded.as_ptr() as usize;
+    assert!(offset < encoded.len());
+
+    // Modifying a blob copies it out of the buffer.
+    decoded.content.to_mut().truncate(16);
+    assert!(!decoded.content.is_shared());
+    assert_eq!(decoded.content.as_bytes(), &[7; 16][..]);
+
+    // Decoding from a slice copies each value.
+    let copied = Attachment::decode(&*encoded).unwrap();
+    assert_eq!(copied, message);
+}
@@ -7,7 +7,7 @@ use std::io::Read;
 use std::io::Write;
 use std::path::Path;
 
-/// Test which bootstraps protobuf.rs and compiler.rs from the .proto definitions in the Protobuf
+/// Test which bootstraps descriptor.rs, protobuf.rs and compiler.rs from the .proto definitions in the Protobuf
 /// repo. Ensures that the checked-in compiled versions are up-to-date.
 #[test]
 fn bootstrap() {
@@ -20,15 +20,32 @@ fn bootstrap() {
         .tempdir()
         .unwrap();
 
+    // The descriptor types and the well-known types are compiled separately, since they are
+    // included by different prost-types features.
+    let descriptor_dir = tempdir.path().join("descriptor");
+    fs::create_dir(&descriptor_dir).unwrap();
     prost_build::Config::new()
         .compile_well_known_types()
         .btree_map(&["."])
-        .out_dir(tempdir.path())
+        .out_dir(&descriptor_dir)
         .compile_protos(
             &[
                 // Protobuf Plugins.
                 protobuf.join("descriptor.proto"),
                 protobuf.join("compiler").join("plugin.proto"),
+            ],
+            &[""],
+        )
+        .unwrap();
+
+    let well_known_types_dir = tempdir.path().join("well_known_types");
+    fs::create_dir(&well_known_types_dir).unwrap();
+    prost_build::Config::new()
+        .compile_well_known_types()
+        .btree_map(&["."])
+        .out_dir(&well_known_types_dir)
+        .compile_protos(
+            &[
                 // Well-known Types (except wrapper.proto, whose types are substituted
                 // for the corresponding standard library types).
                 protobuf.join("any.proto"),
@@ -44,14 +61,20 @@ fn bootstrap() {
         )
         .unwrap();
 
+    let mut bootstrapped_descriptor = String::new();
+    fs::File::open(descriptor_dir.join("google.protobuf.rs"))
+        .unwrap()
+        .read_to_string(&mut bootstrapped_descriptor)
+        .unwrap();
+
     let mut bootstrapped_protobuf = String::new();
-    fs::File::open(tempdir.path().join("google.protobuf.rs"))
+    fs::File::open(well_known_types_dir.join("google.protobuf.rs"))
         .unwrap()
         .read_to_string(&mut bootstrapped_protobuf)
         .unwrap();
 
     let mut bootstrapped_compiler = String::new();
-    fs::File::open(tempdir.path().join("google.protobuf.compiler.rs"))
+    fs::File::open(descriptor_dir.join("google.protobuf.compiler.rs"))
         .unwrap()
         .read_to_string(&mut bootstrapped_compiler)
         .unwrap();
@@ -62,6 +85,12 @@ fn bootstrap() {
         .join("prost-types")
         .join("src");
 
+    let mut descriptor = String::new();
+    fs::File::open(src.join("descriptor.rs"))
+        .unwrap()
+        .read_to_string(&mut descriptor)
+        .unwrap();
+
     let mut protobuf = String::new();
     fs::File::open(src.join("protobuf.rs"))
         .unwrap()
@@ -74,6 +103,12 @@ fn bootstrap() {
         .read_to_string(&mut compiler)
         .unwrap();
 
+    if descriptor != bootstrapped_descriptor {
+        fs::File::create(src.join("descriptor.rs"))
+            .unwrap()
+            .write_all(bootstrapped_descriptor.as_bytes())
+            .unwrap();
+    }
     if protobuf != bootstrapped_protobuf {
         fs::File::create(src.join("protobuf.rs"))
             .unwrap()
@@ -87,6 +122,7 @@ fn bootstrap() {
             .unwrap();
     }
 
+    assert_eq!(descriptor, bootstrapped_descriptor);
     assert_eq!(protobuf, bootstrapped_protobuf);
     assert_eq!(compiler, bootstrapped_compiler);
 }
@@ -0,0 +1,184 @@
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{
+    DecodeBorrowed, DecodeConfig, DecodeErrorKind, DecodeMessage, DuplicateFieldPolicy,
+    EncodeMessage, InvalidUtf8Policy,
+};
+
+#[derive(Clone, PartialEq, prost::EncodeMessage, prost::DecodeBorrowed)]
+struct Document<'a> {
+    #[prost(string, borrowed, tag = "1")]
+    title: Cow<'a, str>,
+    #[prost(bytes, borrowed, tag = "2")]
+    body: &'a [u8],
+    #[prost(string, borrowed, repeated, tag = "3")]
+    tags: Vec<Cow<'a, str>>,
+    #[prost(string, borrowed, optional, tag = "4", default = "untitled")]
+    subtitle: Option<Cow<'a, str>>,
+    #[prost(message, repeated, tag = "5")]
+    sections: Vec<Section<'a>>,
+    #[prost(message, optional, tag = "6")]
+    author: Option<Author>,
+    #[prost(uint32, tag = "7")]
+    version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::EncodeMessage, prost::DecodeBorrowed)]
+struct Section<'a> {
+    #[prost(string, borrowed, required, tag = "1")]
+    heading: Cow<'a, str>,
+    #[prost(bytes, borrowed, repeated, tag = "2")]
+    paragraphs: Vec<&'a [u8]>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Author {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+/// The owned equivalent of `Document`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct OwnedDocument {
+    #[prost(string, tag = "1")]
+    title: String,
+    #[prost(bytes, tag = "2")]
+    body: Vec<u8>,
+    #[prost(string, repeated, tag = "3")]
+    tags: Vec<String>,
+    #[prost(string, optional, tag = "4")]
+    subtitle: Option<String>,
+    #[prost(message, repeated, tag = "5")]
+    sections: Vec<OwnedSection>,
+    #[prost(message, optional, tag = "6")]
+    author: Option<Author>,
+    #[prost(uint32, tag = "7")]
+    version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct OwnedSection {
+    #[prost(string, required, tag = "1")]
+    heading: String,
+    #[prost(bytes, repeated, tag = "2")]
+    paragraphs: Vec<Vec<u8>>,
+}
+
+fn owned_document() -> OwnedDocument {
+    OwnedDocument {
+        title: "Borrowing".to_string(),
+        body: vec![0xde, 0xad, 0xbe, 0xef],
+        tags: vec!["zero".to_string(), "copy".to_string()],
+        subtitle: None,
+        sections: vec![OwnedSection {
+            heading: "Introduction".to_string(),
+            paragraphs: vec![b"first".to_vec(), b"second".to_vec()],
+        }],
+        author: Some(Author {
+            name: "Ada".to_string(),
+        }),
+        version: 3,
+    }
+}
+
+fn encode<M>(message: &M) -> Vec<u8>
+where
+    M: EncodeMessage,
+{
+    let mut buf = Vec::new();
+    message.encode(&mut buf).unwrap();
+    buf
+}
+
+/// Returns `true` if `value` points into `buf`.
+fn borrows_from(value: &[u8], buf: &[u8]) -> bool {
+    let start = buf.as_ptr() as usize;
+    let value_start = value.as_ptr() as usize;
+    value_start >= start && value_start + value.len() <= start + buf.len()
+}
+
+#[test]
+fn decode_borrowed() {
+    let buf = encode(&owned_document());
+    let document = Document::decode_borrowed(&buf).unwrap();
+
+    assert_eq!(document.title, "Borrowing");
+    assert!(matches!(document.title, Cow::Borrowed(_)));
+    assert!(borrows_from(document.title.as_bytes(), &buf));
+    assert_eq!(document.body, [0xde, 0xad, 0xbe, 0xef]);
+    assert!(borrows_from(document.body, &buf));
+    assert_eq!(document.tags, ["zero", "copy"]);
+    assert_eq!(document.subtitle, None);
+    assert_eq!(document.subtitle(), "untitled");
+    assert_eq!(document.sections.len(), 1);
+    assert_eq!(document.sections[0].heading, "Introduction");
+    assert_eq!(
+        document.sections[0].paragraphs,
+        [&b"first"[..], &b"second"[..]]
+    );
+    assert!(borrows_from(document.sections[0].paragraphs[1], &buf));
+    assert_eq!(document.author.as_ref().unwrap().name, "Ada");
+    assert_eq!(document.version, 3);
+
+    // Borrowed messages encode like their owned equivalents.
+    assert_eq!(document.encoded_len(), buf.len());
+    assert_eq!(encode(&document), buf);
+}
+
+#[test]
+fn default_values() {
+    let document = Document::decode_borrowed(&[]).unwrap();
+    assert_eq!(document, Document::default());
+    assert_eq!(document.title, "");
+    assert!(document.body.is_empty());
+    assert_eq!(document.subtitle(), "untitled");
+    assert_eq!(document.encoded_len(), 0);
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(1, &b"caf\xe9".to_vec(), &mut buf);
+
+    let error = Document::decode_borrowed(&buf).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::InvalidUtf8);
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Document.title: \
+         invalid string value: data is not UTF-8 encoded"
+    );
+
+    let config = DecodeConfig::new().invalid_utf8(InvalidUtf8Policy::Replace);
+    let document = Document::decode_borrowed_with_config(&buf, config).unwrap();
+    assert_eq!(document.title, "caf\u{FFFD}");
+    assert!(matches!(document.title, Cow::Owned(_)));
+}
+
+#[test]
+fn decode_policies() {
+    let mut buf = encode(&owned_document());
+    prost::encoding::uint32::encode(7, &4, &mut buf);
+
+    // The last value of a repeated non-repeated field wins, unless duplicates are rejected.
+    assert_eq!(Document::decode_borrowed(&buf).unwrap().version, 4);
+    let reject = DecodeConfig::new().duplicate_fields(DuplicateFieldPolicy::Reject);
+    assert!(Document::decode_borrowed_with_config(&buf, reject).is_err());
+
+    // Nested messages are held to the same recursion limit.
+    let limited = DecodeConfig::new().recursion_limit(0);
+    assert!(OwnedDocument::decode_with_config(&buf[..], limited).is_err());
+    assert!(Document::decode_borrowed_with_config(&buf, limited).is_err());
+}
+
+#[test]
+fn owned_messages() {
+    // Messages owning their fields implement `DecodeBorrowed` as well.
+    let buf = encode(&owned_document());
+    assert_eq!(
+        OwnedDocument::decode_borrowed(&buf).unwrap(),
+        OwnedDocument::decode(&buf[..]).unwrap()
+    );
+}
@@ -104,6 +104,80 @@ fn main() {
             .unwrap();
     }
 
+    prost_build::Config::new()
+        .open_enums([".open_enums.Account"])
+        .compile_protos(&[src.join("open_enums.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .reflect(["."])
+        .compile_protos(&[src.join("reflect.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .compile_protos(&[src.join("required_fields.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .cached_size([".cached_size.Tree", ".cached_size.Leaf"])
+        .compile_protos(&[src.join("cached_size.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .blob([".blob.Attachment.content", ".blob.Attachment.chunks"])
+        .compile_protos(&[src.join("blob.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(&["."])
+        .lossy_string([".lossy_string.Lossy"])
+        .compile_protos(&[src.join("lossy_string.proto")], includes)
+        .unwrap();
+
+    const XOR_CODEC: &str = "crate::bytes_codec::Xor";
+    prost_build::Config::new()
+        .bytes_codec(".bytes_codec.Record.secret", XOR_CODEC)
+        .bytes_codec(".bytes_codec.Record.secrets", XOR_CODEC)
+        .bytes_codec(".bytes_codec.Record.oneof_secret", XOR_CODEC)
+        .compile_protos(&[src.join("bytes_codec.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .message_ranges()
+        .compile_protos(&[src.join("message_ranges.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .constructor(".constructors.Person", Vec::<&str>::new())
+        .constructor(
+            ".constructors.Contact",
+            ["label", "numbers", "kind", "person", "address"],
+        )
+        .compile_protos(&[src.join("constructors.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .schema_hash()
+        .compile_protos(&[src.join("schema_hash.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .descriptors()
+        .compile_protos(&[src.join("descriptors.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .compile_protos(&[src.join("enum_aliases.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .http_bindings()
+        .compile_protos(&[src.join("http_bindings.proto")], includes)
+        .unwrap();
+
     config
         .compile_protos(&[src.join("well_known_types.proto")], includes)
         .unwrap();
@@ -0,0 +1,74 @@
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{BytesCodec, DecodeError, DecodeMessage, EncodeMessage};
+
+mod bytes_codec {
+    include!(concat!(env!("OUT_DIR"), "/bytes_codec.rs"));
+}
+
+use self::bytes_codec::{plain_record, record, PlainRecord, Record};
+
+/// XORs every byte with a fixed key, and prefixes the result with a marker byte.
+pub struct Xor;
+
+const MARKER: u8 = 0xAA;
+const KEY: u8 = 0x5C;
+
+impl BytesCodec for Xor {
+    fn encode(value: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(value.len() + 1);
+        encoded.push(MARKER);
+        encoded.extend(value.iter().map(|b| b ^ KEY));
+        encoded
+    }
+
+    fn encoded_len(value: &[u8]) -> usize {
+        value.len() + 1
+    }
+
+    fn decode(value: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        match value.split_first() {
+            Some((&MARKER, rest)) => Ok(rest.iter().map(|b| b ^ KEY).collect()),
+            _ => Err(DecodeError::new("missing codec marker")),
+        }
+    }
+}
+
+#[test]
+fn test_bytes_codec_transforms_wire_value() {
+    let record = Record {
+        secret: b"secret".to_vec(),
+        secrets: vec![b"a".to_vec(), Vec::new()],
+        public: b"public".to_vec(),
+        kind: Some(record::Kind::OneofSecret(b"oneof".to_vec())),
+    };
+    let encoded = record.encode_to_vec();
+    assert_eq!(encoded.len(), record.encoded_len());
+
+    // The wire representation holds the transformed values.
+    let plain = PlainRecord::decode(&*encoded).unwrap();
+    assert_eq!(plain.secret, Xor::encode(b"secret"));
+    assert_eq!(plain.secrets, vec![Xor::encode(b"a"), Xor::encode(b"")]);
+    assert_eq!(plain.public, b"public".to_vec());
+    assert_eq!(
+        plain.kind,
+        Some(plain_record::Kind::OneofSecret(Xor::encode(b"oneof")))
+    );
+
+    assert_eq!(Record::decode(&*encoded).unwrap(), record);
+}
+
+#[test]
+fn test_bytes_codec_decode_error() {
+    let plain = PlainRecord {
+        secret: b"not transformed".to_vec(),
+        ..PlainRecord::default()
+    };
+    let error = Record::decode(&*plain.encode_to_vec()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Record.secret: missing codec marker"
+    );
+}
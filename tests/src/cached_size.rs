@@ -0,0 +1,84 @@
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{DecodeMessage, EncodeMessage};
+
+mod cached_size {
+    include!(concat!(env!("OUT_DIR"), "/cached_size.rs"));
+}
+
+use self::cached_size::{Leaf, Plain, Tree};
+
+fn leaf(data: &[u8]) -> Leaf {
+    Leaf {
+        data: data.to_vec(),
+        ..Leaf::default()
+    }
+}
+
+fn tree() -> Tree {
+    Tree {
+        name: "root".to_string(),
+        children: vec![
+            Tree {
+                name: "a".to_string(),
+                leaf: Some(leaf(b"abc")),
+                ..Tree::default()
+            },
+            Tree {
+                name: "b".to_string(),
+                plain: Some(Plain {
+                    leaf: Some(leaf(&[0; 200])),
+                }),
+                ..Tree::default()
+            },
+        ],
+        ..Tree::default()
+    }
+}
+
+#[test]
+fn encoded_len_fills_caches() {
+    let tree = tree();
+    assert_eq!(tree._cached_size.get(), None);
+
+    let len = tree.encoded_len();
+    assert_eq!(tree._cached_size.get(), Some(len));
+    for child in &tree.children {
+        assert_eq!(child._cached_size.get(), Some(child.encoded_len()));
+    }
+    let leaf = tree.children[1]
+        .plain
+        .as_ref()
+        .unwrap()
+        .leaf
+        .as_ref()
+        .unwrap();
+    assert_eq!(leaf._cached_size.get(), Some(203));
+}
+
+#[test]
+fn encode_with_cached_sizes() {
+    let mut tree = tree();
+    let buf = tree.encode_to_vec();
+    assert_eq!(buf.len(), tree.encoded_len());
+    assert_eq!(Tree::decode(&buf[..]).unwrap(), tree);
+
+    // Stale caches are refreshed when the message is encoded again.
+    tree.children[0].leaf.as_mut().unwrap().data = vec![1; 300];
+    let mut buf = Vec::new();
+    tree.encode(&mut buf).unwrap();
+    assert_eq!(Tree::decode(&buf[..]).unwrap(), tree);
+    let mut buf = Vec::new();
+    tree.encode_length_delimited(&mut buf).unwrap();
+    assert_eq!(Tree::decode_length_delimited(&buf[..]).unwrap(), tree);
+}
+
+#[test]
+fn caches_are_ignored_by_comparisons() {
+    let sized = tree();
+    sized.encoded_len();
+    assert_eq!(sized, tree());
+    assert_eq!(sized.clone()._cached_size.get(), sized._cached_size.get());
+}
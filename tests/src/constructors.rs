@@ -0,0 +1,38 @@
+mod constructors {
+    include!(concat!(env!("OUT_DIR"), "/constructors.rs"));
+}
+
+use alloc::string::ToString;
+use alloc::vec;
+
+use self::constructors::{contact, Contact, Kind, Person};
+
+#[test]
+fn test_required_fields_constructor() {
+    let person = Person::new("Ferris", 7);
+    assert_eq!(person.name, "Ferris");
+    assert_eq!(person.id, 7);
+    assert_eq!(person.email, None);
+}
+
+#[test]
+fn test_chosen_fields_constructor() {
+    let person = Person::new("Ferris", 7);
+    let contact = Contact::new(
+        "work".to_string(),
+        vec![1, 2],
+        Kind::Friend as i32,
+        person.clone(),
+        contact::Address::Email("ferris@example.com".to_string()),
+    );
+    assert_eq!(contact.label.as_deref(), Some("work"));
+    assert_eq!(contact.numbers, [1, 2]);
+    assert_eq!(contact.kind(), Kind::Friend);
+    assert_eq!(contact.person, Some(person));
+    assert!(contact.tags.is_empty());
+    assert_eq!(
+        contact.address,
+        Some(contact::Address::Email("ferris@example.com".to_string()))
+    );
+    assert_eq!(contact.favorite, None);
+}
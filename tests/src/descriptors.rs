@@ -0,0 +1,43 @@
+mod descriptors {
+    include!(concat!(env!("OUT_DIR"), "/descriptors.rs"));
+}
+
+use self::descriptors::descriptors::{
+    file_descriptor_set, message_descriptor, service_descriptor, MESSAGE_FULL_NAMES, SERVICE_NAMES,
+};
+
+#[test]
+fn test_descriptors() {
+    assert_eq!(
+        MESSAGE_FULL_NAMES,
+        [
+            "descriptors.Order",
+            "descriptors.Order.Line",
+            "descriptors.Receipt"
+        ]
+    );
+    assert_eq!(SERVICE_NAMES, ["descriptors.Checkout"]);
+
+    let file_descriptor_set = file_descriptor_set();
+    assert_eq!(file_descriptor_set.file.len(), 1);
+    assert_eq!(file_descriptor_set.file[0].name(), "descriptors.proto");
+    assert!(file_descriptor_set.file[0].source_code_info.is_none());
+
+    for &full_name in MESSAGE_FULL_NAMES {
+        let descriptor = message_descriptor(full_name).unwrap();
+        assert!(full_name.ends_with(descriptor.name()));
+    }
+    assert_eq!(
+        message_descriptor("descriptors.Order.Line")
+            .unwrap()
+            .field
+            .len(),
+        2
+    );
+    assert!(message_descriptor("descriptors.Order.LabelsEntry").is_none());
+    assert!(message_descriptor("Order").is_none());
+
+    let checkout = service_descriptor("descriptors.Checkout").unwrap();
+    assert_eq!(checkout.method[0].name(), "PlaceOrder");
+    assert!(service_descriptor("descriptors.Other").is_none());
+}
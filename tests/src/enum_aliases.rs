@@ -0,0 +1,21 @@
+mod enum_aliases {
+    include!(concat!(env!("OUT_DIR"), "/enum_aliases.rs"));
+}
+
+use self::enum_aliases::{Job, Status};
+
+#[test]
+fn test_enum_aliases() {
+    assert_eq!(Status::Running, Status::Started);
+    assert_eq!(Status::Finished, Status::Done);
+    assert_eq!(Status::Complete, Status::Done);
+
+    assert_eq!(Status::from_i32(1), Some(Status::Running));
+    assert_eq!(Status::from_i32(2), Some(Status::Complete));
+
+    let job = Job {
+        status: Status::Finished as i32,
+    };
+    assert_eq!(job.status(), Status::Done);
+    assert!(matches!(job.status(), Status::Complete));
+}
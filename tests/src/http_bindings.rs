@@ -0,0 +1,70 @@
+use alloc::vec::Vec;
+
+use prost_types::http::HttpBinding;
+
+mod http_bindings {
+    include!(concat!(env!("OUT_DIR"), "/http_bindings.rs"));
+}
+
+use self::http_bindings::{archive_http, archive_http_, library_http};
+
+#[test]
+fn test_http_bindings() {
+    assert_eq!(
+        library_http::GET_SHELF,
+        &[
+            HttpBinding {
+                verb: "GET",
+                path: "/v1/{name=shelves/*}",
+                body: "",
+                response_body: "",
+            },
+            HttpBinding {
+                verb: "GET",
+                path: "/v1/shelves:byName/{name}",
+                body: "",
+                response_body: "",
+            },
+        ]
+    );
+    assert_eq!(
+        library_http::UPDATE_SHELF,
+        &[HttpBinding {
+            verb: "PATCH",
+            path: "/v1/{shelf.name=shelves/*}",
+            body: "shelf",
+            response_body: "",
+        }]
+    );
+    assert_eq!(
+        library_http::PURGE,
+        &[HttpBinding {
+            verb: "PURGE",
+            path: "/v1/shelves",
+            body: "",
+            response_body: "*",
+        }]
+    );
+
+    let methods = library_http::bindings()
+        .iter()
+        .map(|&(method, _)| method)
+        .collect::<Vec<_>>();
+    assert_eq!(methods, &["GetShelf", "UpdateShelf", "Purge"]);
+}
+
+#[test]
+fn test_http_bindings_names() {
+    // The message module keeps its name, and the method constant doesn't clash with the table.
+    let _ = archive_http::Entry {};
+    assert_eq!(
+        archive_http_::BINDINGS,
+        &[HttpBinding {
+            verb: "GET",
+            path: "/v1/bindings",
+            body: "",
+            response_body: "",
+        }]
+    );
+    assert_eq!(archive_http_::bindings()[0].0, "Bindings");
+}
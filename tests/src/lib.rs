@@ -30,19 +30,47 @@ pub mod no_root_packages;
 pub mod packages;
 pub mod unittest;
 
+#[cfg(test)]
+mod blob;
 #[cfg(test)]
 mod bootstrap;
 #[cfg(test)]
+mod borrowed;
+#[cfg(test)]
+mod bytes_codec;
+#[cfg(test)]
+mod cached_size;
+#[cfg(test)]
+mod constructors;
+#[cfg(test)]
 mod debug;
 #[cfg(test)]
 mod deprecated_field;
 #[cfg(test)]
+mod descriptors;
+#[cfg(test)]
+mod enum_aliases;
+#[cfg(test)]
 mod generic_derive;
 #[cfg(test)]
+mod http_bindings;
+#[cfg(test)]
+mod lossy_string;
+#[cfg(test)]
 mod message_encoding;
 #[cfg(test)]
+mod message_ranges;
+#[cfg(test)]
 mod no_unused_results;
 #[cfg(test)]
+mod open_enums;
+#[cfg(test)]
+mod reflect;
+#[cfg(test)]
+mod required_fields;
+#[cfg(test)]
+mod schema_hash;
+#[cfg(test)]
 mod well_known_types;
 
 pub mod foo {
@@ -253,6 +281,7 @@ mod tests {
 
     use super::*;
 
+    use prost::{DecodeMessage, EncodeMessage};
     use protobuf::test_messages::proto3::TestAllTypesProto3;
 
     #[test]
@@ -406,6 +435,26 @@ mod tests {
         assert!(build_and_roundtrip(101).is_err());
     }
 
+    #[test]
+    fn test_deep_nesting_configured_limit() {
+        use crate::nesting::A;
+        use prost::DecodeConfig;
+
+        let mut a = Box::new(A::default());
+        for _ in 0..200 {
+            let mut next = Box::new(A::default());
+            next.a = Some(a);
+            a = next;
+        }
+        let buf = a.encode_to_vec();
+
+        assert!(A::decode(&*buf).is_err());
+        let config = DecodeConfig::new().recursion_limit(200);
+        assert!(A::decode_with_config(&*buf, config).is_ok());
+        let config = DecodeConfig::new().recursion_limit(199);
+        assert!(A::decode_with_config(&*buf, config).is_err());
+    }
+
     #[test]
     fn test_deep_nesting_oneof() {
         fn build_and_roundtrip(depth: usize) -> Result<(), prost::DecodeError> {
@@ -513,7 +562,7 @@ mod tests {
         //
         // https://github.com/tokio-rs/prost/issues/267
         let buf = vec![b'C'; 1 << 20];
-        <() as Message>::decode(&buf[..]).err().unwrap();
+        <() as DecodeMessage>::decode(&buf[..]).err().unwrap();
     }
 
     #[test]
@@ -0,0 +1,58 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{DecodeMessage, EncodeMessage, LossyString};
+
+mod lossy_string {
+    include!(concat!(env!("OUT_DIR"), "/lossy_string.rs"));
+}
+
+use self::lossy_string::{lossy, Lossy, Strict};
+
+#[test]
+fn test_lossy_string_accepts_invalid_utf8() {
+    let message = Lossy {
+        optional_string: Some(LossyString::from(&b"bad \xff"[..])),
+        required_string: LossyString::from("good"),
+        repeated_string: vec![LossyString::from(&b"\xc3"[..]), LossyString::from("ok")],
+        string_map: Default::default(),
+        kind: Some(lossy::Kind::OneofString(LossyString::from(&b"\xfe"[..]))),
+    };
+    let encoded = message.encode_to_vec();
+
+    // The strict message rejects the invalid UTF-8.
+    assert!(Strict::decode(&*encoded).is_err());
+
+    // The lossy message decodes it, and re-encodes the original bytes.
+    let decoded = Lossy::decode(&*encoded).unwrap();
+    assert_eq!(decoded, message);
+    assert_eq!(decoded.encode_to_vec(), encoded);
+    assert_eq!(decoded.optional_string().to_vec(), b"bad \xff".to_vec());
+    assert_eq!(
+        decoded.optional_string.unwrap().to_string_lossy(),
+        "bad \u{FFFD}"
+    );
+}
+
+#[test]
+fn test_lossy_string_is_wire_compatible() {
+    let strict = Strict {
+        optional_string: None,
+        required_string: "required".into(),
+        repeated_string: vec!["a".into(), "b".into()],
+        string_map: Default::default(),
+        kind: Some(lossy_string::strict::Kind::OneofString("oneof".into())),
+    };
+    let encoded = strict.encode_to_vec();
+    let lossy = Lossy::decode(&*encoded).unwrap();
+    assert_eq!(lossy.required_string.to_str(), Ok("required"));
+    assert_eq!(lossy.optional_string(), "café".as_bytes());
+    assert_eq!(lossy.encode_to_vec(), encoded);
+
+    let strings = lossy
+        .repeated_string
+        .iter()
+        .map(|s| s.to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(strings, &["a", "b"]);
+}
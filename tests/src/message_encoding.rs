@@ -1,6 +1,9 @@
-use bytes::Bytes;
-use prost::alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
-use prost::{Enumeration, Message, Oneof};
+use bytes::{Buf, BufMut, Bytes};
+use prost::alloc::{borrow::ToOwned, format, string::String, sync::Arc, vec, vec::Vec};
+use prost::{
+    BytesAdapter, DecodeConfig, DecodeErrorKind, DecodeMessage, DuplicateFieldPolicy,
+    EncodeMessage, Enumeration, MapContainer, Message, Oneof, ReservedWireTypePolicy, WireType,
+};
 
 use crate::check_message;
 use crate::check_serialize_equivalent;
@@ -387,3 +390,550 @@ pub enum BasicOneof {
     #[prost(string, tag = "9")]
     String(String),
 }
+
+/// A reference counted, immutable bytes type used through `prost::BytesAdapter`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SharedBytes(Arc<[u8]>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl BytesAdapter for SharedBytes {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn replace_with<B>(&mut self, mut buf: B)
+    where
+        B: Buf,
+    {
+        self.0 = buf.copy_to_bytes(buf.remaining()).as_ref().into();
+    }
+
+    fn append_to<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        buf.put_slice(&self.0)
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CustomBytes {
+    #[prost(bytes = "custom(SharedBytes)", tag = "1")]
+    pub plain: SharedBytes,
+
+    #[prost(
+        bytes = "custom(SharedBytes)",
+        optional,
+        tag = "2",
+        default = "b\"foo\""
+    )]
+    pub optional: Option<SharedBytes>,
+
+    #[prost(bytes = "custom(SharedBytes)", repeated, tag = "3")]
+    pub repeated: Vec<SharedBytes>,
+}
+
+#[test]
+fn check_custom_bytes() {
+    let msg = CustomBytes {
+        plain: SharedBytes(Arc::from(&b"plain"[..])),
+        optional: None,
+        repeated: vec![SharedBytes::default(), SharedBytes(Arc::from(&b"x"[..]))],
+    };
+    assert_eq!(msg.optional(), b"foo");
+    check_message(&msg);
+    check_message(&CustomBytes::default());
+    assert_eq!(CustomBytes::default().encoded_len(), 0);
+
+    let bytes = BytesMsg {
+        plain: Bytes::from_static(b"plain"),
+        optional: None,
+        repeated: vec![Bytes::new(), Bytes::from_static(b"x")],
+    };
+    check_serialize_equivalent(&msg, &bytes);
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BytesMsg {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub plain: Bytes,
+
+    #[prost(bytes = "bytes", optional, tag = "2")]
+    pub optional: Option<Bytes>,
+
+    #[prost(bytes = "bytes", repeated, tag = "3")]
+    pub repeated: Vec<Bytes>,
+}
+
+#[test]
+fn reject_duplicate_fields() {
+    let reject = DecodeConfig::new().duplicate_fields(DuplicateFieldPolicy::Reject);
+
+    let basic = Basic {
+        int32: 1,
+        bools: vec![true, false],
+        ..Basic::default()
+    };
+    let mut buf = basic.encode_to_vec();
+    assert_eq!(Basic::decode_with_config(&buf[..], reject).unwrap(), basic);
+
+    // Repeated fields may appear any number of times.
+    buf.extend_from_slice(&[0x10, 0x01]);
+    Basic::decode_with_config(&buf[..], reject).unwrap();
+
+    // Non-repeated fields may not.
+    buf.extend_from_slice(&[0x08, 0x02]);
+    assert_eq!(Basic::decode(&buf[..]).unwrap().int32, 2);
+    assert!(Basic::decode_with_config(&buf[..], reject).is_err());
+
+    // Nor may the members of a oneof, whether the same member appears twice or two of them.
+    let mut buf = Vec::new();
+    BasicOneof::Int(1).encode(&mut buf);
+    BasicOneof::Int(2).encode(&mut buf);
+    assert!(Basic::decode_with_config(&buf[..], reject).is_err());
+
+    let mut buf = Vec::new();
+    BasicOneof::String("one".to_owned()).encode(&mut buf);
+    BasicOneof::Int(2).encode(&mut buf);
+    assert_eq!(
+        Basic::decode(&buf[..]).unwrap().oneof,
+        Some(BasicOneof::Int(2))
+    );
+    let error = Basic::decode_with_config(&buf[..], reject).unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "failed to decode Protobuf message: duplicate member of a oneof: tag 8"
+    );
+    assert_eq!(error.kind(), DecodeErrorKind::DuplicateField { tag: 8 });
+
+    // Duplicates are rejected in nested messages.
+    let mut nested = Vec::new();
+    prost::encoding::int32::encode(1, &1, &mut nested);
+    prost::encoding::int32::encode(1, &2, &mut nested);
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(2, &nested, &mut buf);
+    assert_eq!(
+        Compound::decode(&buf[..]).unwrap().required_message.int32,
+        2
+    );
+    assert!(Compound::decode_with_config(&buf[..], reject).is_err());
+}
+
+#[test]
+fn reserved_wire_types() {
+    let skip = DecodeConfig::new().reserved_wire_types(ReservedWireTypePolicy::Skip);
+
+    // Field 1 with wire type 6, then field 1 (an `int32`) with value 5.
+    let buf = [0x0e, 0x08, 0x05];
+    let error = Basic::decode(&buf[..]).unwrap_err();
+    assert_eq!(
+        error.kind(),
+        DecodeErrorKind::ReservedWireType {
+            tag: 1,
+            wire_type: 6
+        }
+    );
+    assert_eq!(Basic::decode_with_config(&buf[..], skip).unwrap().int32, 5);
+
+    // Reserved wire types are skipped in nested messages too.
+    let mut buf = Vec::new();
+    prost::encoding::bytes::encode(2, &vec![0x0f, 0x08, 0x07], &mut buf);
+    assert!(Compound::decode(&buf[..]).is_err());
+    assert_eq!(
+        Compound::decode_with_config(&buf[..], skip)
+            .unwrap()
+            .required_message
+            .int32,
+        7
+    );
+
+    // Other errors are unaffected.
+    let error = Basic::decode_with_config(&[0x08][..], skip).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::Truncated);
+}
+
+#[test]
+fn decode_error_kinds() {
+    let kind = |buf: &[u8]| Basic::decode(buf).unwrap_err().kind();
+
+    // Field 1 (an `int32`) with a truncated varint, then with an 11 byte varint.
+    assert_eq!(kind(&[0x08, 0x80]), DecodeErrorKind::Truncated);
+    assert_eq!(
+        kind(&[0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        DecodeErrorKind::InvalidVarint
+    );
+    // Field 3 (a `string`) with a value longer than the message, and a value which isn't UTF-8.
+    assert_eq!(kind(&[0x1a, 0x02, b'a']), DecodeErrorKind::Truncated);
+    assert_eq!(kind(&[0x1a, 0x01, 0xff]), DecodeErrorKind::InvalidUtf8);
+    // Field 3 with a fixed 32-bit value.
+    assert_eq!(
+        kind(&[0x1d, 0x00, 0x00, 0x00, 0x00]),
+        DecodeErrorKind::InvalidWireType {
+            expected: WireType::LengthDelimited,
+            actual: WireType::ThirtyTwoBit,
+        }
+    );
+
+    let config = DecodeConfig::new().max_field_size(1);
+    let error = Basic::decode_with_config(&[0x1a, 0x02, b'a', b'b'][..], config).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::LengthOverflow);
+
+    // The required message of `Compound` is nested one level deep.
+    let config = DecodeConfig::new().recursion_limit(0);
+    let buf = Compound::default().encode_to_vec();
+    let error = Compound::decode_with_config(&buf[..], config).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::RecursionLimit);
+
+    // The kinds don't change the messages of the errors.
+    assert_eq!(
+        format!("{}", Basic::decode(&[0x1a, 0x02, b'a'][..]).unwrap_err()),
+        "failed to decode Protobuf message: Basic.string: buffer underflow"
+    );
+}
+
+/// A map stored as a vector of entries sorted by key, used through `prost::MapContainer`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedVecMap<K, V>(Vec<(K, V)>);
+
+impl<K, V> Default for SortedVecMap<K, V> {
+    fn default() -> Self {
+        SortedVecMap(Vec::new())
+    }
+}
+
+impl<K, V> MapContainer for SortedVecMap<K, V>
+where
+    K: Ord,
+{
+    type Key = K;
+    type Value = V;
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        match self.0.binary_search_by(|(k, _)| k.cmp(&key)) {
+            Ok(index) => self.0[index].1 = value,
+            Err(index) => self.0.insert(index, (key, value)),
+        }
+    }
+
+    fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&K, &V),
+    {
+        for (key, value) in &self.0 {
+            f(key, value);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CustomMaps {
+    #[prost(map_container = "string, string", tag = "1")]
+    pub strings: SortedVecMap<String, String>,
+
+    #[prost(map_container = "int32, enumeration(BasicEnumeration)", tag = "2")]
+    pub enumerations: SortedVecMap<i32, i32>,
+
+    #[prost(map_container = "sint32, message", tag = "3")]
+    pub messages: SortedVecMap<i32, BytesMsg>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BTreeMaps {
+    #[prost(btree_map = "string, string", tag = "1")]
+    pub strings: prost::alloc::collections::BTreeMap<String, String>,
+
+    #[prost(btree_map = "int32, enumeration(BasicEnumeration)", tag = "2")]
+    pub enumerations: prost::alloc::collections::BTreeMap<i32, i32>,
+
+    #[prost(btree_map = "sint32, message", tag = "3")]
+    pub messages: prost::alloc::collections::BTreeMap<i32, BytesMsg>,
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fingerprint() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    fn fingerprint(message: &Basic) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        message.fingerprint(&mut hasher);
+        hasher.finish()
+    }
+
+    // Hash maps with different random states iterate their entries in different orders.
+    let messages = (0..2)
+        .map(|_| Basic {
+            string_map: (0..20)
+                .map(|i| (format!("{}", i), "v".to_owned()))
+                .collect(),
+            ..Basic::default()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(fingerprint(&messages[0]), fingerprint(&messages[1]));
+
+    let mut buf = Vec::new();
+    messages[0].encode_deterministic(&mut buf).unwrap();
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&buf);
+    assert_eq!(fingerprint(&messages[0]), hasher.finish());
+
+    let mut other = messages[0].clone();
+    other.int32 = 1;
+    assert_ne!(fingerprint(&other), fingerprint(&messages[0]));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn encode_deterministic_nested() {
+    let basic = || Basic {
+        string_map: (0..20)
+            .map(|i| (format!("{}", i), "v".to_owned()))
+            .collect(),
+        ..Basic::default()
+    };
+    // The hash maps of each message are built with different random states.
+    let encodings = (0..2)
+        .map(|_| {
+            let compound = Compound {
+                optional_message: Some(basic()),
+                repeated_message: vec![basic()],
+                message_map: (0..20).map(|i| (i, basic())).collect(),
+                ..Compound::default()
+            };
+            let mut buf = Vec::new();
+            compound.encode_deterministic(&mut buf).unwrap();
+            assert_eq!(buf.len(), compound.encoded_len());
+            buf
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(encodings[0], encodings[1]);
+}
+
+#[test]
+fn check_custom_maps() {
+    let mut custom = CustomMaps::default();
+    let mut btree = BTreeMaps::default();
+    for &(key, value) in &[("b", "2"), ("a", "1"), ("", "empty key")] {
+        custom.strings.insert(key.to_owned(), value.to_owned());
+        btree.strings.insert(key.to_owned(), value.to_owned());
+    }
+    for &(key, value) in &[(3, BasicEnumeration::THREE), (0, BasicEnumeration::ZERO)] {
+        MapContainer::insert(&mut custom.enumerations, key, value as i32);
+        btree.enumerations.insert(key, value as i32);
+    }
+    let msg = BytesMsg {
+        plain: Bytes::from_static(b"plain"),
+        ..BytesMsg::default()
+    };
+    MapContainer::insert(&mut custom.messages, -1, msg.clone());
+    btree.messages.insert(-1, msg);
+
+    check_message(&custom);
+    check_serialize_equivalent(&custom, &btree);
+    assert_eq!(custom.encoded_len(), btree.encoded_len());
+    assert!(format!("{:?}", custom).contains("enumerations: {0: ZERO, 3: THREE}"));
+
+    custom.clear();
+    assert_eq!(custom, CustomMaps::default());
+}
+
+#[test]
+fn decode_into_reuses_repeated_messages() {
+    let basic = |string: &str| Basic {
+        string: string.to_owned(),
+        bools: vec![true],
+        ..Basic::default()
+    };
+
+    let mut msg = Compound {
+        optional_message: Some(basic("optional")),
+        repeated_message: vec![
+            basic("a long string which is reused"),
+            basic("b"),
+            basic("c"),
+        ],
+        ..Compound::default()
+    };
+    let elements = msg.repeated_message.as_ptr();
+    let string_capacity = msg.repeated_message[0].string.capacity();
+
+    let decoded = Compound {
+        repeated_message: vec![basic("x"), Basic::default()],
+        ..Compound::default()
+    };
+    let buf = decoded.encode_to_vec();
+    msg.decode_into(&buf[..]).unwrap();
+
+    assert_eq!(msg, decoded);
+    assert_eq!(msg.repeated_message.as_ptr(), elements);
+    assert_eq!(msg.repeated_message[0].string.capacity(), string_capacity);
+
+    // Elements are added when there are more than before.
+    let decoded = Compound {
+        repeated_message: vec![basic("1"), basic("2"), basic("3"), basic("4")],
+        ..Compound::default()
+    };
+    msg.decode_into(&decoded.encode_to_vec()[..]).unwrap();
+    assert_eq!(msg, decoded);
+
+    // Truncated input is rejected.
+    let buf = decoded.encode_to_vec();
+    assert!(msg.decode_into(&buf[..buf.len() - 1]).is_err());
+}
+
+#[test]
+fn decode_error_field_path() {
+    // A `Compound` whose third repeated message has a `string` which isn't UTF-8.
+    let buf = [
+        0x12, 0x00, 0x1a, 0x00, 0x1a, 0x00, 0x1a, 0x03, 0x1a, 0x01, 0xff,
+    ];
+    let expected = "failed to decode Protobuf message: \
+                    Compound.repeated_message[2].string: \
+                    invalid string value: data is not UTF-8 encoded";
+
+    let error = Compound::decode(&buf[..]).unwrap_err();
+    assert_eq!(
+        error.field_path().as_deref(),
+        Some("repeated_message[2].string")
+    );
+    assert_eq!(format!("{}", error), expected);
+
+    // Replacing the contents of a message reports the same path.
+    let mut msg = Compound {
+        repeated_message: vec![Basic::default(); 5],
+        ..Compound::default()
+    };
+    let error = msg.decode_into(&buf[..]).unwrap_err();
+    assert_eq!(format!("{}", error), expected);
+
+    let error = Compound::decode(&[0x12][..]).unwrap_err();
+    assert_eq!(error.field_path().as_deref(), Some("required_message"));
+    assert_eq!(
+        Compound::decode(&[0x80][..]).unwrap_err().field_path(),
+        None
+    );
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Contact {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(flatten, tags = "2, 3")]
+    pub address: PostalAddress,
+    #[prost(uint32, tag = "4")]
+    pub age: u32,
+}
+
+/// The fields of `Contact` with tags 2 and 3, gathered in their own message.
+#[derive(Clone, PartialEq, Message)]
+pub struct PostalAddress {
+    #[prost(string, tag = "2")]
+    pub street: String,
+    #[prost(string, repeated, tag = "3")]
+    pub lines: Vec<String>,
+}
+
+/// The wire format of `Contact`.
+#[derive(Clone, PartialEq, Message)]
+pub struct FlatContact {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub street: String,
+    #[prost(string, repeated, tag = "3")]
+    pub lines: Vec<String>,
+    #[prost(uint32, tag = "4")]
+    pub age: u32,
+}
+
+#[test]
+fn check_flatten() {
+    let contact = Contact {
+        name: "Ada".to_owned(),
+        address: PostalAddress {
+            street: "Main St".to_owned(),
+            lines: vec!["Flat 2".to_owned()],
+        },
+        age: 36,
+    };
+    let flat = FlatContact {
+        name: "Ada".to_owned(),
+        street: "Main St".to_owned(),
+        lines: vec!["Flat 2".to_owned()],
+        age: 36,
+    };
+
+    check_message(&contact);
+    assert_eq!(contact.encode_to_vec(), flat.encode_to_vec());
+    assert_eq!(contact.encoded_len(), flat.encoded_len());
+    assert_eq!(Contact::decode(&flat.encode_to_vec()[..]).unwrap(), contact);
+
+    let mut cleared = contact;
+    cleared.clear();
+    assert_eq!(cleared, Contact::default());
+}
+
+/// A write-only record, which is never decoded.
+#[derive(Clone, PartialEq, prost::EncodeMessage)]
+pub struct Event {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, optional, tag = "2")]
+    pub source: Option<EventSource>,
+    #[prost(uint64, repeated, tag = "3")]
+    pub counters: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::EncodeMessage)]
+pub struct EventSource {
+    #[prost(string, tag = "1")]
+    pub host: String,
+}
+
+/// A read-only view of `Event`, which is never encoded.
+#[derive(Clone, PartialEq, prost::DecodeMessage)]
+pub struct EventView {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(uint64, repeated, tag = "3")]
+    pub counters: Vec<u64>,
+}
+
+#[test]
+fn encode_and_decode_halves() {
+    fn encoded<M: EncodeMessage>(message: &M) -> Vec<u8> {
+        message.encode_to_vec()
+    }
+
+    let event = Event {
+        name: "started".to_owned(),
+        source: Some(EventSource {
+            host: "localhost".to_owned(),
+        }),
+        counters: vec![1, 2],
+    };
+    let buf = encoded(&event);
+    assert_eq!(buf.len(), event.encoded_len());
+
+    let view = EventView::decode(&buf[..]).unwrap();
+    assert_eq!(view.name, "started");
+    assert_eq!(view.counters, [1, 2]);
+    assert_eq!(
+        format!("{:?}", view),
+        r#"EventView { name: "started", counters: [1, 2] }"#
+    );
+}
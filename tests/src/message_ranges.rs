@@ -0,0 +1,16 @@
+mod message_ranges {
+    include!(concat!(env!("OUT_DIR"), "/message_ranges.rs"));
+}
+
+use self::message_ranges::{Ranged, Unranged};
+
+#[test]
+fn test_message_ranges() {
+    assert_eq!(Ranged::RESERVED_RANGES, &[2..3, 9..12]);
+    assert_eq!(Ranged::RESERVED_NAMES, &["foo", "bar"]);
+    assert_eq!(Ranged::EXTENSION_RANGES, &[100..200, 1000..536_870_912]);
+
+    assert!(Unranged::RESERVED_RANGES.is_empty());
+    assert!(Unranged::RESERVED_NAMES.is_empty());
+    assert!(Unranged::EXTENSION_RANGES.is_empty());
+}
@@ -18,7 +18,7 @@ pub mod widget {
 
 #[test]
 fn test() {
-    use prost::Message;
+    use prost::EncodeMessage;
 
     let mut widget_factory = widget::factory::WidgetFactory::default();
     assert_eq!(0, widget_factory.encoded_len());
@@ -0,0 +1,44 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{DecodeMessage, EncodeMessage, OpenEnum};
+
+mod open_enums {
+    include!(concat!(env!("OUT_DIR"), "/open_enums.rs"));
+}
+
+use self::open_enums::{Account, ClosedAccount, Status};
+
+#[test]
+fn test_open_enum_accessors() {
+    // A value added to `Status` by a newer schema.
+    let account = Account {
+        status: 3,
+        previous_status: None,
+        history: vec![1, 3],
+    };
+    assert_eq!(account.status(), OpenEnum::Unknown(3));
+    assert_eq!(account.previous_status(), OpenEnum::Known(Status::Unknown));
+    assert_eq!(
+        account.history().collect::<Vec<_>>(),
+        vec![OpenEnum::Known(Status::Active), OpenEnum::Unknown(3)]
+    );
+
+    let decoded = Account::decode(&*account.encode_to_vec()).unwrap();
+    assert_eq!(decoded, account);
+
+    let mut account = Account::default();
+    account.set_status(Status::Suspended);
+    account.set_previous_status(OpenEnum::Unknown(4));
+    account.push_history(Status::Active);
+    account.push_history(OpenEnum::Unknown(5));
+    assert_eq!(account.status, 2);
+    assert_eq!(account.previous_status, Some(4));
+    assert_eq!(account.history, vec![1, 5]);
+}
+
+#[test]
+fn test_closed_enum_accessors() {
+    let account = ClosedAccount { status: 3 };
+    assert_eq!(account.status(), Status::Unknown);
+}
@@ -24,7 +24,7 @@ impl DoIt for gizmo::Gizmo {
 
 #[test]
 fn test() {
-    use prost::Message;
+    use prost::EncodeMessage;
 
     let mut widget_factory = widget::factory::WidgetFactory::default();
     assert_eq!(0, widget_factory.encoded_len());
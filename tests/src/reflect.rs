@@ -0,0 +1,101 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::reflect::{FieldKind, FieldLabel, Value};
+use prost::ReflectMessage;
+
+mod reflect {
+    include!(concat!(env!("OUT_DIR"), "/reflect.rs"));
+}
+
+use self::reflect::{user, Address, User};
+
+/// Renders a value, as a generic text formatter would.
+fn render(value: &Value<'_>) -> String {
+    match *value {
+        Value::String(value) => format!("{:?}", value),
+        Value::U64(value) => value.to_string(),
+        Value::Enum(value) => value.to_string(),
+        Value::Bytes(value) => format!("{:?}", value),
+        Value::Message(message) => {
+            let fields = message
+                .fields()
+                .iter()
+                .filter_map(|field| {
+                    let value = message.get(field.name)?;
+                    Some(format!("{}: {}", field.name, render(&value)))
+                })
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Value::List(ref values) => {
+            let values = values.iter().map(render).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Map(ref entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", render(key), render(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::Oneof(tag, ref value) => format!("#{} {}", tag, render(value)),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_reflect_fields() {
+    let user = User::default();
+    assert_eq!(user.message_name(), "User");
+    let fields = user.fields();
+    let names = fields.iter().map(|field| field.name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["name", "id", "roles", "avatar", "address", "previous", "contact"]
+    );
+    assert_eq!(fields[1].kind, FieldKind::Uint64);
+    assert_eq!(fields[1].label, FieldLabel::Optional);
+    assert_eq!(fields[2].kind, FieldKind::Enum);
+    assert_eq!(fields[2].label, FieldLabel::Repeated);
+    assert_eq!(fields[5].kind, FieldKind::Map);
+    assert_eq!(fields[6].kind, FieldKind::Oneof);
+    assert_eq!(fields[6].tags, [7, 8]);
+
+    assert!(user.get("name").is_some());
+    assert!(user.get("id").is_none());
+    assert!(user.get("address").is_none());
+    assert!(user.get("contact").is_none());
+    assert!(user.get("missing").is_none());
+}
+
+#[test]
+fn test_reflect_values() {
+    let mut user = User {
+        name: "ada".to_string(),
+        id: Some(7),
+        roles: vec![1, 0],
+        avatar: vec![0xff],
+        address: Some(Address {
+            city: "London".to_string(),
+        }),
+        contact: Some(user::Contact::Mail(Address {
+            city: "Paris".to_string(),
+        })),
+        ..User::default()
+    };
+    user.previous.insert(
+        "2020".to_string(),
+        Address {
+            city: "Rome".to_string(),
+        },
+    );
+
+    assert_eq!(
+        render(&Value::Message(&user)),
+        "{name: \"ada\", id: 7, roles: [1, 0], avatar: [255], address: {city: \"London\"}, \
+         previous: {\"2020\": {city: \"Rome\"}}, contact: #8 {city: \"Paris\"}}"
+    );
+}
@@ -0,0 +1,123 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use prost::encoding::{group, message, string, uint32};
+use prost::{
+    DecodeConfig, DecodeErrorKind, DecodeMessage, DuplicateFieldPolicy, EncodeMessage, Message,
+    PartialDecoder, RequiredFieldPolicy,
+};
+
+mod required_fields {
+    include!(concat!(env!("OUT_DIR"), "/required_fields.rs"));
+}
+
+use self::required_fields::{person, Address, Person};
+
+fn config() -> DecodeConfig {
+    DecodeConfig::new().required_fields(RequiredFieldPolicy::Reject)
+}
+
+/// Encodes the `name` and `id` fields of a `Person`.
+fn name_and_id() -> Vec<u8> {
+    let mut buf = Vec::new();
+    string::encode(1, &"Ada".to_string(), &mut buf);
+    uint32::encode(2, &7, &mut buf);
+    buf
+}
+
+#[test]
+fn required_fields_present() {
+    let person = Person {
+        name: "Ada".to_string(),
+        id: 7,
+        address: Address {
+            city: "London".to_string(),
+            street: None,
+        },
+        previous: alloc::vec![Address::default()],
+        badge: Some(person::Badge { level: 3 }),
+    };
+    let buf = person.encode_to_vec();
+    assert_eq!(
+        Person::decode_with_config(&buf[..], config()).unwrap(),
+        person
+    );
+}
+
+#[test]
+fn missing_required_fields() {
+    let mut buf = Vec::new();
+    string::encode(1, &"Ada".to_string(), &mut buf);
+    assert!(Person::decode(&buf[..]).is_ok());
+    let error = Person::decode_with_config(&buf[..], config()).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::MissingRequiredFields);
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: missing required fields: id, address"
+    );
+
+    let mut decoder = PartialDecoder::<Person>::with_config(config());
+    decoder.push(&buf).unwrap();
+    assert!(decoder.finish().is_err());
+}
+
+#[test]
+fn missing_nested_required_fields() {
+    // An empty nested message.
+    let mut buf = name_and_id();
+    message::encode(3, &(), &mut buf);
+    assert!(Person::decode(&buf[..]).is_ok());
+    let error = Person::decode_with_config(&buf[..], config()).unwrap_err();
+    assert_eq!(error.kind(), DecodeErrorKind::MissingRequiredFields);
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Person.address: missing required fields: city"
+    );
+
+    // An empty element of a repeated message field.
+    let mut buf = name_and_id();
+    message::encode(3, &Address::default(), &mut buf);
+    assert!(Person::decode_with_config(&buf[..], config()).is_ok());
+    message::encode(4, &(), &mut buf);
+    assert!(Person::decode_with_config(&buf[..], config()).is_err());
+
+    // An empty group.
+    let mut buf = name_and_id();
+    message::encode(3, &Address::default(), &mut buf);
+    group::encode(5, &(), &mut buf);
+    let error = Person::decode_with_config(&buf[..], config()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Person.badge: missing required fields: level"
+    );
+}
+
+/// A message with the fields of an `Address` flattened into it.
+#[derive(Clone, PartialEq, Message)]
+struct Shipment {
+    #[prost(flatten, tags = "1, 2")]
+    address: Address,
+    #[prost(uint32, required, tag = "3")]
+    weight: u32,
+}
+
+#[test]
+fn flattened_required_fields() {
+    let mut buf = Vec::new();
+    uint32::encode(3, &5, &mut buf);
+    assert!(Shipment::decode(&buf[..]).is_ok());
+    let error = Shipment::decode_with_config(&buf[..], config()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: missing required fields: city"
+    );
+
+    string::encode(1, &"London".to_string(), &mut buf);
+    assert!(Shipment::decode_with_config(&buf[..], config()).is_ok());
+
+    // The non-repeated fields of the flattened message may only appear once as well.
+    string::encode(1, &"Paris".to_string(), &mut buf);
+    let reject = DecodeConfig::new().duplicate_fields(DuplicateFieldPolicy::Reject);
+    assert_eq!(Shipment::decode(&buf[..]).unwrap().address.city, "Paris");
+    assert!(Shipment::decode_with_config(&buf[..], reject).is_err());
+}
@@ -0,0 +1,11 @@
+mod schema_hash {
+    include!(concat!(env!("OUT_DIR"), "/schema_hash.rs"));
+}
+
+use self::schema_hash::SCHEMA_HASH_SCHEMA_HASH;
+
+#[test]
+fn test_schema_hash() {
+    assert_eq!(SCHEMA_HASH_SCHEMA_HASH.len(), 32);
+    assert_ne!(SCHEMA_HASH_SCHEMA_HASH, [0; 32]);
+}